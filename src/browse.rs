@@ -0,0 +1,94 @@
+/*
+    browse.rs  WJ126
+
+    * directory listing for the file browser
+*/
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub size: u64,
+    pub kind: &'static str,
+}
+
+fn file_kind(file_type: &fs::FileType) -> &'static str {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_block_device() {
+            return "block device";
+        }
+        if file_type.is_char_device() {
+            return "char device";
+        }
+        if file_type.is_fifo() {
+            return "fifo";
+        }
+        if file_type.is_socket() {
+            return "socket";
+        }
+    }
+    if file_type.is_dir() {
+        "dir"
+    } else if file_type.is_file() {
+        "file"
+    } else {
+        "other"
+    }
+}
+
+// directories first, then by name
+// the parent directory comes first of all, as ".."
+pub fn list(dir: &Path, show_hidden: bool) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for dirent in fs::read_dir(dir)? {
+        let dirent = dirent?;
+        let name = dirent.file_name().to_string_lossy().into_owned();
+        if !show_hidden && name.starts_with('.') {
+            continue;
+        }
+        let path = dirent.path();
+        // follow symlinks, but still list dangling ones
+        let (kind, is_dir, size) = match fs::metadata(&path) {
+            Ok(metadata) => {
+                let kind = if dirent.file_type().is_ok_and(|t| t.is_symlink()) {
+                    "link"
+                } else {
+                    file_kind(&metadata.file_type())
+                };
+                (kind, metadata.is_dir(), metadata.len())
+            }
+            Err(_) => ("broken link", false, 0),
+        };
+        entries.push(Entry {
+            name,
+            path,
+            is_dir,
+            size,
+            kind,
+        });
+    }
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    if let Some(parent) = dir.parent() {
+        entries.insert(
+            0,
+            Entry {
+                name: "..".to_owned(),
+                path: parent.to_path_buf(),
+                is_dir: true,
+                size: 0,
+                kind: "dir",
+            },
+        );
+    }
+    Ok(entries)
+}
+
+// EOB
@@ -0,0 +1,198 @@
+/*
+    clipboard.rs  WJ126
+
+    * copy to the system clipboard via the terminal (OSC 52)
+    * paste from it via the usual command line tools
+*/
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+// tried in order; the first one that runs wins
+const PASTE_COMMANDS: [&[&str]; 4] = [
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-o", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--output"],
+    &["pbpaste"],
+];
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64URL_CHARS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut encoder = Base64::new(false);
+    encoder.push(data);
+    encoder.finish()
+}
+
+// base64 encoding of data that comes in pieces
+// URL-safe base64 uses '-' and '_', and has no padding
+pub struct Base64 {
+    chars: &'static [u8; 64],
+    pad: bool,
+    rest: Vec<u8>,
+    out: String,
+}
+
+impl Base64 {
+    pub fn new(url_safe: bool) -> Self {
+        Base64 {
+            chars: if url_safe {
+                BASE64URL_CHARS
+            } else {
+                BASE64_CHARS
+            },
+            pad: !url_safe,
+            rest: Vec::with_capacity(3),
+            out: String::new(),
+        }
+    }
+
+    pub fn push(&mut self, mut data: &[u8]) {
+        if !self.rest.is_empty() {
+            let n = (3 - self.rest.len()).min(data.len());
+            self.rest.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.rest.len() < 3 {
+                return;
+            }
+            let rest = std::mem::take(&mut self.rest);
+            self.encode(&rest);
+        }
+        let mut chunks = data.chunks_exact(3);
+        for chunk in &mut chunks {
+            self.encode(chunk);
+        }
+        self.rest.extend_from_slice(chunks.remainder());
+    }
+
+    pub fn finish(mut self) -> String {
+        let rest = std::mem::take(&mut self.rest);
+        if !rest.is_empty() {
+            self.encode(&rest);
+        }
+        self.out
+    }
+
+    // encode 1 to 3 bytes
+    fn encode(&mut self, chunk: &[u8]) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        self.out.push(self.chars[(n >> 18) as usize & 63] as char);
+        self.out.push(self.chars[(n >> 12) as usize & 63] as char);
+        if chunk.len() > 1 {
+            self.out.push(self.chars[(n >> 6) as usize & 63] as char);
+        } else if self.pad {
+            self.out.push('=');
+        }
+        if chunk.len() > 2 {
+            self.out.push(self.chars[n as usize & 63] as char);
+        } else if self.pad {
+            self.out.push('=');
+        }
+    }
+}
+
+// the terminal emulator puts the data on the clipboard
+// this also works over ssh, but not every terminal supports it
+pub fn copy<W: Write>(out: &mut W, data: &[u8]) -> std::io::Result<()> {
+    write!(out, "\x1b]52;c;{}\x07", base64_encode(data))?;
+    out.flush()
+}
+
+// read the clipboard contents
+pub fn paste() -> io::Result<Vec<u8>> {
+    for argv in PASTE_COMMANDS {
+        let output = match Command::new(argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if !output.status.success() {
+            return Err(io::Error::other(format!("{} failed", argv[0])));
+        }
+        return Ok(output.stdout);
+    }
+    Err(io::Error::other(
+        "no clipboard tool found (wl-paste, xclip, xsel or pbpaste)",
+    ))
+}
+
+// bytes as "DE AD BE EF"
+pub fn format_hex(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 3);
+    for (i, byte) in data.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        write!(out, "{:02X}", byte).unwrap();
+    }
+    out
+}
+
+// bytes as a C array: const uint8_t data[N] = { ... };
+pub fn format_c_array(data: &[u8]) -> String {
+    let mut out = format!("const uint8_t data[{}] = {{\n", data.len());
+    format_array_items(&mut out, data);
+    out.push_str("};\n");
+    out
+}
+
+// bytes as a Rust array: const DATA: [u8; N] = [ ... ];
+pub fn format_rust_array(data: &[u8]) -> String {
+    let mut out = format!("const DATA: [u8; {}] = [\n", data.len());
+    format_array_items(&mut out, data);
+    out.push_str("];\n");
+    out
+}
+
+// 0xDE, 0xAD, ... with 12 to a line
+fn format_array_items(out: &mut String, data: &[u8]) {
+    for line in data.chunks(12) {
+        out.push_str("   ");
+        for byte in line {
+            write!(out, " 0x{:02X},", byte).unwrap();
+        }
+        out.push('\n');
+    }
+}
+
+// bytes written as hex, like "4a 4f 59 21", "0x4a, 0x4f" or "4a4f5921"
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for word in text.split(|c: char| c.is_whitespace() || c == ',') {
+        if word.is_empty() {
+            continue;
+        }
+        let digits = word
+            .strip_prefix("0x")
+            .or_else(|| word.strip_prefix("0X"))
+            .unwrap_or(word);
+        if digits.is_empty()
+            || digits.len() % 2 != 0
+            || !digits.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(format!("not hex bytes: '{}'", word));
+        }
+        for i in (0..digits.len()).step_by(2) {
+            bytes.push(u8::from_str_radix(&digits[i..i + 2], 16).unwrap());
+        }
+    }
+    if bytes.is_empty() {
+        return Err("nothing to paste".to_owned());
+    }
+    Ok(bytes)
+}
+
+// EOB
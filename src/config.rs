@@ -0,0 +1,200 @@
+/*
+    config.rs  WJ126
+
+    * ~/.config/rhex/config.toml (a small subset of TOML)
+*/
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(u64),
+    Bool(bool),
+}
+
+// a [table.path] with its key = value entries
+#[derive(Debug, Default)]
+pub struct Table {
+    pub path: Vec<String>,
+    pub entries: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    pub tables: Vec<Table>,
+}
+
+impl Table {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut config = Config {
+            tables: vec![Table::default()],
+        };
+
+        for (lineno, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: &str| format!("line {}: {}", lineno + 1, msg);
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.strip_suffix(']').ok_or_else(|| err("missing ']'"))?;
+                let path = parse_key_path(header).ok_or_else(|| err("invalid table name"))?;
+                config.tables.push(Table {
+                    path,
+                    entries: Vec::new(),
+                });
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| err("expected key = value"))?;
+            let key = parse_key_path(key.trim())
+                .filter(|path| path.len() == 1)
+                .ok_or_else(|| err("invalid key"))?
+                .remove(0);
+            let value = parse_value(value.trim()).ok_or_else(|| err("invalid value"))?;
+            config.tables.last_mut().unwrap().entries.push((key, value));
+        }
+        Ok(config)
+    }
+
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables
+            .iter()
+            .find(|table| table.path.len() == 1 && table.path[0] == name)
+    }
+
+    // all tables named [prefix.*]
+    pub fn tables_under<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a Table> {
+        self.tables
+            .iter()
+            .filter(move |table| table.path.len() == 2 && table.path[0] == prefix)
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("rhex"))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+// a missing config file is not an error
+pub fn load() -> Result<Config, String> {
+    let path = match config_path() {
+        Some(path) => path,
+        None => return Ok(Config::default()),
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => Config::parse(&text).map_err(|err| format!("{}: {}", path.display(), err)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(format!("{}: {}", path.display(), err)),
+    }
+}
+
+// add text at the end of the config file, creating it if needed
+pub fn append(text: &str) -> Result<PathBuf, String> {
+    let path = config_path().ok_or("can not find home directory")?;
+    let dir = path.parent().unwrap();
+    fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| f.write_all(text.as_bytes()))
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(path)
+}
+
+// search history, one entry per line, oldest first
+pub fn history_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("history"))
+}
+
+pub fn load_history() -> Vec<String> {
+    history_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|text| text.lines().map(str::to_owned).collect())
+        .unwrap_or_default()
+}
+
+pub fn save_history(entries: &[String]) -> Result<(), String> {
+    let path = history_path().ok_or("can not find home directory")?;
+    let dir = path.parent().unwrap();
+    fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+    let mut text = entries.join("\n");
+    text.push('\n');
+    fs::write(&path, text).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+// key or dotted table name, possibly with "quoted" parts
+fn parse_key_path(s: &str) -> Option<Vec<String>> {
+    let mut path = Vec::new();
+    let mut rest = s.trim();
+    loop {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"')?;
+            path.push(quoted[..end].to_owned());
+            rest = quoted[end + 1..].trim_start();
+        } else {
+            let end = rest.find('.').unwrap_or(rest.len());
+            let part = rest[..end].trim();
+            if part.is_empty()
+                || !part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return None;
+            }
+            path.push(part.to_owned());
+            rest = rest[end..].trim_start();
+        }
+        if rest.is_empty() {
+            return Some(path);
+        }
+        rest = rest.strip_prefix('.')?.trim_start();
+    }
+}
+
+fn parse_value(s: &str) -> Option<Value> {
+    match s {
+        "true" => return Some(Value::Bool(true)),
+        "false" => return Some(Value::Bool(false)),
+        _ => {}
+    }
+    if let Some(quoted) = s.strip_prefix('"') {
+        return Some(Value::Str(quoted.strip_suffix('"')?.to_owned()));
+    }
+    crate::parse_number(&s.replace('_', "")).map(Value::Int)
+}
+
+// remove a trailing comment, but not a '#' inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// EOB
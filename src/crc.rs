@@ -0,0 +1,290 @@
+/*
+    crc.rs  WJ126
+
+    * parameterized CRC calculation ("Rocksoft" model)
+*/
+
+use crate::config::{Table, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrcParams {
+    pub width: u32,
+    pub poly: u64,
+    pub init: u64,
+    pub xorout: u64,
+    pub refin: bool,
+    pub refout: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Preset {
+    pub name: String,
+    pub params: CrcParams,
+}
+
+const fn preset(
+    name: &'static str,
+    width: u32,
+    poly: u64,
+    init: u64,
+    xorout: u64,
+    reflect: bool,
+) -> (&'static str, CrcParams) {
+    (
+        name,
+        CrcParams {
+            width,
+            poly,
+            init,
+            xorout,
+            refin: reflect,
+            refout: reflect,
+        },
+    )
+}
+
+// common presets, see the CRC catalogue by Greg Cook
+pub const CATALOGUE: &[(&str, CrcParams)] = &[
+    preset("CRC-8/SMBUS", 8, 0x07, 0x00, 0x00, false),
+    preset("CRC-8/MAXIM-DOW", 8, 0x31, 0x00, 0x00, true),
+    preset("CRC-8/SAE-J1850", 8, 0x1d, 0xff, 0xff, false),
+    preset("CRC-8/AUTOSAR", 8, 0x2f, 0xff, 0xff, false),
+    preset("CRC-8/CDMA2000", 8, 0x9b, 0xff, 0x00, false),
+    preset("CRC-8/ROHC", 8, 0x07, 0xff, 0x00, true),
+    preset("CRC-8/I-432-1", 8, 0x07, 0x00, 0x55, false),
+    preset("CRC-16/ARC", 16, 0x8005, 0x0000, 0x0000, true),
+    preset("CRC-16/MODBUS", 16, 0x8005, 0xffff, 0x0000, true),
+    preset("CRC-16/USB", 16, 0x8005, 0xffff, 0xffff, true),
+    preset("CRC-16/MAXIM-DOW", 16, 0x8005, 0x0000, 0xffff, true),
+    preset("CRC-16/UMTS", 16, 0x8005, 0x0000, 0x0000, false),
+    preset("CRC-16/IBM-3740", 16, 0x1021, 0xffff, 0x0000, false),
+    preset("CRC-16/XMODEM", 16, 0x1021, 0x0000, 0x0000, false),
+    preset("CRC-16/KERMIT", 16, 0x1021, 0x0000, 0x0000, true),
+    preset("CRC-16/IBM-SDLC", 16, 0x1021, 0xffff, 0xffff, true),
+    preset("CRC-16/GENIBUS", 16, 0x1021, 0xffff, 0xffff, false),
+    preset("CRC-16/MCRF4XX", 16, 0x1021, 0xffff, 0x0000, true),
+    preset("CRC-16/DNP", 16, 0x3d65, 0x0000, 0xffff, true),
+    preset(
+        "CRC-32/ISO-HDLC",
+        32,
+        0x04c11db7,
+        0xffffffff,
+        0xffffffff,
+        true,
+    ),
+    preset(
+        "CRC-32/BZIP2",
+        32,
+        0x04c11db7,
+        0xffffffff,
+        0xffffffff,
+        false,
+    ),
+    preset(
+        "CRC-32/MPEG-2",
+        32,
+        0x04c11db7,
+        0xffffffff,
+        0x00000000,
+        false,
+    ),
+    preset(
+        "CRC-32/CKSUM",
+        32,
+        0x04c11db7,
+        0x00000000,
+        0xffffffff,
+        false,
+    ),
+    preset(
+        "CRC-32/JAMCRC",
+        32,
+        0x04c11db7,
+        0xffffffff,
+        0x00000000,
+        true,
+    ),
+    preset("CRC-32/ISCSI", 32, 0x1edc6f41, 0xffffffff, 0xffffffff, true),
+    preset("CRC-32/XFER", 32, 0x000000af, 0x00000000, 0x00000000, false),
+    preset(
+        "CRC-64/XZ",
+        64,
+        0x42f0e1eba9ea3693,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        true,
+    ),
+];
+
+impl CrcParams {
+    // parse "width=16 poly=0x8005 init=0xffff xorout=0 refin refout"
+    pub fn parse(args: &str) -> Result<CrcParams, String> {
+        let mut params = CrcParams {
+            width: 32,
+            poly: 0,
+            init: 0,
+            xorout: 0,
+            refin: false,
+            refout: false,
+        };
+        let mut have_poly = false;
+
+        for word in args.split_whitespace() {
+            let (key, value) = match word.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (word, None),
+            };
+            let number = || {
+                value
+                    .and_then(crate::parse_number)
+                    .ok_or_else(|| format!("invalid value: {}", word))
+            };
+            match key {
+                "width" => params.width = number()? as u32,
+                "poly" => {
+                    params.poly = number()?;
+                    have_poly = true;
+                }
+                "init" => params.init = number()?,
+                "xorout" => params.xorout = number()?,
+                "refin" => params.refin = true,
+                "refout" => params.refout = true,
+                "reflect" => {
+                    params.refin = true;
+                    params.refout = true;
+                }
+                _ => return Err(format!("unknown CRC parameter: {}", key)),
+            }
+        }
+        if !have_poly {
+            return Err("missing CRC parameter: poly".to_owned());
+        }
+        params.check()?;
+        Ok(params)
+    }
+
+    pub fn from_table(table: &Table) -> Result<CrcParams, String> {
+        let number = |key: &str, default: Option<u64>| match table.get(key) {
+            Some(Value::Int(n)) => Ok(*n),
+            None => default.ok_or_else(|| format!("missing {}", key)),
+            _ => Err(format!("{} must be a number", key)),
+        };
+        let boolean = |key: &str| match table.get(key) {
+            Some(Value::Bool(b)) => Ok(*b),
+            None => Ok(false),
+            _ => Err(format!("{} must be true or false", key)),
+        };
+        let params = CrcParams {
+            width: number("width", Some(32))? as u32,
+            poly: number("poly", None)?,
+            init: number("init", Some(0))?,
+            xorout: number("xorout", Some(0))?,
+            refin: boolean("refin")?,
+            refout: boolean("refout")?,
+        };
+        params.check()?;
+        Ok(params)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if ![8, 16, 32, 64].contains(&self.width) {
+            return Err(format!("unsupported CRC width: {}", self.width));
+        }
+        let mask = self.mask();
+        if self.poly & !mask != 0 || self.init & !mask != 0 || self.xorout & !mask != 0 {
+            return Err(format!("CRC parameter too large for width {}", self.width));
+        }
+        Ok(())
+    }
+
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.width)
+    }
+
+    pub fn digits(&self) -> usize {
+        self.width as usize / 4
+    }
+
+    // config file representation
+    pub fn to_toml(&self, name: &str) -> String {
+        let digits = self.digits();
+        format!(
+            "\n[crc.\"{}\"]\nwidth = {}\npoly = 0x{:0digits$x}\ninit = 0x{:0digits$x}\nxorout = 0x{:0digits$x}\nrefin = {}\nrefout = {}\n",
+            name, self.width, self.poly, self.init, self.xorout, self.refin, self.refout
+        )
+    }
+}
+
+impl std::fmt::Display for CrcParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let digits = self.digits();
+        write!(
+            f,
+            "width={} poly=0x{:0digits$x} init=0x{:0digits$x} xorout=0x{:0digits$x}",
+            self.width, self.poly, self.init, self.xorout
+        )?;
+        if self.refin {
+            write!(f, " refin")?;
+        }
+        if self.refout {
+            write!(f, " refout")?;
+        }
+        Ok(())
+    }
+}
+
+// table driven CRC, fed in chunks
+pub struct Crc {
+    params: CrcParams,
+    table: [u64; 256],
+    value: u64,
+}
+
+impl Crc {
+    pub fn new(params: &CrcParams) -> Crc {
+        let width = params.width;
+        let mask = params.mask();
+        let top = 1u64 << (width - 1);
+        let mut table = [0u64; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut value = (i as u64) << (width - 8);
+            for _ in 0..8 {
+                value = if value & top != 0 {
+                    (value << 1) ^ params.poly
+                } else {
+                    value << 1
+                };
+            }
+            *entry = value & mask;
+        }
+        Crc {
+            params: params.clone(),
+            table,
+            value: params.init,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let shift = self.params.width - 8;
+        let mask = self.params.mask();
+        for &byte in data {
+            let byte = if self.params.refin {
+                byte.reverse_bits()
+            } else {
+                byte
+            };
+            let idx = ((self.value >> shift) as u8 ^ byte) as usize;
+            self.value = ((self.value << 8) & mask) ^ self.table[idx];
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        let mut value = self.value;
+        if self.params.refout {
+            value = value.reverse_bits() >> (64 - self.params.width);
+        }
+        (value ^ self.params.xorout) & self.params.mask()
+    }
+}
+
+// EOB
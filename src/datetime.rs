@@ -0,0 +1,298 @@
+/*
+    datetime.rs  WJ126
+
+    * date and time formatting without external crates
+*/
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// days since 1970-01-01 to (year, month, day)
+// see Howard Hinnant's "civil_from_days" algorithm
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// timezone in which timestamps are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeZone {
+    Local,
+    Utc,
+    // offset east of UTC, in seconds
+    Fixed(i32),
+}
+
+impl TimeZone {
+    // "local", "UTC", or a fixed offset like "+02:00", "-0530" or "UTC+2"
+    pub fn parse(s: &str) -> Option<TimeZone> {
+        if s.eq_ignore_ascii_case("local") {
+            return Some(TimeZone::Local);
+        }
+        let upper = s.to_ascii_uppercase();
+        let offset = upper
+            .strip_prefix("UTC")
+            .or_else(|| upper.strip_prefix("GMT"))
+            .unwrap_or(&upper);
+        if offset.is_empty() {
+            return Some(TimeZone::Utc);
+        }
+        let (sign, digits) = match offset.split_at(1) {
+            ("+", digits) => (1, digits),
+            ("-", digits) => (-1, digits),
+            _ => return None,
+        };
+        let (hours, minutes) = match digits.split_once(':') {
+            Some((hours, minutes)) => (hours, minutes),
+            None if digits.len() > 2 => digits.split_at(digits.len() - 2),
+            None => (digits, "0"),
+        };
+        let hours: i32 = hours.parse().ok()?;
+        let minutes: i32 = minutes.parse().ok()?;
+        if hours > 14 || minutes > 59 {
+            return None;
+        }
+        Some(TimeZone::Fixed(sign * (hours * 3600 + minutes * 60)))
+    }
+
+    // offset from UTC at the given time, which for the local zone
+    // depends on daylight saving time
+    pub fn offset_at(&self, secs: i64) -> i64 {
+        match *self {
+            TimeZone::Local => local_offset(secs).unwrap_or(0),
+            TimeZone::Utc => 0,
+            TimeZone::Fixed(offset) => offset as i64,
+        }
+    }
+}
+
+impl std::fmt::Display for TimeZone {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            TimeZone::Local => write!(f, "local"),
+            TimeZone::Utc => write!(f, "UTC"),
+            TimeZone::Fixed(offset) => write!(f, "UTC{}", format_offset(offset as i64)),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn local_offset(secs: i64) -> Option<i64> {
+    // time_t is 32 bits on some platforms
+    #[allow(clippy::useless_conversion)]
+    let time: libc::time_t = secs.try_into().ok()?;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // localtime_r() reads $TZ and /etc/localtime
+    let result = unsafe { libc::localtime_r(&time, &mut tm) };
+    if result.is_null() {
+        None
+    } else {
+        Some(tm.tm_gmtoff as i64)
+    }
+}
+
+#[cfg(not(unix))]
+fn local_offset(_secs: i64) -> Option<i64> {
+    None
+}
+
+// offset in seconds as "+HH:MM"
+fn format_offset(offset: i64) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    format!("{}{:02}:{:02}", sign, offset / 3600, offset % 3600 / 60)
+}
+
+// seconds since the epoch as "YYYY-MM-DD HH:MM:SS", without zone
+// Returns None for years that do not fit in four digits
+pub fn format_civil(secs: i64) -> Option<String> {
+    let days = secs.div_euclid(86400);
+    let rem = secs.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    ))
+}
+
+// seconds since the epoch as ISO 8601 in UTC, like "2024-01-31T12:00:00Z"
+// Returns None for dates outside 1970 to 2200, as a value that is
+// not meant as a timestamp gives a year like 48512
+pub fn format_iso_likely(secs: i64) -> Option<String> {
+    let (year, _, _) = civil_from_days(secs.div_euclid(86400));
+    if !(1970..=2200).contains(&year) {
+        return None;
+    }
+    format_civil(secs).map(|civil| format!("{}Z", civil.replacen(' ', "T", 1)))
+}
+
+// seconds since the epoch in the given timezone, with the zone shown
+// like "UTC", "UTC+05:30", or "+02:00 local"
+pub fn format_time(secs: i64, tz: TimeZone) -> String {
+    let offset = tz.offset_at(secs);
+    let civil = match secs.checked_add(offset).and_then(format_civil) {
+        Some(civil) => civil,
+        None => return "out of range".to_owned(),
+    };
+    match tz {
+        TimeZone::Local => format!("{} {} local", civil, format_offset(offset)),
+        _ => format!("{} {}", civil, tz),
+    }
+}
+
+pub fn format_system_time(time: SystemTime, tz: TimeZone) -> String {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => format_time(duration.as_secs() as i64, tz),
+        Err(err) => format_time(-(err.duration().as_secs() as i64), tz),
+    }
+}
+
+// seconds between the epochs of other timestamp formats and 1970-01-01
+pub const FILETIME_EPOCH: i64 = 11644473600; // 1601-01-01
+pub const HFS_EPOCH: i64 = 2082844800; // 1904-01-01
+
+// MS-DOS date and time, as in FAT and ZIP: the date in the upper
+// 16 bits, the time in the lower. It carries no timezone
+pub fn format_dos(value: u32) -> Option<String> {
+    let date = value >> 16;
+    let time = value & 0xffff;
+    let (year, month, day) = (1980 + (date >> 9), (date >> 5) & 0xf, date & 0x1f);
+    let (hour, minute, second) = (time >> 11, (time >> 5) & 0x3f, (time & 0x1f) * 2);
+    if !(1..=12).contains(&month) || day == 0 || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} (no zone)",
+        year, month, day, hour, minute, second
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parse_zone() {
+        assert_eq!(TimeZone::parse("local"), Some(TimeZone::Local));
+        assert_eq!(TimeZone::parse("utc"), Some(TimeZone::Utc));
+        assert_eq!(TimeZone::parse("GMT"), Some(TimeZone::Utc));
+        assert_eq!(TimeZone::parse("+02:00"), Some(TimeZone::Fixed(7200)));
+        assert_eq!(TimeZone::parse("-0530"), Some(TimeZone::Fixed(-19800)));
+        assert_eq!(TimeZone::parse("UTC+2"), Some(TimeZone::Fixed(7200)));
+        assert_eq!(TimeZone::parse("+15"), None);
+        assert_eq!(TimeZone::parse("+01:60"), None);
+        assert_eq!(TimeZone::parse("CET"), None);
+        assert_eq!(TimeZone::Fixed(-19800).to_string(), "UTC-05:30");
+    }
+
+    #[test]
+    fn epoch() {
+        assert_eq!(format_time(0, TimeZone::Utc), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_time(-1, TimeZone::Utc), "1969-12-31 23:59:59 UTC");
+        assert_eq!(
+            format_time(0, TimeZone::Fixed(-3600)),
+            "1969-12-31 23:00:00 UTC-01:00"
+        );
+        assert_eq!(
+            format_system_time(UNIX_EPOCH - Duration::from_secs(1), TimeZone::Utc),
+            "1969-12-31 23:59:59 UTC"
+        );
+        assert_eq!(format_iso_likely(0).unwrap(), "1970-01-01T00:00:00Z");
+        assert_eq!(format_iso_likely(-1), None);
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        // leap day
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn range() {
+        assert_eq!(format_civil(253402300799).unwrap(), "9999-12-31 23:59:59");
+        assert_eq!(format_civil(253402300800), None);
+        assert_eq!(format_civil(-62167219200).unwrap(), "0000-01-01 00:00:00");
+        assert_eq!(format_civil(-62167219201), None);
+        assert_eq!(format_time(i64::MAX, TimeZone::Fixed(3600)), "out of range");
+        assert_eq!(
+            format_iso_likely(7258118399).unwrap(),
+            "2199-12-31T23:59:59Z"
+        );
+        assert_eq!(format_iso_likely(7289654400), None);
+    }
+
+    #[test]
+    fn other_epochs() {
+        assert_eq!(
+            format_civil(-FILETIME_EPOCH).unwrap(),
+            "1601-01-01 00:00:00"
+        );
+        assert_eq!(format_civil(-HFS_EPOCH).unwrap(), "1904-01-01 00:00:00");
+        assert_eq!(
+            format_dos(0x00210000).unwrap(),
+            "1980-01-01 00:00:00 (no zone)"
+        );
+        assert_eq!(format_dos(0), None);
+    }
+
+    // the only test that touches $TZ
+    #[cfg(unix)]
+    #[test]
+    fn daylight_saving() {
+        extern "C" {
+            fn tzset();
+        }
+        // Central European Time, no tzdata needed
+        std::env::set_var("TZ", "CET-1CEST,M3.5.0,M10.5.0/3");
+        unsafe { tzset() };
+
+        // 2024-03-31 01:00 UTC, clocks go from 02:00 to 03:00
+        let spring = 1711846800;
+        assert_eq!(TimeZone::Local.offset_at(spring - 1), 3600);
+        assert_eq!(TimeZone::Local.offset_at(spring), 7200);
+        assert_eq!(
+            format_time(spring - 1, TimeZone::Local),
+            "2024-03-31 01:59:59 +01:00 local"
+        );
+        assert_eq!(
+            format_time(spring, TimeZone::Local),
+            "2024-03-31 03:00:00 +02:00 local"
+        );
+
+        // 2024-10-27 01:00 UTC, clocks go from 03:00 back to 02:00
+        let autumn = 1729990800;
+        assert_eq!(TimeZone::Local.offset_at(autumn - 1), 7200);
+        assert_eq!(TimeZone::Local.offset_at(autumn), 3600);
+        assert_eq!(
+            format_time(autumn - 1, TimeZone::Local),
+            "2024-10-27 02:59:59 +02:00 local"
+        );
+        assert_eq!(
+            format_time(autumn, TimeZone::Local),
+            "2024-10-27 02:00:00 +01:00 local"
+        );
+
+        // a fixed zone does not observe daylight saving time
+        assert_eq!(TimeZone::Fixed(3600).offset_at(spring), 3600);
+        assert_eq!(
+            format_time(0, TimeZone::Local),
+            "1970-01-01 01:00:00 +01:00 local"
+        );
+    }
+}
+
+// EOB
@@ -0,0 +1,593 @@
+/*
+    edits.rs  WJ126
+
+    * pending (unsaved) modifications, laid over the file data
+    * a piece table: the data is a list of pieces, that come either from
+      the file or from a buffer of added bytes, so bytes can be inserted
+      and deleted as well as overwritten
+*/
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+const COPY_CHUNKSIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Source {
+    File,
+    Added,
+}
+
+// a run of bytes, at start in the file or in the added buffer
+#[derive(Debug, Clone, Copy)]
+pub struct Piece {
+    source: Source,
+    start: u64,
+    len: u64,
+}
+
+// where a byte of the data comes from
+pub enum Origin {
+    File(u64),
+    Added(u8),
+}
+
+// an edit, as recorded for undo and redo:
+// at pos, the removed pieces were replaced by the inserted ones
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub pos: u64,
+    removed: Vec<Piece>,
+    inserted: Vec<Piece>,
+}
+
+impl Change {
+    // end of the changed bytes
+    pub fn end(&self) -> u64 {
+        self.pos + total_len(&self.removed).max(total_len(&self.inserted))
+    }
+
+    // the change made the data longer or shorter
+    pub fn resizes(&self) -> bool {
+        total_len(&self.removed) != total_len(&self.inserted)
+    }
+}
+
+fn total_len(pieces: &[Piece]) -> u64 {
+    pieces.iter().map(|piece| piece.len).sum()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Edits {
+    file_len: u64,
+    pieces: Vec<Piece>,
+    // data position of each piece
+    starts: Vec<u64>,
+    // never changes, only grows, so that undo can refer to it
+    added: Vec<u8>,
+    // for each added byte, the offset of the file byte it overwrote
+    bases: Vec<Option<u64>>,
+    // goes up with every change, so that what was read can be cached
+    version: u64,
+}
+
+impl Edits {
+    pub fn new(file_len: u64) -> Self {
+        let mut edits = Edits {
+            file_len,
+            ..Default::default()
+        };
+        edits.reset();
+        edits
+    }
+
+    // the data is the file, without changes
+    fn reset(&mut self) {
+        self.pieces.clear();
+        if self.file_len > 0 {
+            self.pieces.push(Piece {
+                source: Source::File,
+                start: 0,
+                len: self.file_len,
+            });
+        }
+        self.renumber();
+    }
+
+    fn renumber(&mut self) {
+        self.version += 1;
+        self.starts.clear();
+        let mut pos = 0;
+        for piece in &self.pieces {
+            self.starts.push(pos);
+            pos += piece.len;
+        }
+    }
+
+    // join neighbouring pieces that continue each other
+    fn merge(&mut self) {
+        let mut merged: Vec<Piece> = Vec::with_capacity(self.pieces.len());
+        for piece in self.pieces.drain(..) {
+            if piece.len == 0 {
+                continue;
+            }
+            match merged.last_mut() {
+                Some(last)
+                    if last.source == piece.source && last.start + last.len == piece.start =>
+                {
+                    last.len += piece.len
+                }
+                _ => merged.push(piece),
+            }
+        }
+        self.pieces = merged;
+        self.renumber();
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn file_len(&self) -> u64 {
+        self.file_len
+    }
+
+    // length of the data, with the changes
+    pub fn data_len(&self) -> u64 {
+        match (self.pieces.last(), self.starts.last()) {
+            (Some(piece), Some(&start)) => start + piece.len,
+            _ => 0,
+        }
+    }
+
+    // there are no changes
+    pub fn is_empty(&self) -> bool {
+        self.pieces.iter().all(|piece| {
+            piece.source == Source::File && piece.start == 0 && piece.len == self.file_len
+        })
+    }
+
+    // number of bytes that were typed in
+    pub fn modified_len(&self) -> u64 {
+        self.pieces
+            .iter()
+            .filter(|piece| piece.source == Source::Added)
+            .map(|piece| piece.len)
+            .sum()
+    }
+
+    // index of the piece that holds pos
+    fn find(&self, pos: u64) -> usize {
+        self.starts.partition_point(|&start| start <= pos) - 1
+    }
+
+    pub fn origin(&self, pos: u64) -> Origin {
+        let idx = self.find(pos);
+        let piece = &self.pieces[idx];
+        let offset = piece.start + pos - self.starts[idx];
+        match piece.source {
+            Source::File => Origin::File(offset),
+            Source::Added => Origin::Added(self.added[offset as usize]),
+        }
+    }
+
+    // the file offset of the byte that was overwritten at pos, if any
+    // For bytes that were not changed, that is where they are in the file
+    pub fn base(&self, pos: u64) -> Option<u64> {
+        let idx = self.find(pos);
+        let piece = &self.pieces[idx];
+        let offset = piece.start + pos - self.starts[idx];
+        match piece.source {
+            Source::File => Some(offset),
+            Source::Added => self.bases[offset as usize],
+        }
+    }
+
+    // split the piece that holds pos, so that a piece starts at pos
+    // Returns the index of that piece
+    fn split(&mut self, pos: u64) -> usize {
+        if pos >= self.data_len() {
+            return self.pieces.len();
+        }
+        let idx = self.find(pos);
+        let offset = pos - self.starts[idx];
+        if offset == 0 {
+            return idx;
+        }
+        let piece = self.pieces[idx];
+        self.pieces[idx].len = offset;
+        self.pieces.insert(
+            idx + 1,
+            Piece {
+                source: piece.source,
+                start: piece.start + offset,
+                len: piece.len - offset,
+            },
+        );
+        self.starts.insert(idx + 1, pos);
+        idx + 1
+    }
+
+    // replace len bytes at pos by the pieces
+    // Returns the pieces that were taken out
+    fn replace(&mut self, pos: u64, len: u64, pieces: Vec<Piece>) -> Vec<Piece> {
+        let first = self.split(pos);
+        let last = self.split(pos + len);
+        let removed = self.pieces.splice(first..last, pieces).collect();
+        self.merge();
+        removed
+    }
+
+    fn add(&mut self, bytes: &[u8], bases: &[Option<u64>]) -> Vec<Piece> {
+        if bytes.is_empty() {
+            return Vec::new();
+        }
+        let start = self.added.len() as u64;
+        self.added.extend_from_slice(bytes);
+        self.bases.extend_from_slice(bases);
+        vec![Piece {
+            source: Source::Added,
+            start,
+            len: bytes.len() as u64,
+        }]
+    }
+
+    // replace len bytes at pos by bytes; this overwrites, inserts
+    // as well as deletes
+    pub fn splice(&mut self, pos: u64, len: u64, bytes: &[u8]) -> Change {
+        let bases: Vec<Option<u64>> = (0..bytes.len() as u64)
+            .map(|i| if i < len { self.base(pos + i) } else { None })
+            .collect();
+        let inserted = self.add(bytes, &bases);
+        let removed = self.replace(pos, len, inserted.clone());
+        Change {
+            pos,
+            removed,
+            inserted,
+        }
+    }
+
+    pub fn undo(&mut self, change: &Change) {
+        self.replace(
+            change.pos,
+            total_len(&change.inserted),
+            change.removed.clone(),
+        );
+    }
+
+    pub fn redo(&mut self, change: &Change) {
+        self.replace(
+            change.pos,
+            total_len(&change.removed),
+            change.inserted.clone(),
+        );
+    }
+
+    // read the data at pos into buf, from the file and the added bytes
+    // Returns the number of bytes read
+    pub fn read(&self, mut file: &File, pos: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let end = pos.saturating_add(buf.len() as u64).min(self.data_len());
+        if pos >= end {
+            return Ok(0);
+        }
+        let mut idx = self.find(pos);
+        let mut addr = pos;
+        while addr < end {
+            let piece = &self.pieces[idx];
+            let offset = piece.start + addr - self.starts[idx];
+            let n = (self.starts[idx] + piece.len).min(end) - addr;
+            let dest = &mut buf[(addr - pos) as usize..(addr - pos + n) as usize];
+            match piece.source {
+                Source::File => {
+                    file.seek(SeekFrom::Start(offset))?;
+                    file.read_exact(dest)?;
+                }
+                Source::Added => {
+                    dest.copy_from_slice(&self.added[offset as usize..(offset + n) as usize])
+                }
+            }
+            addr += n;
+            idx += 1;
+        }
+        Ok((end - pos) as usize)
+    }
+
+    // modified ranges as [start, end), in order
+    pub fn ranges(&self) -> impl Iterator<Item = (u64, u64)> {
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        for (piece, &start) in self.pieces.iter().zip(&self.starts) {
+            if piece.source != Source::Added {
+                continue;
+            }
+            let end = start + piece.len;
+            match ranges.last_mut() {
+                Some(last) if last.1 == start => last.1 = end,
+                _ => ranges.push((start, end)),
+            }
+        }
+        ranges.into_iter()
+    }
+
+    // first range that starts after pos
+    pub fn next_range(&self, pos: u64) -> Option<(u64, u64)> {
+        self.ranges().find(|&(start, _)| start > pos)
+    }
+
+    // last range that starts before pos
+    pub fn prev_range(&self, pos: u64) -> Option<(u64, u64)> {
+        self.ranges().filter(|&(start, _)| start < pos).last()
+    }
+
+    // the file data did not move, so the changes can be written in place;
+    // the file may have been cut short
+    pub fn is_in_place(&self) -> bool {
+        self.data_len() <= self.file_len
+            && self
+                .pieces
+                .iter()
+                .zip(&self.starts)
+                .all(|(piece, &start)| piece.source == Source::Added || piece.start == start)
+    }
+
+    // write the added bytes at their offsets, truncate, and sync to disk
+    // Only for changes that are in place
+    pub fn write(&self, file: &mut File) -> io::Result<()> {
+        for (piece, &start) in self.pieces.iter().zip(&self.starts) {
+            if piece.source == Source::Added {
+                let offset = piece.start as usize;
+                file.seek(SeekFrom::Start(start))?;
+                file.write_all(&self.added[offset..offset + piece.len as usize])?;
+            }
+        }
+        if self.data_len() < self.file_len {
+            file.set_len(self.data_len())?;
+        }
+        file.sync_all()
+    }
+
+    // write all of the data to out, reading from file
+    pub fn copy_to(&self, file: &File, out: &mut dyn Write) -> io::Result<()> {
+        let mut buf = vec![0u8; COPY_CHUNKSIZE];
+        let mut pos = 0;
+        while pos < self.data_len() {
+            let n = self.read(file, pos, &mut buf)?;
+            out.write_all(&buf[..n])?;
+            pos += n as u64;
+        }
+        Ok(())
+    }
+
+    // copy the file bytes that a change refers to, so that it can be
+    // undone after the file has been overwritten
+    pub fn detach(&mut self, mut file: &File, change: &mut Change) -> io::Result<()> {
+        for piece in change.removed.iter_mut().chain(change.inserted.iter_mut()) {
+            if piece.source == Source::File {
+                let mut bytes = vec![0u8; piece.len as usize];
+                file.seek(SeekFrom::Start(piece.start))?;
+                file.read_exact(&mut bytes)?;
+                *piece = self.add(&bytes, &vec![None; bytes.len()])[0];
+            }
+        }
+        Ok(())
+    }
+
+    // the data was saved, and is now what is in the file
+    pub fn saved(&mut self, file_len: u64) {
+        if !self.is_in_place() {
+            // the file data moved
+            self.bases.fill(None);
+        }
+        self.file_len = file_len;
+        self.reset();
+    }
+
+    // the file changed on disk; the changes stay, but file data past
+    // the new end is gone
+    pub fn resize_file(&mut self, file_len: u64) {
+        let unchanged = self.is_empty();
+        self.file_len = file_len;
+        if unchanged {
+            self.reset();
+            return;
+        }
+        for piece in self.pieces.iter_mut() {
+            if piece.source == Source::File {
+                piece.len = (piece.start + piece.len)
+                    .min(file_len)
+                    .saturating_sub(piece.start);
+            }
+        }
+        self.merge();
+        if self.data_len() == 0 {
+            self.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::path::PathBuf;
+
+    // a file in the temp directory, removed when dropped
+    struct TempFile {
+        path: PathBuf,
+        file: File,
+    }
+
+    impl TempFile {
+        fn new(name: &str, data: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!("rhex-{}-{}", std::process::id(), name));
+            std::fs::write(&path, data).unwrap();
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&path)
+                .unwrap();
+            TempFile { path, file }
+        }
+
+        fn contents(&self) -> Vec<u8> {
+            std::fs::read(&self.path).unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    // all of the data, with the changes
+    fn data(edits: &Edits, file: &File) -> Vec<u8> {
+        let mut buf = vec![0u8; edits.data_len() as usize];
+        assert_eq!(edits.read(file, 0, &mut buf).unwrap(), buf.len());
+        buf
+    }
+
+    #[test]
+    fn splice() {
+        let tmp = TempFile::new("splice", b"abcdefgh");
+        let cases: [(u64, u64, &[u8], &[u8]); 9] = [
+            // overwrite
+            (0, 2, b"XY", b"XYcdefgh"),
+            (3, 2, b"XY", b"abcXYfgh"),
+            (6, 2, b"XY", b"abcdefXY"),
+            // insert
+            (0, 0, b"XY", b"XYabcdefgh"),
+            (3, 0, b"XY", b"abcXYdefgh"),
+            (8, 0, b"XY", b"abcdefghXY"),
+            // delete
+            (0, 2, b"", b"cdefgh"),
+            (3, 2, b"", b"abcfgh"),
+            (6, 2, b"", b"abcdef"),
+        ];
+        for (pos, len, bytes, expect) in cases {
+            let mut edits = Edits::new(8);
+            let change = edits.splice(pos, len, bytes);
+            assert_eq!(data(&edits, &tmp.file), expect, "at {}", pos);
+            assert_eq!(edits.data_len(), expect.len() as u64);
+            assert_eq!(change.resizes(), len != bytes.len() as u64);
+            assert!(!edits.is_empty());
+            edits.undo(&change);
+            assert!(edits.is_empty());
+            assert_eq!(data(&edits, &tmp.file), b"abcdefgh");
+        }
+
+        // overwritten bytes remember where they came from
+        let mut edits = Edits::new(8);
+        edits.splice(2, 0, b"X");
+        edits.splice(4, 1, b"Y");
+        assert_eq!(data(&edits, &tmp.file), b"abXcYefgh");
+        assert!(matches!(edits.origin(4), Origin::Added(b'Y')));
+        assert_eq!(edits.base(4), Some(3));
+        assert_eq!(edits.base(2), None);
+        assert_eq!(edits.base(5), Some(4));
+        assert_eq!(edits.modified_len(), 2);
+        assert_eq!(edits.ranges().collect::<Vec<_>>(), [(2, 3), (4, 5)]);
+        assert_eq!(edits.next_range(2), Some((4, 5)));
+        assert_eq!(edits.prev_range(4), Some((2, 3)));
+    }
+
+    #[test]
+    fn undo_redo_across_save() {
+        let mut tmp = TempFile::new("undo", b"abcdefgh");
+        let mut edits = Edits::new(8);
+        let mut history = [edits.splice(1, 2, b"XY"), edits.splice(5, 0, b"Z")];
+        assert_eq!(data(&edits, &tmp.file), b"aXYdeZfgh");
+
+        // the data moved, so the file is written anew
+        assert!(!edits.is_in_place());
+        for change in history.iter_mut() {
+            edits.detach(&tmp.file, change).unwrap();
+        }
+        let mut out = Vec::new();
+        edits.copy_to(&tmp.file, &mut out).unwrap();
+        tmp.file.set_len(0).unwrap();
+        tmp.file.seek(SeekFrom::Start(0)).unwrap();
+        tmp.file.write_all(&out).unwrap();
+        edits.saved(out.len() as u64);
+        assert!(edits.is_empty());
+        assert_eq!(tmp.contents(), b"aXYdeZfgh");
+
+        for change in history.iter().rev() {
+            edits.undo(change);
+        }
+        // undo changes the saved data back
+        assert_eq!(data(&edits, &tmp.file), b"abcdefgh");
+        assert!(!edits.is_empty());
+        for change in history.iter() {
+            edits.redo(change);
+        }
+        assert_eq!(data(&edits, &tmp.file), b"aXYdeZfgh");
+    }
+
+    #[test]
+    fn in_place() {
+        let mut edits = Edits::new(8);
+        edits.splice(2, 2, b"XY");
+        assert!(edits.is_in_place());
+
+        // the tail is cut off; the rest stays where it was
+        edits.splice(6, 2, b"");
+        assert!(edits.is_in_place());
+
+        // a net shrink in the middle moves the data after it
+        let mut edits = Edits::new(8);
+        edits.splice(2, 3, b"X");
+        assert!(!edits.is_in_place());
+
+        // as does a net growth
+        let mut edits = Edits::new(8);
+        edits.splice(2, 0, b"X");
+        assert!(!edits.is_in_place());
+    }
+
+    #[test]
+    fn write_truncates() {
+        let mut tmp = TempFile::new("write", b"abcdefgh");
+        let mut edits = Edits::new(8);
+        edits.splice(1, 1, b"X");
+        edits.splice(5, 3, b"");
+        assert!(edits.is_in_place());
+        edits.write(&mut tmp.file).unwrap();
+        assert_eq!(tmp.contents(), b"aXcde");
+        edits.saved(5);
+        assert!(edits.is_empty());
+        assert_eq!(edits.data_len(), 5);
+        assert_eq!(data(&edits, &tmp.file), b"aXcde");
+    }
+
+    #[test]
+    fn read_and_copy() {
+        let tmp = TempFile::new("read", b"0123456789");
+        let mut edits = Edits::new(10);
+        edits.splice(4, 2, b"abc");
+        assert_eq!(data(&edits, &tmp.file), b"0123abc6789");
+
+        // reads stop at the end of the data
+        let mut buf = [0u8; 4];
+        assert_eq!(edits.read(&tmp.file, 3, &mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"3abc");
+        assert_eq!(edits.read(&tmp.file, 9, &mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"89");
+        assert_eq!(edits.read(&tmp.file, 11, &mut buf).unwrap(), 0);
+
+        let mut out = Vec::new();
+        edits.copy_to(&tmp.file, &mut out).unwrap();
+        assert_eq!(out, b"0123abc6789");
+
+        // a detached change no longer refers to the file
+        let mut change = edits.splice(0, 3, b"");
+        edits.detach(&tmp.file, &mut change).unwrap();
+        let empty = TempFile::new("detached", b"");
+        edits.undo(&change);
+        assert!(matches!(edits.origin(0), Origin::Added(b'0')));
+        let mut buf = [0u8; 3];
+        edits.read(&empty.file, 0, &mut buf).unwrap();
+        assert_eq!(&buf, b"012");
+    }
+}
+
+// EOB
@@ -0,0 +1,296 @@
+/*
+    elf.rs  WJ126
+
+    * minimal ELF parser: sections and symbol tables
+*/
+
+use std::io::{Read, Seek, SeekFrom};
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_NOBITS: u32 = 8;
+const SHT_DYNSYM: u32 = 11;
+
+const SHN_UNDEF: u16 = 0;
+const SHN_LORESERVE: u16 = 0xff00;
+
+const ET_REL: u16 = 1;
+
+// refuse to load tables larger than this
+const MAX_TABLE_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub name: String,
+    pub sh_type: u32,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+    pub link: u32,
+    pub entsize: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub value: u64,
+    pub size: u64,
+    pub section: String,
+    // file offset, if the symbol lives in a file-backed section
+    pub file_offset: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct Elf {
+    pub is_64bit: bool,
+    pub big_endian: bool,
+    pub e_type: u16,
+    pub sections: Vec<Section>,
+}
+
+pub fn is_elf(header: &[u8]) -> bool {
+    header.len() >= 4 && header[..4] == [0x7f, b'E', b'L', b'F']
+}
+
+fn read_exact_at<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    size: u64,
+) -> Result<Vec<u8>, String> {
+    if size > MAX_TABLE_SIZE {
+        return Err("ELF table is too large".to_owned());
+    }
+    let mut buf = vec![0u8; size as usize];
+    reader
+        .seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("seek error: {}", e))?;
+    reader
+        .read_exact(&mut buf)
+        .map_err(|_| "truncated ELF file".to_owned())?;
+    Ok(buf)
+}
+
+// C string at index in a string table
+fn c_string(strtab: &[u8], index: usize) -> String {
+    if index >= strtab.len() {
+        return String::new();
+    }
+    let end = strtab[index..]
+        .iter()
+        .position(|&b| b == 0)
+        .map_or(strtab.len(), |n| index + n);
+    String::from_utf8_lossy(&strtab[index..end]).into_owned()
+}
+
+impl Elf {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Elf, String> {
+        let ident = read_exact_at(reader, 0, 16).map_err(|_| "not an ELF file".to_owned())?;
+        if !is_elf(&ident) {
+            return Err("not an ELF file".to_owned());
+        }
+        let is_64bit = match ident[4] {
+            1 => false,
+            2 => true,
+            _ => return Err("unknown ELF class".to_owned()),
+        };
+        let big_endian = match ident[5] {
+            1 => false,
+            2 => true,
+            _ => return Err("unknown ELF data encoding".to_owned()),
+        };
+
+        let mut elf = Elf {
+            is_64bit,
+            big_endian,
+            e_type: 0,
+            sections: Vec::new(),
+        };
+
+        let header = read_exact_at(reader, 0, if is_64bit { 64 } else { 52 })?;
+        elf.e_type = elf.u16_at(&header, 0x10);
+        let (shoff, shentsize, shnum, shstrndx) = if is_64bit {
+            (
+                elf.u64_at(&header, 0x28),
+                elf.u16_at(&header, 0x3a) as u64,
+                elf.u16_at(&header, 0x3c) as u64,
+                elf.u16_at(&header, 0x3e) as usize,
+            )
+        } else {
+            (
+                elf.u32_at(&header, 0x20) as u64,
+                elf.u16_at(&header, 0x2e) as u64,
+                elf.u16_at(&header, 0x30) as u64,
+                elf.u16_at(&header, 0x32) as usize,
+            )
+        };
+        if shoff == 0 || shnum == 0 {
+            return Ok(elf);
+        }
+        let min_entsize = if is_64bit { 64 } else { 40 };
+        if shentsize < min_entsize {
+            return Err("invalid section header size".to_owned());
+        }
+
+        let table = read_exact_at(reader, shoff, shentsize * shnum)?;
+        let mut name_indices = Vec::new();
+        for i in 0..shnum as usize {
+            let sh = &table[i * shentsize as usize..];
+            let section = if is_64bit {
+                Section {
+                    name: String::new(),
+                    sh_type: elf.u32_at(sh, 4),
+                    addr: elf.u64_at(sh, 16),
+                    offset: elf.u64_at(sh, 24),
+                    size: elf.u64_at(sh, 32),
+                    link: elf.u32_at(sh, 40),
+                    entsize: elf.u64_at(sh, 56),
+                }
+            } else {
+                Section {
+                    name: String::new(),
+                    sh_type: elf.u32_at(sh, 4),
+                    addr: elf.u32_at(sh, 12) as u64,
+                    offset: elf.u32_at(sh, 16) as u64,
+                    size: elf.u32_at(sh, 20) as u64,
+                    link: elf.u32_at(sh, 24),
+                    entsize: elf.u32_at(sh, 36) as u64,
+                }
+            };
+            name_indices.push(elf.u32_at(sh, 0) as usize);
+            elf.sections.push(section);
+        }
+
+        // section names
+        if shstrndx < elf.sections.len() && elf.sections[shstrndx].sh_type != SHT_NOBITS {
+            let strsec = &elf.sections[shstrndx];
+            if let Ok(strtab) = read_exact_at(reader, strsec.offset, strsec.size) {
+                for (section, idx) in elf.sections.iter_mut().zip(name_indices) {
+                    section.name = c_string(&strtab, idx);
+                }
+            }
+        }
+        Ok(elf)
+    }
+
+    // all symbols from .symtab and .dynsym, sorted by name
+    pub fn symbols<R: Read + Seek>(&self, reader: &mut R) -> Result<Vec<Symbol>, String> {
+        let mut symbols = Vec::new();
+        let mut found_table = false;
+
+        for section in &self.sections {
+            if section.sh_type != SHT_SYMTAB && section.sh_type != SHT_DYNSYM {
+                continue;
+            }
+            let strsec = match self.sections.get(section.link as usize) {
+                Some(strsec) => strsec,
+                None => continue,
+            };
+            found_table = true;
+
+            let entsize = if self.is_64bit { 24 } else { 16 };
+            if section.entsize != 0 && section.entsize < entsize {
+                return Err("invalid symbol table entry size".to_owned());
+            }
+            let stride = section.entsize.max(entsize) as usize;
+            let table = read_exact_at(reader, section.offset, section.size)?;
+            let strtab = read_exact_at(reader, strsec.offset, strsec.size)?;
+
+            for sym in table.chunks_exact(stride) {
+                let (name_idx, value, size, shndx) = if self.is_64bit {
+                    (
+                        self.u32_at(sym, 0),
+                        self.u64_at(sym, 8),
+                        self.u64_at(sym, 16),
+                        self.u16_at(sym, 6),
+                    )
+                } else {
+                    (
+                        self.u32_at(sym, 0),
+                        self.u32_at(sym, 4) as u64,
+                        self.u32_at(sym, 8) as u64,
+                        self.u16_at(sym, 14),
+                    )
+                };
+                let name = c_string(&strtab, name_idx as usize);
+                if name.is_empty() {
+                    continue;
+                }
+                symbols.push(Symbol {
+                    name,
+                    value,
+                    size,
+                    section: self.section_name(shndx),
+                    file_offset: self.file_offset(value, shndx),
+                });
+            }
+        }
+        if !found_table {
+            return Err("no symbol table".to_owned());
+        }
+
+        symbols.sort_by(|a, b| a.name.cmp(&b.name).then(a.value.cmp(&b.value)));
+        symbols.dedup_by(|a, b| a.name == b.name && a.value == b.value);
+        Ok(symbols)
+    }
+
+    // convert symbol value to file offset via the section mapping
+    fn file_offset(&self, value: u64, shndx: u16) -> Option<u64> {
+        if shndx == SHN_UNDEF || shndx >= SHN_LORESERVE {
+            return None;
+        }
+        let section = self.sections.get(shndx as usize)?;
+        if section.sh_type == SHT_NOBITS {
+            return None;
+        }
+        // in relocatable objects the value is relative to the section
+        let rel = if self.e_type == ET_REL {
+            value
+        } else {
+            value.checked_sub(section.addr)?
+        };
+        if rel >= section.size {
+            return None;
+        }
+        section.offset.checked_add(rel)
+    }
+
+    fn section_name(&self, shndx: u16) -> String {
+        match shndx {
+            SHN_UNDEF => "UND".to_owned(),
+            0xfff1 => "ABS".to_owned(),
+            0xfff2 => "COMMON".to_owned(),
+            _ => match self.sections.get(shndx as usize) {
+                Some(section) if !section.name.is_empty() => section.name.clone(),
+                _ => format!("[{}]", shndx),
+            },
+        }
+    }
+
+    fn u16_at(&self, buf: &[u8], pos: usize) -> u16 {
+        let bytes = [buf[pos], buf[pos + 1]];
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+
+    fn u32_at(&self, buf: &[u8], pos: usize) -> u32 {
+        let bytes: [u8; 4] = buf[pos..pos + 4].try_into().unwrap();
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    fn u64_at(&self, buf: &[u8], pos: usize) -> u64 {
+        let bytes: [u8; 8] = buf[pos..pos + 8].try_into().unwrap();
+        if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        }
+    }
+}
+
+// EOB
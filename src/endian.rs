@@ -0,0 +1,141 @@
+/*
+    endian.rs  WJ126
+
+    * guess the byte order of the data in a file
+*/
+
+use crate::Endiannes::{self, *};
+
+// number of aligned words needed before statistics count
+const MIN_VOTES: u64 = 32;
+
+// byte order given away by a signature at the start of the data
+fn from_signature(data: &[u8]) -> Option<Endiannes> {
+    if data.starts_with(b"\x7fELF") && data.len() > 5 {
+        // EI_DATA
+        return match data[5] {
+            1 => Some(LittleEndian),
+            2 => Some(BigEndian),
+            _ => None,
+        };
+    }
+    const SIGNATURES: &[(&[u8], Endiannes)] = &[
+        (b"\xff\xfe", LittleEndian), // UTF-16 BOM, also UTF-32LE
+        (b"\xfe\xff", BigEndian),
+        (b"\x00\x00\xfe\xff", BigEndian), // UTF-32BE BOM
+        (b"II*\x00", LittleEndian),       // TIFF
+        (b"MM\x00*", BigEndian),
+        (b"\xce\xfa\xed\xfe", LittleEndian), // Mach-O
+        (b"\xcf\xfa\xed\xfe", LittleEndian),
+        (b"\xfe\xed\xfa\xce", BigEndian),
+        (b"\xfe\xed\xfa\xcf", BigEndian),
+        (b"\xd4\xc3\xb2\xa1", LittleEndian), // pcap
+        (b"\xa1\xb2\xc3\xd4", BigEndian),
+        (b"\xd0\x0d\xfe\xed", BigEndian), // device tree
+    ];
+    SIGNATURES
+        .iter()
+        .find(|(magic, _)| data.starts_with(magic))
+        .map(|&(_, order)| order)
+}
+
+// Look at aligned words, and count which byte order gives more
+// plausible values: small non-zero 32-bit integers, and UTF-16
+// code units in the ASCII range
+// Returns None when the evidence is not strong enough
+pub fn guess(data: &[u8]) -> Option<Endiannes> {
+    if let Some(order) = from_signature(data) {
+        return Some(order);
+    }
+
+    let mut little = 0u64;
+    let mut big = 0u64;
+    for word in data.chunks_exact(4) {
+        let le = u32::from_le_bytes(word.try_into().unwrap());
+        let be = u32::from_be_bytes(word.try_into().unwrap());
+        let small = |value: u32| value != 0 && value < 0x10000;
+        if small(le) && !small(be) {
+            little += 1;
+        } else if small(be) && !small(le) {
+            big += 1;
+        }
+    }
+    for unit in data.chunks_exact(2) {
+        let printable = |b: u8| (b' '..=b'~').contains(&b);
+        if printable(unit[0]) && unit[1] == 0 {
+            little += 1;
+        } else if unit[0] == 0 && printable(unit[1]) {
+            big += 1;
+        }
+    }
+
+    if little + big < MIN_VOTES {
+        return None;
+    }
+    if little >= 4 * big {
+        Some(LittleEndian)
+    } else if big >= 4 * little {
+        Some(BigEndian)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn little_endian() {
+        // an array of small 32-bit counters
+        let data: Vec<u8> = (1..=64u32).flat_map(|n| (n * 100).to_le_bytes()).collect();
+        assert_eq!(guess(&data), Some(LittleEndian));
+
+        // UTF-16LE text
+        let data: Vec<u8> = "hello, world! "
+            .repeat(4)
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(guess(&data), Some(LittleEndian));
+    }
+
+    #[test]
+    fn big_endian() {
+        let data: Vec<u8> = (1..=64u32).flat_map(|n| (n * 100).to_be_bytes()).collect();
+        assert_eq!(guess(&data), Some(BigEndian));
+
+        let data: Vec<u8> = "hello, world! "
+            .repeat(4)
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        assert_eq!(guess(&data), Some(BigEndian));
+    }
+
+    #[test]
+    fn signature() {
+        assert_eq!(guess(b"\x7fELF\x02\x02\x01"), Some(BigEndian));
+        assert_eq!(guess(b"II*\x00"), Some(LittleEndian));
+        assert_eq!(guess(b"\xfe\xff"), Some(BigEndian));
+    }
+
+    #[test]
+    fn undecidable() {
+        // too little data
+        assert_eq!(guess(&[1, 0, 0, 0]), None);
+        // all zeroes votes for neither
+        assert_eq!(guess(&[0; 1024]), None);
+        // as many values of either byte order
+        let data: Vec<u8> = (1..=64u32)
+            .flat_map(|n| {
+                let mut pair = (n * 100).to_le_bytes().to_vec();
+                pair.extend_from_slice(&(n * 100).to_be_bytes());
+                pair
+            })
+            .collect();
+        assert_eq!(guess(&data), None);
+    }
+}
+
+// EOB
@@ -0,0 +1,306 @@
+/*
+    expr.rs  WJ126
+
+    * arithmetic for offsets, like "0x200 + 12*0x48" or ". + 0x30"
+*/
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExprError {
+    // char index into the expression
+    pub pos: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at column {}", self.msg, self.pos + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(i128),
+    Op(char),
+    Open,
+    Close,
+    End,
+}
+
+// variables that may be used in an expression
+pub struct Vars {
+    pub here: u64, // "."
+    pub end: u64,  // "end"
+}
+
+fn tokenize(text: &str, vars: &Vars) -> Result<Vec<(usize, Token)>, ExprError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = match c {
+            '+' | '-' | '*' | '/' => Token::Op(c),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '.' => Token::Number(vars.here as i128),
+            _ if c.is_ascii_alphanumeric() => {
+                while i + 1 < chars.len()
+                    && (chars[i + 1].is_ascii_alphanumeric() || chars[i + 1] == '_')
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..=i].iter().filter(|&&c| c != '_').collect();
+                if word == "end" {
+                    Token::Number(vars.end as i128)
+                } else {
+                    let value = if let Some(hex) =
+                        word.strip_prefix("0x").or_else(|| word.strip_prefix("0X"))
+                    {
+                        u64::from_str_radix(hex, 16).ok()
+                    } else {
+                        word.parse::<u64>().ok()
+                    };
+                    match value {
+                        Some(value) => Token::Number(value as i128),
+                        None if c.is_ascii_digit() => {
+                            return Err(ExprError {
+                                pos: start,
+                                msg: format!("invalid number '{}'", word),
+                            })
+                        }
+                        None => {
+                            return Err(ExprError {
+                                pos: start,
+                                msg: format!("unknown name '{}'", word),
+                            })
+                        }
+                    }
+                }
+            }
+            _ => {
+                return Err(ExprError {
+                    pos: start,
+                    msg: format!("unexpected '{}'", c),
+                })
+            }
+        };
+        tokens.push((start, token));
+        i += 1;
+    }
+    tokens.push((chars.len(), Token::End));
+    Ok(tokens)
+}
+
+// recursive descent:
+//   expr   = term { ("+" | "-") term }
+//   term   = factor { ("*" | "/") factor }
+//   factor = "-" factor | number | "(" expr ")"
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    idx: usize,
+}
+
+// values stay within u64 range, in either sign
+fn check_range(pos: usize, value: Option<i128>) -> Result<i128, ExprError> {
+    match value {
+        Some(value) if value.unsigned_abs() <= u64::MAX as u128 => Ok(value),
+        _ => Err(ExprError {
+            pos,
+            msg: "overflow".to_owned(),
+        }),
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> (usize, Token) {
+        self.tokens[self.idx]
+    }
+
+    fn next(&mut self) -> (usize, Token) {
+        let token = self.tokens[self.idx];
+        if token.1 != Token::End {
+            self.idx += 1;
+        }
+        token
+    }
+
+    fn expr(&mut self) -> Result<i128, ExprError> {
+        let mut value = self.term()?;
+        while let (pos, Token::Op(op @ ('+' | '-'))) = self.peek() {
+            self.next();
+            let rhs = self.term()?;
+            value = if op == '+' {
+                check_range(pos, value.checked_add(rhs))?
+            } else {
+                check_range(pos, value.checked_sub(rhs))?
+            };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<i128, ExprError> {
+        let mut value = self.factor()?;
+        while let (pos, Token::Op(op @ ('*' | '/'))) = self.peek() {
+            self.next();
+            let rhs = self.factor()?;
+            value = if op == '*' {
+                check_range(pos, value.checked_mul(rhs))?
+            } else if rhs == 0 {
+                return Err(ExprError {
+                    pos,
+                    msg: "division by zero".to_owned(),
+                });
+            } else {
+                value / rhs
+            };
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<i128, ExprError> {
+        let (pos, token) = self.next();
+        match token {
+            Token::Number(value) => Ok(value),
+            Token::Op('-') => Ok(-self.factor()?),
+            Token::Open => {
+                let value = self.expr()?;
+                match self.next() {
+                    (_, Token::Close) => Ok(value),
+                    (pos, _) => Err(ExprError {
+                        pos,
+                        msg: "missing ')'".to_owned(),
+                    }),
+                }
+            }
+            Token::End => Err(ExprError {
+                pos,
+                msg: "unexpected end of expression".to_owned(),
+            }),
+            Token::Op(c) => Err(ExprError {
+                pos,
+                msg: format!("unexpected '{}'", c),
+            }),
+            Token::Close => Err(ExprError {
+                pos,
+                msg: "unexpected ')'".to_owned(),
+            }),
+        }
+    }
+}
+
+pub fn eval(text: &str, vars: &Vars) -> Result<u64, ExprError> {
+    let tokens = tokenize(text, vars)?;
+    let mut parser = Parser { tokens, idx: 0 };
+    let value = parser.expr()?;
+    let (pos, token) = parser.peek();
+    if token != Token::End {
+        return Err(ExprError {
+            pos,
+            msg: "expected an operator".to_owned(),
+        });
+    }
+    if value < 0 {
+        return Err(ExprError {
+            pos: 0,
+            msg: "negative offset".to_owned(),
+        });
+    }
+    Ok(value as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS: Vars = Vars {
+        here: 0x100,
+        end: 0x1000,
+    };
+
+    fn calc(text: &str) -> Result<u64, ExprError> {
+        eval(text, &VARS)
+    }
+
+    // the message and column of an error
+    fn error(text: &str) -> (String, usize) {
+        let err = calc(text).unwrap_err();
+        (err.msg, err.pos + 1)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(calc("0x200 + 12*0x48"), Ok(0x200 + 12 * 0x48));
+        assert_eq!(calc("2 + 3 * 4"), Ok(14));
+        assert_eq!(calc("(2 + 3) * 4"), Ok(20));
+        assert_eq!(calc("20 - 4 - 3"), Ok(13));
+        assert_eq!(calc("100 / 10 / 5"), Ok(2));
+        assert_eq!(calc("7 / 2 * 2"), Ok(6));
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(calc("-2 + 5"), Ok(3));
+        assert_eq!(calc("10 - -2"), Ok(12));
+        assert_eq!(calc("--4"), Ok(4));
+        assert_eq!(calc("-(3 - 5)"), Ok(2));
+        assert_eq!(error("-1"), ("negative offset".to_owned(), 1));
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(calc("0x1F"), Ok(31));
+        assert_eq!(calc("0XfF"), Ok(255));
+        assert_eq!(calc("017"), Ok(17));
+        assert_eq!(calc("1_000"), Ok(1000));
+        assert_eq!(calc("0xffff_ffff_ffff_ffff"), Ok(u64::MAX));
+        assert_eq!(error("1 + 0xg"), ("invalid number '0xg'".to_owned(), 5));
+    }
+
+    #[test]
+    fn variables() {
+        assert_eq!(calc(". + 0x30"), Ok(0x130));
+        assert_eq!(calc("end - ."), Ok(0xf00));
+        assert_eq!(error("foo"), ("unknown name 'foo'".to_owned(), 1));
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            error("0x10000000000000000"),
+            ("invalid number '0x10000000000000000'".to_owned(), 1)
+        );
+        assert_eq!(error("0xffffffffffffffff + 1"), ("overflow".to_owned(), 20));
+        assert_eq!(
+            error("0x100000000 * 0x100000000"),
+            ("overflow".to_owned(), 13)
+        );
+        // intermediate values may be negative, as long as they fit
+        assert_eq!(calc("1 - 0xffffffffffffffff + 0xffffffffffffffff"), Ok(1));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(error("1 / 0"), ("division by zero".to_owned(), 3));
+        assert_eq!(error("8 / (4 - 4)"), ("division by zero".to_owned(), 3));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(error("1 2"), ("expected an operator".to_owned(), 3));
+        assert_eq!(error("(1 + 2"), ("missing ')'".to_owned(), 7));
+        assert_eq!(error("1 + 2)"), ("expected an operator".to_owned(), 6));
+        assert_eq!(error("1 +"), ("unexpected end of expression".to_owned(), 4));
+        assert_eq!(error("* 2"), ("unexpected '*'".to_owned(), 1));
+        assert_eq!(error("1 % 2"), ("unexpected '%'".to_owned(), 3));
+        assert_eq!(error(""), ("unexpected end of expression".to_owned(), 1));
+    }
+}
+
+// EOB
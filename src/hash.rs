@@ -0,0 +1,318 @@
+/*
+    hash.rs  WJ126
+
+    * checksums and hashes for the :hash command
+*/
+
+use crate::crc::{Crc, CrcParams};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Crc32,
+    Crc64,
+    Adler32,
+    Xxh64,
+}
+
+pub const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::Crc32,
+    Algorithm::Crc64,
+    Algorithm::Adler32,
+    Algorithm::Xxh64,
+];
+
+impl Algorithm {
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        ALGORITHMS
+            .iter()
+            .copied()
+            .find(|alg| alg.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Crc32 => "crc32",
+            Algorithm::Crc64 => "crc64",
+            Algorithm::Adler32 => "adler32",
+            Algorithm::Xxh64 => "xxh64",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Algorithm::Crc32 => "CRC-32/ISO-HDLC, as used by zip, gzip and png",
+            Algorithm::Crc64 => "CRC-64/XZ",
+            Algorithm::Adler32 => "Adler-32, as used by zlib",
+            Algorithm::Xxh64 => "xxHash64 with seed 0, as used by LZ4 and zstd",
+        }
+    }
+
+    pub fn hasher(&self) -> Box<dyn Hasher> {
+        match self {
+            Algorithm::Crc32 => Box::new(CrcHasher::new("CRC-32/ISO-HDLC")),
+            Algorithm::Crc64 => Box::new(CrcHasher::new("CRC-64/XZ")),
+            Algorithm::Adler32 => Box::new(Adler32::new()),
+            Algorithm::Xxh64 => Box::new(Xxh64::new(0)),
+        }
+    }
+}
+
+pub trait Hasher {
+    fn update(&mut self, data: &[u8]);
+    // the digest as hex string
+    fn finish(&self) -> String;
+}
+
+struct CrcHasher {
+    crc: Crc,
+    digits: usize,
+}
+
+impl CrcHasher {
+    fn new(preset: &str) -> CrcHasher {
+        let params: &CrcParams = crate::crc::CATALOGUE
+            .iter()
+            .find(|(name, _)| *name == preset)
+            .map(|(_, params)| params)
+            .unwrap();
+        CrcHasher {
+            crc: Crc::new(params),
+            digits: params.digits(),
+        }
+    }
+}
+
+impl Hasher for CrcHasher {
+    fn update(&mut self, data: &[u8]) {
+        self.crc.update(data);
+    }
+
+    fn finish(&self) -> String {
+        format!("{:0digits$X}", self.crc.finish(), digits = self.digits)
+    }
+}
+
+const ADLER_MOD: u32 = 65521;
+// largest n such that 255n(n+1)/2 + (n+1)(ADLER_MOD-1) fits in u32
+const ADLER_NMAX: usize = 5552;
+
+struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    fn new() -> Adler32 {
+        Adler32 { a: 1, b: 0 }
+    }
+}
+
+impl Hasher for Adler32 {
+    fn update(&mut self, data: &[u8]) {
+        // defer the modulo for as long as possible
+        for block in data.chunks(ADLER_NMAX) {
+            for &byte in block {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= ADLER_MOD;
+            self.b %= ADLER_MOD;
+        }
+    }
+
+    fn finish(&self) -> String {
+        format!("{:08X}", self.b << 16 | self.a)
+    }
+}
+
+const PRIME64_1: u64 = 0x9E3779B185EBCA87;
+const PRIME64_2: u64 = 0xC2B2AE3D27D4EB4F;
+const PRIME64_3: u64 = 0x165667B19E3779F9;
+const PRIME64_4: u64 = 0x85EBCA77C2B2AE63;
+const PRIME64_5: u64 = 0x27D4EB2F165667C5;
+
+struct Xxh64 {
+    seed: u64,
+    acc: [u64; 4],
+    buf: [u8; 32],
+    buf_len: usize,
+    total_len: u64,
+}
+
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME64_2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME64_1)
+}
+
+fn xxh64_merge(hash: u64, acc: u64) -> u64 {
+    (hash ^ xxh64_round(0, acc))
+        .wrapping_mul(PRIME64_1)
+        .wrapping_add(PRIME64_4)
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data[..8].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes(data[..4].try_into().unwrap())
+}
+
+impl Xxh64 {
+    fn new(seed: u64) -> Xxh64 {
+        Xxh64 {
+            seed,
+            acc: [
+                seed.wrapping_add(PRIME64_1).wrapping_add(PRIME64_2),
+                seed.wrapping_add(PRIME64_2),
+                seed,
+                seed.wrapping_sub(PRIME64_1),
+            ],
+            buf: [0; 32],
+            buf_len: 0,
+            total_len: 0,
+        }
+    }
+
+    fn stripe(&mut self, stripe: &[u8]) {
+        for (i, acc) in self.acc.iter_mut().enumerate() {
+            *acc = xxh64_round(*acc, read_u64(&stripe[i * 8..]));
+        }
+    }
+}
+
+impl Hasher for Xxh64 {
+    fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+
+        // complete a partial stripe first
+        if self.buf_len > 0 {
+            let n = (32 - self.buf_len).min(data.len());
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+            if self.buf_len < 32 {
+                return;
+            }
+            let buf = self.buf;
+            self.stripe(&buf);
+            self.buf_len = 0;
+        }
+
+        let mut stripes = data.chunks_exact(32);
+        for stripe in &mut stripes {
+            self.stripe(stripe);
+        }
+        let rest = stripes.remainder();
+        self.buf[..rest.len()].copy_from_slice(rest);
+        self.buf_len = rest.len();
+    }
+
+    fn finish(&self) -> String {
+        let mut hash = if self.total_len >= 32 {
+            let [v1, v2, v3, v4] = self.acc;
+            let mut hash = v1
+                .rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+            for acc in self.acc {
+                hash = xxh64_merge(hash, acc);
+            }
+            hash
+        } else {
+            self.seed.wrapping_add(PRIME64_5)
+        };
+        hash = hash.wrapping_add(self.total_len);
+
+        let mut rest = &self.buf[..self.buf_len];
+        while rest.len() >= 8 {
+            hash ^= xxh64_round(0, read_u64(rest));
+            hash = hash
+                .rotate_left(27)
+                .wrapping_mul(PRIME64_1)
+                .wrapping_add(PRIME64_4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            hash ^= (read_u32(rest) as u64).wrapping_mul(PRIME64_1);
+            hash = hash
+                .rotate_left(23)
+                .wrapping_mul(PRIME64_2)
+                .wrapping_add(PRIME64_3);
+            rest = &rest[4..];
+        }
+        for &byte in rest {
+            hash ^= (byte as u64).wrapping_mul(PRIME64_5);
+            hash = hash.rotate_left(11).wrapping_mul(PRIME64_1);
+        }
+
+        // avalanche
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(PRIME64_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(PRIME64_3);
+        hash ^= hash >> 32;
+        format!("{:016X}", hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(alg: Algorithm, data: &[u8]) -> String {
+        let mut hasher = alg.hasher();
+        hasher.update(data);
+        hasher.finish()
+    }
+
+    #[test]
+    fn crc32() {
+        assert_eq!(digest(Algorithm::Crc32, b""), "00000000");
+        assert_eq!(digest(Algorithm::Crc32, b"abc"), "352441C2");
+        assert_eq!(digest(Algorithm::Crc32, b"123456789"), "CBF43926");
+    }
+
+    #[test]
+    fn crc64() {
+        assert_eq!(digest(Algorithm::Crc64, b""), "0000000000000000");
+        assert_eq!(digest(Algorithm::Crc64, b"abc"), "2CD8094A1A277627");
+        assert_eq!(digest(Algorithm::Crc64, b"123456789"), "995DC9BBDF1939FA");
+    }
+
+    #[test]
+    fn adler32() {
+        assert_eq!(digest(Algorithm::Adler32, b""), "00000001");
+        assert_eq!(digest(Algorithm::Adler32, b"abc"), "024D0127");
+        assert_eq!(digest(Algorithm::Adler32, b"Wikipedia"), "11E60398");
+    }
+
+    #[test]
+    fn xxh64() {
+        assert_eq!(digest(Algorithm::Xxh64, b""), "EF46DB3751D8E999");
+        assert_eq!(digest(Algorithm::Xxh64, b"abc"), "44BC2CF5AD770999");
+    }
+
+    #[test]
+    fn in_pieces() {
+        // longer than a stripe, and longer than ADLER_NMAX
+        let data: Vec<u8> = (0..10000u32).map(|i| (i * 7 + i / 256) as u8).collect();
+        for &alg in ALGORITHMS {
+            let mut hasher = alg.hasher();
+            for piece in data.chunks(13) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finish(), digest(alg, &data), "{}", alg.name());
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Algorithm::from_name("XXH64"), Some(Algorithm::Xxh64));
+        assert_eq!(Algorithm::from_name("md5"), None);
+    }
+}
+
+// EOB
@@ -0,0 +1,123 @@
+/*
+    magic.rs  WJ126
+
+    * file type signatures
+    * well-known GUIDs, like the GPT partition types
+*/
+
+#[derive(Debug)]
+pub struct Magic {
+    pub name: &'static str,
+    pub bytes: &'static [u8],
+    // position of the signature, relative to the start of the object
+    pub at: u64,
+}
+
+const fn magic(name: &'static str, bytes: &'static [u8], at: u64) -> Magic {
+    Magic { name, bytes, at }
+}
+
+pub const MAGIC_TABLE: &[Magic] = &[
+    magic("PNG image", b"\x89PNG\r\n\x1a\n", 0),
+    magic("JPEG image", b"\xff\xd8\xff", 0),
+    magic("GIF image", b"GIF87a", 0),
+    magic("GIF image", b"GIF89a", 0),
+    magic("BMP image", b"BM", 0),
+    magic("TIFF image (little endian)", b"II*\x00", 0),
+    magic("TIFF image (big endian)", b"MM\x00*", 0),
+    magic("PDF document", b"%PDF-", 0),
+    magic("PEM data", b"-----BEGIN ", 0),
+    magic("ZIP archive", b"PK\x03\x04", 0),
+    magic("ZIP archive (empty)", b"PK\x05\x06", 0),
+    magic("gzip compressed data", b"\x1f\x8b\x08", 0),
+    magic("bzip2 compressed data", b"BZh", 0),
+    magic("xz compressed data", b"\xfd7zXZ\x00", 0),
+    magic("zstd compressed data", b"\x28\xb5\x2f\xfd", 0),
+    magic("LZ4 compressed data", b"\x04\x22\x4d\x18", 0),
+    magic("zlib compressed data", b"\x78\x9c", 0),
+    magic("zlib compressed data", b"\x78\xda", 0),
+    magic("7-zip archive", b"7z\xbc\xaf\x27\x1c", 0),
+    magic("RAR archive", b"Rar!\x1a\x07", 0),
+    magic("Microsoft cabinet", b"MSCF\x00\x00\x00\x00", 0),
+    magic("tar archive", b"ustar", 257),
+    magic("cpio archive", b"070701", 0),
+    magic("squashfs filesystem", b"hsqs", 0),
+    magic("U-Boot image", b"\x27\x05\x19\x56", 0),
+    magic("device tree blob", b"\xd0\x0d\xfe\xed", 0),
+    magic("ELF executable", b"\x7fELF", 0),
+    magic("DOS/PE executable", b"MZ", 0),
+    magic("Mach-O executable (32-bit)", b"\xfe\xed\xfa\xce", 0),
+    magic("Mach-O executable (64-bit)", b"\xfe\xed\xfa\xcf", 0),
+    magic("Mach-O executable (32-bit)", b"\xce\xfa\xed\xfe", 0),
+    magic("Mach-O executable (64-bit)", b"\xcf\xfa\xed\xfe", 0),
+    magic("Java class / Mach-O fat binary", b"\xca\xfe\xba\xbe", 0),
+    magic("WebAssembly module", b"\x00asm", 0),
+    magic("RIFF container (WAV/AVI/WebP)", b"RIFF", 0),
+    magic("Ogg stream", b"OggS", 0),
+    magic("FLAC audio", b"fLaC", 0),
+    magic("MP3 audio (ID3 tag)", b"ID3", 0),
+    magic("SQLite database", b"SQLite format 3\x00", 0),
+];
+
+impl Magic {
+    // short signatures give too many false positives,
+    // so those only count when found exactly where asked
+    pub fn is_weak(&self) -> bool {
+        self.bytes.len() < 3
+    }
+
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.starts_with(self.bytes)
+    }
+}
+
+// type of the object that starts at data[0]
+pub fn identify(data: &[u8]) -> Option<&'static Magic> {
+    MAGIC_TABLE.iter().find(|magic| {
+        let at = magic.at as usize;
+        at < data.len() && magic.matches(&data[at..])
+    })
+}
+
+// in the canonical form, lowercase
+const KNOWN_GUIDS: &[(&str, &str)] = &[
+    ("00000000-0000-0000-0000-000000000000", "nil"),
+    (
+        "c12a7328-f81f-11d2-ba4b-00a0c93ec93b",
+        "EFI system partition",
+    ),
+    (
+        "024dee41-33e7-11d3-9d69-0008c781f39f",
+        "MBR partition scheme",
+    ),
+    (
+        "21686148-6449-6e6f-744e-656564454649",
+        "BIOS boot partition",
+    ),
+    ("e3c9e316-0b5c-4db8-817d-f92df00215ae", "Microsoft reserved"),
+    (
+        "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7",
+        "Microsoft basic data",
+    ),
+    ("de94bba4-06d1-4d40-a16a-bfd50179d6ac", "Windows recovery"),
+    ("0fc63daf-8483-4772-8e79-3d69d8477de4", "Linux filesystem"),
+    ("0657fd6d-a4ab-43c4-84e5-0933c84b4f4f", "Linux swap"),
+    ("e6d6d379-f507-44c2-a23c-238f2a3df928", "Linux LVM"),
+    ("a19d880f-05fc-4d3b-a006-743f0f84911e", "Linux RAID"),
+    (
+        "4f68bce3-e8cd-4db1-96e7-fbcaf984b709",
+        "Linux root (x86-64)",
+    ),
+    ("933ac7e1-2eb4-4f13-b844-0e14e2aef915", "Linux home"),
+    ("48465300-0000-11aa-aa11-00306543ecac", "Apple HFS+"),
+    ("7c3457ef-0000-11aa-aa11-00306543ecac", "Apple APFS"),
+];
+
+pub fn known_guid(guid: &str) -> Option<&'static str> {
+    KNOWN_GUIDS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(guid))
+        .map(|&(_, name)| name)
+}
+
+// EOB
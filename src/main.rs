@@ -27,32 +27,239 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
-use anyhow::{Context, Result};
-use crossterm::event::{Event, KeyCode, KeyEvent};
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::style::Stylize;
 use crossterm::terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
 use crossterm::tty::IsTty;
 use crossterm::{cursor, execute, style, terminal, QueueableCommand};
 use float_pretty_print::PrettyPrintFloat;
+use std::collections::{BTreeSet, HashMap};
 use std::env::{self};
 use std::error::Error;
 use std::ffi::OsStr;
 use std::fmt::Write as fmtWrite;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::Write as ioWrite;
-use std::io::{stdout, Read, Seek, Stdout};
-use std::path::Path;
+use std::io::{stdout, BufWriter, Read, Seek, Stdout};
+use std::path::{Path, PathBuf};
 use std::process;
-
+use std::time::{Duration, Instant, SystemTime};
+
+mod browse;
+mod charset;
+mod clipboard;
+mod config;
+mod crc;
+mod datetime;
+mod edits;
+mod elf;
+mod endian;
+mod expr;
+mod hash;
+mod magic;
+mod overlay;
+mod prompt;
+mod regex;
+mod search;
+mod stats;
+mod strings;
+mod theme;
+mod transform;
+mod typed;
+mod xxd;
+
+use charset::Charset;
+use crc::{Crc, CrcParams};
+use edits::{Change, Edits, Origin};
+use overlay::{Overlay, OverlayItem, OverlayResult};
+use prompt::{Prompt, PromptResult};
+use strings::{Encoding, Scanner};
+use theme::{Style, Theme};
+use transform::Transform;
+use typed::ElemType;
 use Endiannes::*;
 
-#[derive(Debug, PartialEq, Eq)]
+// how the character pane shows control characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharMode {
+    Dot,    // as the placeholder
+    Glyph,  // as Unicode control pictures
+    Letter, // dimmed letter, as in ^A
+}
+
+// number base of the addresses in the left pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AddrBase {
+    Hex,
+    Dec,
+    Oct,
+}
+
+impl AddrBase {
+    fn from_name(name: &str) -> Option<AddrBase> {
+        match name {
+            "hex" => Some(AddrBase::Hex),
+            "dec" => Some(AddrBase::Dec),
+            "oct" => Some(AddrBase::Oct),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            AddrBase::Hex => "hexadecimal",
+            AddrBase::Dec => "decimal",
+            AddrBase::Oct => "octal",
+        }
+    }
+}
+
+// fixed-point formats for the info pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FixedPoint {
+    Q15,
+    Q16_16,
+    Q31,
+}
+
+impl FixedPoint {
+    fn from_name(name: &str) -> Option<FixedPoint> {
+        match name.to_lowercase().as_str() {
+            "q15" => Some(FixedPoint::Q15),
+            "q16.16" => Some(FixedPoint::Q16_16),
+            "q31" => Some(FixedPoint::Q31),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            FixedPoint::Q15 => "q15",
+            FixedPoint::Q16_16 => "q16.16",
+            FixedPoint::Q31 => "q31",
+        }
+    }
+
+    // bytes in the value
+    fn size(&self) -> usize {
+        match self {
+            FixedPoint::Q15 => 2,
+            _ => 4,
+        }
+    }
+
+    fn frac_bits(&self) -> i32 {
+        match self {
+            FixedPoint::Q15 => 15,
+            FixedPoint::Q16_16 => 16,
+            FixedPoint::Q31 => 31,
+        }
+    }
+
+    // with as many decimals as it takes to get the integer back
+    fn format(&self, value: f64) -> String {
+        let decimals = (self.frac_bits() as f64 * 2f64.log10()).ceil() as usize;
+        format!("{:.*}", decimals, value / 2f64.powi(self.frac_bits()))
+    }
+}
+
+// where the status bar goes, if anywhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusBar {
+    Off,
+    Top,
+    Bottom,
+}
+
+impl StatusBar {
+    fn from_name(name: &str) -> Option<StatusBar> {
+        match name {
+            "off" => Some(StatusBar::Off),
+            "top" => Some(StatusBar::Top),
+            "bottom" => Some(StatusBar::Bottom),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Endiannes {
     LittleEndian,
     BigEndian,
 }
 
+// what to do with the answer given at the prompt
+#[derive(Debug, PartialEq, Eq)]
+enum PromptKind {
+    Preview,
+    Command,
+    Goto,
+    Percent,
+    Search { forward: bool },
+    Extract,
+    // the range [start, end) to dump
+    Xxd(u64, u64),
+    Confirm(Confirm),
+}
+
+// actions that ask before going ahead
+#[derive(Debug, PartialEq, Eq)]
+enum Confirm {
+    // [start, end) and the value
+    Fill(u64, u64, u8),
+    Xor(u64, u64, Vec<u8>),
+    Truncate(u64),
+    Revert,
+    // overwrite an existing file; and go on with it, or not
+    WriteAs(PathBuf, bool),
+    // overwrite an existing file with [start, end)
+    Extract(PathBuf, u64, u64),
+    Xxd(PathBuf, u64, u64),
+    // quit without saving
+    Quit,
+}
+
+// what to do with the item selected in the overlay
+#[derive(Debug, PartialEq, Eq)]
+enum OverlayKind {
+    // file offsets of the listed items
+    Jump(Vec<u64>),
+    Info,
+    Digest,
+    Browse(PathBuf, Vec<browse::Entry>),
+    // suggested record sizes
+    Stride(Vec<u64>),
+}
+
+// [start, end) in the file
+type Range = (u64, u64);
+
 const HEX_PAGESIZE: usize = 4096;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+const READ_CHUNKSIZE: usize = 16 * HEX_PAGESIZE;
+
+// :identify looks for signatures in this range around the cursor
+const IDENTIFY_BEFORE: u64 = 256;
+const IDENTIFY_AFTER: u64 = 4096;
+// sample size for guessing the byte order
+const ENDIAN_SAMPLE: usize = 16 * 1024;
+// searching while typing looks no further than this
+const INCREMENTAL_WINDOW: u64 = 1024 * 1024;
+// number of searches remembered
+const HISTORY_SIZE: usize = 100;
+// narrower than this, not even 4 bytes per row fit with the addresses
+const MIN_WIDTH: u16 = 40;
+// default for :set undolevels
+const UNDO_LEVELS: usize = 1000;
+// filling more than this asks first
+const FILL_CONFIRM: u64 = 16 * 1024 * 1024;
+// copying more than this to the clipboard is refused
+const COPY_LIMIT: u64 = 4 * 1024 * 1024;
+// the same, for copying as an array literal
+const LITERAL_LIMIT: u64 = 64 * 1024;
+// value lines in the info pane, below the address
+const INFO_VALUE_LINES: usize = 5;
 
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -63,9 +270,24 @@ struct HexView {
 
     view_width: u16,
     view_height: u16,
+    view_top: u16, // screen row of the first row of the view
     leftpane_width: u16,
     centerpane_width: u16,
     rightpane_width: u16,
+    // bytes per row: 8, 16, 24 or 32
+    row_bytes: u64,
+    // bytes per group in the hex pane: 1, 2, 4 or 8
+    group_size: u64,
+    // groups show their bytes in the byte order, like a word
+    group_endian: bool,
+    // the centre pane shows bytes in binary, 8 digits each
+    bin_view: bool,
+    // bytes per row and group size to go back to, after the binary view
+    hex_layout: (u64, u64),
+    // byte columns that fit the terminal, and the first one shown; the
+    // hex and character panes scroll sideways when a row is too wide
+    view_cols: u64,
+    hscroll: u64,
 
     cursor_x: u16,
     cursor_y: u16,
@@ -77,27 +299,132 @@ struct HexView {
     offset: u64,
     page_address: u64,
     page: [u8; HEX_PAGESIZE],
+    edits: Edits,
+    // groups of changes that are undone as one
+    undo_stack: Vec<Vec<Change>>,
+    redo_stack: Vec<Vec<Change>>,
+    undo_levels: usize,
+
+    prompt: Option<(PromptKind, Prompt)>,
+    overlay: Option<(OverlayKind, Overlay)>,
+    message: Option<String>,
+    preview: Option<Transform>,
+    preview_range: Option<(u64, u64)>, // preview only this, in both panes
+    char_mode: CharMode,
+    charset: Charset, // of the character pane
+    addr_base: AddrBase,
+    status_bar: StatusBar,
+    // color bytes by class: zero, printable, 0xFF, and other bytes
+    byte_colors: bool,
+    // shade the row and the column of the cursor
+    crosshair: bool,
+    // highlight the bytes on screen that equal the byte at the cursor
+    same_bytes: bool,
+    // column offsets above the hexdump
+    ruler: bool,
+    ruler_y: Option<u16>, // where it is drawn, if there is room
+    info_pane: bool,      // the bottom pane with the decoded values
+    info_scroll: usize,   // first value line shown in the info pane
+    fixed_formats: Vec<FixedPoint>,
+    // where the view is in the file, along the right edge
+    scrollbar: bool,
+    // tint every other row, or every other group of this many rows
+    shade: u64,
+    // collapse rows that repeat the row above into a "*" line, like hexdump
+    squeeze: bool,
+    squeeze_rows: Vec<(u64, Option<u64>)>, // row on screen, and end of a run
+    squeeze_expanded: Option<u64>,         // repeated row shown, as it has the cursor
+    // runs of repeated rows found so far, for the edits version and
+    // bytes per row they hold for
+    squeeze_runs: Vec<Range>,
+    squeeze_runs_for: (u64, u64),
+    placeholder: char, // for non-printable bytes
+    tz: datetime::TimeZone,
+    stride: Option<u64>, // record size
+    symbols: Option<Vec<elf::Symbol>>,
+    crc_presets: Vec<crc::Preset>, // user defined, from the config file
+    theme: Theme,
+    themes: Vec<Theme>, // user defined, from the config file
+
+    // the selection runs from the anchor up to and including the cursor
+    selection_anchor: Option<u64>,
+    // first key of a two-key command
+    pending_key: Option<char>,
+    count: Option<u64>, // numeric prefix while it is being typed
+    word_size: u64,     // for moving by words: 2, 4 or 8 bytes
+    marks: HashMap<char, u64>,
+    registers: HashMap<char, Vec<u8>>, // yanked values; '"' is the default
+    register: Option<char>,            // given with '"' before Y, p or P
+    min_string: u64,                   // shortest string for 's' and 'S'
+    home_row: bool,                    // Home and End go to the start and end of the row
+    align: u64,                        // boundary for '{' and '}'
+    wrap_scan: bool,                   // searches wrap around the end of the file
+    search_match: Option<(u64, u64)>,  // highlighted, as [start, end)
+    highlight_search: bool,            // all matches on screen
+    search_scope: Option<(u64, u64)>,  // searches stay in [start, end)
+    search_job: Option<search::Job>,   // search running in the background
+    search_history: Vec<String>,       // oldest first
+    edit_mode: bool,                   // typing hex digits overwrites bytes
+    insert_mode: bool,                 // typing inserts bytes, when editing
+    read_only: bool,                   // editing and saving are off
+    open_read_only: bool,              // -R, for every file opened
+    low_nibble: bool,                  // the next digit is the low nibble
+    char_pane: bool,                   // the cursor is in the character pane
+    save_history: bool,                // in ~/.config/rhex/history
+    visible_matches: Vec<(u64, u64)>,
+    last_search: Option<search::Pattern>,
+    search_forward: bool, // direction of the last search
+    browse_hidden: bool,
+    // off when the byte order was given on the command line
+    suggest_endian: bool,
+
+    // size and mtime at last (re)load, to detect changes
+    file_stamp: Option<(u64, SystemTime)>,
+    autoreload: bool,
+    highlight_changes: bool,
+    // bytes that differ since the previous reload
+    changed: BTreeSet<u64>,
+
+    // typed array view
+    array_view: bool,
+    array_type: ElemType,
+    array_per_row: u64,
+    array_top: u64,     // row index of the top row
+    array_cursor: u64,  // element index
+    array_hscroll: u64, // first visible column
+    array_origin: u64,  // byte position in hex view when array view was entered
+    array_bit: u32,     // column of the cursor within the byte, in the bit view
+    msb0: bool,         // bit 0 is the most significant bit
 
     update_needed: bool,
+    // clear the screen before the next redraw
+    full_redraw: bool,
+    // what each row of the view shows on screen, so that redrawing
+    // only has to print the rows that changed
+    drawn_rows: Vec<Option<Vec<u8>>>,
+    // offset of the hexdump that is on screen
+    drawn_offset: Option<u64>,
+    quit: bool,
 }
 
 impl HexView {
     fn new() -> Self {
         let terminal_size = terminal::size().expect("unable to get terminal size");
 
-        if terminal_size.0 < 80 {
+        if terminal_size.0 < MIN_WIDTH {
             eprintln!("error: terminal is not wide enough");
             process::exit(1);
         }
-        let view_width = 80;
+        let view_width = terminal_size.0.min(80);
 
         if terminal_size.1 < 10 {
             eprintln!("error: terminal is not high enough");
             process::exit(1);
         }
-        // the hexdump view will be most of the screen
-        // we need 6 lines at the bottom for the info pane
-        let view_height = terminal_size.1 - 6;
+        // the hexdump view will be most of the screen, below the title
+        // we need 6 lines at the bottom for the info pane,
+        // plus one status line, and the status bar
+        let view_height = terminal_size.1 - 9;
 
         HexView {
             stdout: stdout(),
@@ -105,9 +432,17 @@ impl HexView {
             terminal_height: terminal_size.1,
             view_width,
             view_height,
+            view_top: 1,
             leftpane_width: 10,   // address: 8 + spacing: 2
-            centerpane_width: 50, // hex bytes: 8 * (2 + 1) * 2 + spacing: 2
+            centerpane_width: 50, // hex bytes: 16 * 2 + between: 15 + middle: 1 + spacing: 2
             rightpane_width: 17,  // ascii: 16 + spacing: 1
+            row_bytes: 16,
+            group_size: 1,
+            group_endian: false,
+            bin_view: false,
+            hex_layout: (16, 1),
+            view_cols: 16,
+            hscroll: 0,
             cursor_x: 0,
             cursor_y: 0,
             endian: LittleEndian,
@@ -117,38 +452,367 @@ impl HexView {
             offset: 0,
             page_address: 0,
             page: [0u8; HEX_PAGESIZE],
+            edits: Edits::default(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            undo_levels: UNDO_LEVELS,
+            prompt: None,
+            overlay: None,
+            message: None,
+            preview: None,
+            preview_range: None,
+            char_mode: CharMode::Dot,
+            charset: Charset::Ascii,
+            addr_base: AddrBase::Hex,
+            status_bar: StatusBar::Bottom,
+            crosshair: false,
+            same_bytes: false,
+            ruler: false,
+            ruler_y: None,
+            info_pane: true,
+            info_scroll: 0,
+            fixed_formats: vec![FixedPoint::Q15, FixedPoint::Q16_16, FixedPoint::Q31],
+            scrollbar: true,
+            shade: 0,
+            squeeze: false,
+            squeeze_rows: Vec::new(),
+            squeeze_expanded: None,
+            squeeze_runs: Vec::new(),
+            squeeze_runs_for: (0, 0),
+            // see https://no-color.org/
+            byte_colors: env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+            placeholder: '.',
+            tz: datetime::TimeZone::Local,
+            stride: None,
+            symbols: None,
+            crc_presets: Vec::new(),
+            theme: Theme::default(),
+            themes: Vec::new(),
+            selection_anchor: None,
+            pending_key: None,
+            count: None,
+            word_size: 4,
+            marks: HashMap::new(),
+            registers: HashMap::new(),
+            register: None,
+            min_string: 4,
+            home_row: false,
+            align: 512,
+            wrap_scan: true,
+            search_match: None,
+            highlight_search: false,
+            search_scope: None,
+            search_job: None,
+            search_history: Vec::new(),
+            edit_mode: false,
+            insert_mode: false,
+            read_only: false,
+            open_read_only: false,
+            low_nibble: false,
+            char_pane: false,
+            save_history: false,
+            visible_matches: Vec::new(),
+            last_search: None,
+            search_forward: true,
+            browse_hidden: false,
+            suggest_endian: true,
+            file_stamp: None,
+            autoreload: false,
+            highlight_changes: false,
+            changed: BTreeSet::new(),
+            array_view: false,
+            array_type: ElemType::U32,
+            array_per_row: 4,
+            array_top: 0,
+            array_cursor: 0,
+            array_hscroll: 0,
+            array_origin: 0,
+            array_bit: 0,
+            msb0: false,
             update_needed: false,
+            full_redraw: true,
+            drawn_rows: Vec::new(),
+            drawn_offset: None,
+            quit: false,
         }
     }
 
     fn load(&mut self, filename: &str) {
-        self.fd = Some(
-            File::open(filename)
-                .with_context(|| format!("failed to open '{}'", filename))
-                .unwrap(),
-        );
-
-        let metadata = std::fs::metadata(filename)
-            .with_context(|| format!("failed to stat() file '{}'", filename))
-            .unwrap();
-        self.filesize = metadata.len();
-
-        if self.filesize == 0 {
-            eprintln!("empty file: {}", filename);
+        if let Err(err) = self.open_file(filename) {
+            eprintln!("error: {}", err);
             process::exit(1);
         }
+    }
 
-        if self.filesize > u32::MAX as u64 {
-            // address will be printed extra-wide
-            self.leftpane_width = 10 + 2;
-        } else {
-            // address will be printed with 8 hex digits
-            self.leftpane_width = 8 + 2;
+    // (re)start viewing a file
+    fn open_file(&mut self, filename: &str) -> Result<(), String> {
+        let fd = File::open(filename)
+            .map_err(|err| format!("failed to open '{}': {}", filename, err))?;
+        let metadata = fd
+            .metadata()
+            .map_err(|err| format!("failed to stat() file '{}': {}", filename, err))?;
+        if metadata.is_dir() {
+            return Err(format!("{} is a directory", filename));
+        }
+        if metadata.len() == 0 {
+            return Err(format!("empty file: {}", filename));
+        }
+
+        // a file opened after a read-only one may well be writable
+        self.read_only = self.open_read_only || !is_writable(filename);
+        if self.read_only {
+            self.edit_mode = false;
+            self.insert_mode = false;
         }
+        self.fd = Some(fd);
+        self.filesize = metadata.len();
+        self.file_stamp = metadata
+            .modified()
+            .ok()
+            .map(|mtime| (metadata.len(), mtime));
+
+        self.leftpane_width = self.addr_digits() as u16 + 2;
 
         self.filename = Some(filename.to_owned());
 
+        self.offset = 0;
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        self.array_view = false;
+        self.edits = Edits::new(self.filesize);
+        self.squeeze_runs.clear();
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.selection_anchor = None;
+        self.changed.clear();
+        self.symbols = None;
+        self.stride = None;
+        self.marks.clear();
+        self.search_match = None;
+        self.search_scope = None;
+        self.search_job = None;
         self.page_fault(0);
+        self.fit_width();
+
+        let len = self.filesize.min(HEX_PAGESIZE as u64) as usize;
+        let mut notes = Vec::new();
+        if let Some(magic) = magic::identify(&self.page[..len]) {
+            notes.push(format!("file type: {}", magic.name));
+        }
+        if self.suggest_endian {
+            let sample = self.read_bytes(0, ENDIAN_SAMPLE);
+            match endian::guess(&sample) {
+                Some(BigEndian) if self.endian == LittleEndian => {
+                    notes.push("data looks big-endian, press B to switch".to_owned())
+                }
+                Some(LittleEndian) if self.endian == BigEndian => {
+                    notes.push("data looks little-endian, press L to switch".to_owned())
+                }
+                _ => {}
+            }
+        }
+        self.message = if notes.is_empty() {
+            None
+        } else {
+            Some(notes.join("; "))
+        };
+        Ok(())
+    }
+
+    // list a directory, to pick a file
+    fn open_browser(&mut self, dir: &Path) {
+        let dir = std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let entries = match browse::list(&dir, self.browse_hidden) {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.message = Some(format!("error: {}: {}", dir.display(), err));
+                return;
+            }
+        };
+
+        let name_width = (self.terminal_width as usize).saturating_sub(32).max(20);
+        let items = entries
+            .iter()
+            .map(|entry| {
+                let name = if entry.is_dir {
+                    format!("{}/", entry.name)
+                } else {
+                    entry.name.clone()
+                };
+                let size = if entry.is_dir {
+                    String::new()
+                } else {
+                    human_size(entry.size)
+                };
+                OverlayItem {
+                    text: format!("{:<name_width$} {:>12}  {}", name, size, entry.kind),
+                    key: entry.name.clone(),
+                    enabled: true,
+                }
+            })
+            .collect();
+        let title = format!("{}  ('.' toggles hidden files)", dir.display());
+        let overlay = Overlay::new(&title, items, false);
+        self.overlay = Some((OverlayKind::Browse(dir, entries), overlay));
+        self.update_needed = true;
+    }
+
+    fn browse_select(&mut self, dir: PathBuf, entry: &browse::Entry) {
+        if entry.is_dir {
+            self.open_browser(&entry.path);
+            return;
+        }
+        if !self.edits.is_empty() {
+            self.message = Some("there are unsaved changes".to_owned());
+            self.open_browser(&dir);
+            return;
+        }
+        if let Err(err) = self.open_file(&entry.path.to_string_lossy()) {
+            self.open_browser(&dir);
+            self.message = Some(format!("error: {}", err));
+        }
+    }
+
+    // Returns the errors and warnings, all of them
+    fn load_config(&mut self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let config = match config::load() {
+            Ok(config) => config,
+            Err(err) => {
+                problems.push(format!("error: {}", err));
+                return problems;
+            }
+        };
+        // [theme.NAME] before the options, that may pick one
+        for table in config.tables_under("theme") {
+            let (theme, warnings) = Theme::from_table(&table.path[1], table);
+            for warning in warnings {
+                problems.push(format!(
+                    "warning: config: theme {}: {}",
+                    theme.name, warning
+                ));
+            }
+            self.themes.push(theme);
+        }
+        // [options] like for :set
+        if let Some(table) = config.table("options") {
+            for (name, value) in &table.entries {
+                let word = match value {
+                    config::Value::Bool(true) => name.to_owned(),
+                    config::Value::Bool(false) => format!("no{}", name),
+                    config::Value::Int(n) => format!("{}={}", name, n),
+                    config::Value::Str(s) => format!("{}={}", name, s),
+                };
+                if let Err(err) = self.set_option(&word) {
+                    problems.push(format!("error: config: {}", err));
+                }
+            }
+        }
+        if self.save_history {
+            self.search_history = config::load_history();
+        }
+        for table in config.tables_under("crc") {
+            let name = &table.path[1];
+            match CrcParams::from_table(table) {
+                Ok(params) => self.crc_presets.push(crc::Preset {
+                    name: name.to_owned(),
+                    params,
+                }),
+                Err(err) => {
+                    problems.push(format!("error: config: crc preset {}: {}", name, err));
+                }
+            }
+        }
+        problems
+    }
+
+    // re-read the file from disk
+    // Returns false if it could not be read
+    fn reload(&mut self) -> bool {
+        let filename = self.filename.clone().unwrap();
+        let (fd, metadata) = match File::open(&filename).and_then(|fd| {
+            let metadata = fd.metadata()?;
+            Ok((fd, metadata))
+        }) {
+            Ok(result) => result,
+            Err(err) => {
+                self.message = Some(format!("error: {}: {}", filename, err));
+                return false;
+            }
+        };
+        if metadata.len() == 0 {
+            self.message = Some("file is empty, not reloaded".to_owned());
+            return false;
+        }
+
+        let old_page = self.page;
+        let old_address = self.page_address;
+        let old_len = self
+            .edits
+            .file_len()
+            .saturating_sub(old_address)
+            .min(HEX_PAGESIZE as u64);
+
+        self.fd = Some(fd);
+        self.edits.resize_file(metadata.len());
+        self.filesize = self.edits.data_len();
+        self.file_stamp = metadata
+            .modified()
+            .ok()
+            .map(|mtime| (metadata.len(), mtime));
+        self.symbols = None;
+        if let Some(anchor) = self.selection_anchor {
+            self.selection_anchor = Some(anchor.min(self.filesize - 1));
+        }
+        self.page_fault(old_address);
+
+        // compare against what was in the page cache
+        self.changed.clear();
+        if self.highlight_changes {
+            let new_len = self
+                .edits
+                .file_len()
+                .saturating_sub(old_address)
+                .min(HEX_PAGESIZE as u64);
+            let new_page = &self.page[..new_len as usize];
+            for (i, &byte) in new_page.iter().enumerate() {
+                if i as u64 >= old_len || byte != old_page[i] {
+                    self.changed.insert(old_address + i as u64);
+                }
+            }
+        }
+
+        if self.offset > self.end_offset() {
+            self.offset = self.end_offset();
+        }
+        let pos = self.cursor_pos();
+        if self.array_view {
+            self.array_cursor = self.array_cursor.min(self.array_count() - 1);
+            self.array_scroll_into_view();
+        } else if pos >= self.filesize
+            || self.offset + self.cursor_y as u64 * self.row_bytes >= self.filesize
+        {
+            self.jump_to(pos);
+        }
+        self.update_needed = true;
+        true
+    }
+
+    // reload when the file changed on disk
+    fn check_reload(&mut self) {
+        let filename = match &self.filename {
+            Some(filename) => filename,
+            None => return,
+        };
+        let stamp = std::fs::metadata(filename).ok().and_then(|metadata| {
+            metadata
+                .modified()
+                .ok()
+                .map(|mtime| (metadata.len(), mtime))
+        });
+        if stamp.is_some() && stamp != self.file_stamp {
+            self.reload();
+        }
     }
 
     fn page_fault(&mut self, address: u64) {
@@ -175,6 +839,16 @@ impl HexView {
     fn at(&mut self, address: u64) -> u8 {
         assert!(address < self.filesize);
 
+        match self.edits.origin(address) {
+            Origin::Added(value) => value,
+            Origin::File(offset) => self.file_byte(offset),
+        }
+    }
+
+    // byte at an offset in the file, through the page buffer
+    fn file_byte(&mut self, address: u64) -> u8 {
+        assert!(address < self.edits.file_len());
+
         if address >= self.page_address && address < self.page_address + HEX_PAGESIZE as u64 {
             return self.page[(address - self.page_address) as usize];
         }
@@ -185,16 +859,239 @@ impl HexView {
         self.page[(address - self.page_address) as usize]
     }
 
+    // read up to len bytes at pos, through the page buffer
+    fn read_bytes(&mut self, pos: u64, len: usize) -> Vec<u8> {
+        let end = pos.saturating_add(len as u64).min(self.filesize);
+        (pos..end).map(|addr| self.at(addr)).collect()
+    }
+
+    // feed the bytes in [start, end) to f in large chunks,
+    // bypassing the page buffer
+    // long reads show progress in the status line, and can be
+    // cancelled with Esc; that gives an error of kind Interrupted
+    fn read_range(
+        &mut self,
+        label: &str,
+        start: u64,
+        end: u64,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> std::io::Result<()> {
+        let mut buf = vec![0u8; READ_CHUNKSIZE];
+        let mut pos = start;
+        let mut last_update = Instant::now();
+        while pos < end {
+            let want = (end - pos).min(READ_CHUNKSIZE as u64) as usize;
+            let n = self
+                .edits
+                .read(self.fd.as_ref().unwrap(), pos, &mut buf[..want])?;
+            if n == 0 {
+                break;
+            }
+            f(&buf[..n]);
+            pos += n as u64;
+            self.progress(label, pos - start, end - start, &mut last_update)?;
+        }
+        Ok(())
+    }
+
+    // find the pattern after (or before) pos, wrapping around if enabled
+    // Returns the match, and whether the search wrapped
+    fn find(
+        &mut self,
+        pattern: &search::Pattern,
+        pos: u64,
+        forward: bool,
+    ) -> std::io::Result<Option<(u64, u64, bool)>> {
+        let spans = self.search_spans(pattern, pos, forward);
+        for (i, span) in spans.into_iter().enumerate() {
+            if let Some((found, len)) = self.find_range(pattern, span, forward)? {
+                return Ok(Some((found, len, i > 0)));
+            }
+        }
+        Ok(None)
+    }
+
+    // the first span holds matches that start after (or before) pos,
+    // the second one is for wrapping around
+    fn search_spans(
+        &self,
+        pattern: &search::Pattern,
+        pos: u64,
+        forward: bool,
+    ) -> Vec<search::Span> {
+        let n = pattern.max_len() as u64;
+        let (lo, hi) = self.search_scope.unwrap_or((0, self.filesize));
+        let (first, second) = if forward {
+            (
+                (lo.max(pos + 1), hi, hi),
+                (lo, hi.min(pos + n), hi.min(pos + 1)),
+            )
+        } else {
+            ((lo, hi.min(pos + n - 1), pos), (lo.max(pos), hi, hi))
+        };
+        if self.wrap_scan {
+            vec![first, second]
+        } else {
+            vec![first]
+        }
+    }
+
+    // search [start, end) for matches that start before limit
+    // Returns the match as (position, length)
+    fn find_range(
+        &mut self,
+        pattern: &search::Pattern,
+        span: search::Span,
+        forward: bool,
+    ) -> std::io::Result<Option<(u64, u64)>> {
+        let (start, end, _) = span;
+        if start >= end {
+            return Ok(None);
+        }
+        let mut done = 0;
+        let mut last_update = Instant::now();
+        let found = search::scan(pattern, span, forward, &mut |pos, buf| {
+            let n = self.edits.read(self.fd.as_ref().unwrap(), pos, buf)?;
+            done += n as u64;
+            self.progress("search", done, end - start, &mut last_update)?;
+            Ok(n)
+        })?;
+        Ok(found.map(|(found, len)| (found, len as u64)))
+    }
+
+    // like read_range, but f may stop the scan by returning a position
+    // Backward scans go from end down to start
+    // Returns the position found, if any
+    fn scan_range(
+        &mut self,
+        label: &str,
+        start: u64,
+        end: u64,
+        forward: bool,
+        f: &mut dyn FnMut(u64, &[u8]) -> Option<u64>,
+    ) -> std::io::Result<Option<u64>> {
+        let mut buf = vec![0u8; READ_CHUNKSIZE];
+        let mut done = 0;
+        let mut last_update = Instant::now();
+        while done < end - start {
+            let want = (end - start - done).min(READ_CHUNKSIZE as u64);
+            let pos = if forward {
+                start + done
+            } else {
+                end - done - want
+            };
+            let n = self
+                .edits
+                .read(self.fd.as_ref().unwrap(), pos, &mut buf[..want as usize])?;
+            if n == 0 {
+                break;
+            }
+            if let Some(found) = f(pos, &buf[..n]) {
+                return Ok(Some(found));
+            }
+            done += n as u64;
+            self.progress(label, done, end - start, &mut last_update)?;
+        }
+        Ok(None)
+    }
+
+    // show progress of a long operation every so often,
+    // and check whether the user cancelled it
+    fn progress(
+        &mut self,
+        label: &str,
+        done: u64,
+        total: u64,
+        last_update: &mut Instant,
+    ) -> std::io::Result<()> {
+        if last_update.elapsed() < PROGRESS_INTERVAL {
+            return Ok(());
+        }
+        *last_update = Instant::now();
+        let percent = done * 100 / total;
+        self.draw_progress(&format!("{}: {}%  (Esc to cancel)", label, percent));
+        if user_cancelled() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "cancelled",
+            ));
+        }
+        Ok(())
+    }
+
+    fn draw_progress(&mut self, text: &str) {
+        let width = self.terminal_width as usize;
+        let linebuf: String = format!("  {}", text).chars().take(width).collect();
+        self.stdout
+            .queue(cursor::MoveTo(0, self.terminal_height - 1))
+            .unwrap()
+            .queue(style::Print(format!("{:<width$}", linebuf, width = width)))
+            .unwrap();
+        self.stdout.flush().unwrap();
+    }
+
+    // selected range as [start, end)
+    fn selection(&self) -> Option<(u64, u64)> {
+        let anchor = self.selection_anchor?;
+        let pos = self.cursor_pos();
+        Some((anchor.min(pos), anchor.max(pos) + 1))
+    }
+
+    fn is_match(&self, pos: u64) -> bool {
+        self.search_match
+            .is_some_and(|(start, end)| (start..end).contains(&pos))
+    }
+
+    fn is_visible_match(&self, pos: u64) -> bool {
+        self.visible_matches
+            .iter()
+            .any(|&(start, end)| (start..end).contains(&pos))
+    }
+
+    // pos holds the same byte as the cursor
+    fn is_same_byte(&mut self, pos: u64) -> bool {
+        let cursor = self.cursor_pos();
+        self.same_bytes && pos != cursor && self.at(pos) == self.at(cursor)
+    }
+
+    fn is_selected(&self, pos: u64) -> bool {
+        self.selection()
+            .is_some_and(|(start, end)| (start..end).contains(&pos))
+    }
+
     fn draw_screen(&mut self) {
         if !self.update_needed {
             return;
         }
 
-        self.clearscreen();
+        if self.full_redraw {
+            self.clearscreen();
+        }
+
+        // only the file browser, until a file is opened
+        if self.fd.is_none() {
+            self.clearscreen();
+            self.draw_title();
+            self.draw_statusline();
+            self.draw_overlay();
+            self.stdout.flush().unwrap();
+            self.update_needed = false;
+            return;
+        }
 
+        if !self.array_view {
+            self.hscroll_to_cursor();
+        }
+
+        // the rows hold the cursor, and the bottom pane and status line
+        // are drawn over in place
+        self.draw_ruler();
         self.draw_hexdump();
         self.draw_bottom_pane();
-        self.draw_cursor();
+        self.draw_statusline();
+        if self.overlay.is_some() {
+            self.draw_overlay();
+        }
 
         self.stdout.flush().unwrap();
         self.update_needed = false;
@@ -206,604 +1103,5432 @@ impl HexView {
             .unwrap()
             .queue(cursor::MoveTo(0, 0))
             .unwrap();
+        self.forget_rows();
+        self.full_redraw = false;
+    }
+
+    // the screen no longer shows what the rows were drawn as
+    fn forget_rows(&mut self) {
+        self.drawn_rows.clear();
+        self.drawn_offset = None;
     }
 
     fn draw_hexdump(&mut self) {
+        if self.array_view {
+            self.drawn_offset = None;
+            for y in 0..self.view_height {
+                self.draw_array_line(y);
+            }
+            return;
+        }
+        if self.squeeze {
+            // rows do not move by a fixed amount; only changed rows are printed
+            self.drawn_offset = None;
+            self.squeeze_layout();
+        } else if let Some(drawn_offset) = self.drawn_offset {
+            self.scroll_view(drawn_offset);
+        }
+        if !self.squeeze {
+            self.drawn_offset = Some(self.offset);
+        }
+        self.find_visible_matches();
         for y in 0..self.view_height {
             self.draw_hexdump_line(y);
         }
     }
 
-    fn draw_hexdump_line(&mut self, y: u16) {
-        let mut linebuf = String::new();
-
-        let addr = self.offset + y as u64 * 16;
-        if addr >= self.filesize {
+    // the view scrolled by a few rows since it was drawn at drawn_offset;
+    // let the terminal move the rows that stay, so only the new rows
+    // have to be drawn
+    fn scroll_view(&mut self, drawn_offset: u64) {
+        let delta = self.offset.abs_diff(drawn_offset);
+        if delta == 0 || !delta.is_multiple_of(self.row_bytes) {
             return;
         }
-
-        // left pane: address (also known as: offset)
-        if self.filesize > u32::MAX as u64 {
-            write!(linebuf, "{:10X}", addr).unwrap();
-        } else {
-            write!(linebuf, "{:08X}", addr).unwrap();
+        let rows = delta / self.row_bytes;
+        if rows >= self.view_height as u64 {
+            return;
         }
-        write!(linebuf, "  ").unwrap();
+        let rows = rows as usize;
+        self.drawn_rows.resize(self.view_height as usize, None);
 
-        // middle pane: hex bytes (left side: 8 bytes)
-        for x in 0..8 {
-            let offset = addr + x;
-            if offset >= self.filesize {
-                write!(linebuf, "   ").unwrap();
-            } else {
-                write!(linebuf, "{:02X} ", self.at(offset)).unwrap();
-            }
+        // scroll only the region of the view, with DECSTBM
+        self.stdout
+            .queue(style::Print(format!(
+                "\x1b[{};{}r",
+                self.view_top + 1,
+                self.view_top + self.view_height
+            )))
+            .unwrap();
+        if self.offset > drawn_offset {
+            self.stdout.queue(terminal::ScrollUp(rows as u16)).unwrap();
+            self.drawn_rows.drain(..rows);
+            self.drawn_rows.resize(self.view_height as usize, None);
+        } else {
+            self.stdout
+                .queue(terminal::ScrollDown(rows as u16))
+                .unwrap();
+            self.drawn_rows.truncate(self.view_height as usize - rows);
+            self.drawn_rows.splice(0..0, vec![None; rows]);
         }
-        write!(linebuf, " ").unwrap();
+        self.stdout.queue(style::Print("\x1b[r")).unwrap();
+    }
 
-        // hex bytes (right side: 8 bytes)
-        for x in 0..8 {
-            let offset = addr + 8 + x;
-            if offset >= self.filesize {
-                write!(linebuf, "   ").unwrap();
-            } else {
-                write!(linebuf, "{:02X} ", self.at(offset)).unwrap();
-            }
+    // print the row at y, unless the screen already shows it
+    fn put_row(&mut self, y: u16, mut row: Vec<u8>) {
+        self.queue_scrollbar(&mut row, y);
+        let y = y as usize;
+        if self.drawn_rows.len() <= y {
+            self.drawn_rows.resize(y + 1, None);
         }
-        write!(linebuf, " ").unwrap();
-
-        // right pane: character view (16 bytes)
-        for x in 0..16 {
-            let mut c;
-            let offset = addr + x;
-            if offset >= self.filesize {
-                c = ' ';
-            } else {
-                c = self.at(offset) as char;
-                if !(c >= ' ' && c <= '~') {
-                    c = '.';
-                }
-            }
-            linebuf.push(c);
+        if self.drawn_rows[y].as_ref() == Some(&row) {
+            return;
         }
-        linebuf.push(' ');
-
         self.stdout
-            .queue(cursor::MoveTo(0, y as u16))
-            .unwrap()
-            .queue(style::Print(&linebuf))
+            .queue(cursor::MoveTo(0, self.view_top + y as u16))
             .unwrap();
-        linebuf.clear();
-    }
-
-    fn draw_bottom_pane(&mut self) {
-        let y = self.view_height; // screen position
-        let pos = self.offset + self.cursor_y as u64 * 16 + self.cursor_x as u64;
-
-        self.draw_info_address(y, pos);
-        self.draw_info_i8(y + 1, pos);
-        self.draw_info_i16(y + 2, pos);
-        self.draw_info_i32(y + 3, pos);
-        self.draw_info_i64(y + 4, pos);
-        self.draw_info_f32_f64_and_endianness(y + 5, pos);
+        self.stdout.write_all(&row).unwrap();
+        self.drawn_rows[y] = Some(row);
     }
 
-    fn draw_info_address(&mut self, y: u16, pos: u64) {
-        let mut linebuf = String::new();
-
-        if self.filesize > u32::MAX as u64 {
-            write!(
-                linebuf,
-                "  @0x{:10x}  {:<10}  @{:<24}  size: {}",
-                pos, " ", pos, self.filesize
+    // the cell of the scrollbar at row y, at the right edge of the view
+    // There is no scrollbar if the rows reach the edge
+    fn queue_scrollbar(&self, out: &mut Vec<u8>, y: u16) {
+        if !self.scrollbar {
+            return;
+        }
+        let (width, top, total) = if self.array_view {
+            (
+                self.array_addr_width()
+                    + self.array_per_row as usize * (self.array_type.width() + 2),
+                self.array_top,
+                self.array_count().div_ceil(self.array_per_row),
             )
-            .unwrap();
         } else {
-            write!(
-                linebuf,
-                "  @0x{:08x}  {:<12}  @{:<24}  size: {} ",
-                pos, " ", pos, self.filesize
+            (
+                (self.leftpane_width + self.centerpane_width + self.rightpane_width) as usize,
+                self.offset / self.row_bytes,
+                self.filesize.div_ceil(self.row_bytes),
             )
-            .unwrap();
+        };
+        if width >= self.view_width as usize {
+            return;
         }
-        self.stdout
-            .queue(cursor::MoveTo(0, y))
+        let (start, len) = scrollbar_thumb(top, total, self.view_height as u64);
+        let c = if (start..start + len).contains(&(y as u64)) {
+            self.theme.scrollbar_thumb.paint('█')
+        } else {
+            self.theme.scrollbar.paint('│')
+        };
+        out.queue(cursor::MoveToColumn(self.view_width - 1))
             .unwrap()
-            .queue(style::Print(&linebuf))
+            .queue(style::PrintStyledContent(c))
             .unwrap();
-        linebuf.clear();
     }
 
-    fn draw_info_i8(&mut self, y: u16, pos: u64) {
-        let mut linebuf = String::new();
-
-        if pos < self.filesize {
-            let data_i8 = self.at(pos) as i8;
-            let data_u8 = self.at(pos);
-            write!(
-                linebuf,
-                "  i8 : {:<20}  u8 : {:<20}  0x{:02x} ",
-                data_i8, data_u8, data_u8
-            )
-            .unwrap();
-        } else {
-            write!(linebuf, "  i8 : {:<20}  u8 : {:<20}  --   ", "--", "--").unwrap();
+    // matches of the last search that are on screen
+    fn find_visible_matches(&mut self) {
+        self.visible_matches.clear();
+        if !self.highlight_search {
+            return;
         }
-        self.stdout
-            .queue(cursor::MoveTo(0, y))
-            .unwrap()
-            .queue(style::Print(&linebuf))
-            .unwrap();
-        linebuf.clear();
+        let pattern = match self.last_search.take() {
+            Some(pattern) => pattern,
+            None => return,
+        };
+        let n = pattern.max_len() as u64;
+        let start = self.offset;
+        let end = self
+            .filesize
+            .min(start + self.view_height as u64 * self.row_bytes + n - 1);
+        let window: Vec<u8> = (start..end).map(|pos| self.at(pos)).collect();
+        self.visible_matches = pattern
+            .find_all(start, &window)
+            .map(|(i, len)| (start + i as u64, start + (i + len) as u64))
+            .collect();
+        self.last_search = Some(pattern);
     }
 
-    fn draw_info_i16(&mut self, y: u16, pos: u64) {
-        let mut linebuf = String::new();
-
-        if pos + 1 < self.filesize {
-            let bytes16 = [self.at(pos), self.at(pos + 1)];
-            let data_i16;
-            let data_u16;
-            if self.endian == LittleEndian {
-                data_i16 = i16::from_le_bytes(bytes16);
-                data_u16 = u16::from_le_bytes(bytes16);
-            } else {
-                data_i16 = i16::from_be_bytes(bytes16);
-                data_u16 = u16::from_be_bytes(bytes16);
+    fn draw_array_line(&mut self, y: u16) {
+        let mut row = Vec::new();
+        let (linebuf, cells) = match self.format_array_line(y) {
+            Some(line) => line,
+            None => {
+                row.queue(Clear(ClearType::UntilNewLine)).unwrap();
+                self.put_row(y, row);
+                return;
             }
-            write!(
-                linebuf,
-                "  i16: {:<20}  u16: {:<20}  0x{:04x} ",
-                data_i16, data_u16, data_u16
-            )
-            .unwrap();
+        };
+        row.queue(style::Print(&linebuf)).unwrap();
+
+        let first_idx = (self.array_top + y as u64) * self.array_per_row + self.array_hscroll;
+        for (i, value) in cells.into_iter().enumerate() {
+            let is_cursor = first_idx + i as u64 == self.array_cursor && self.overlay.is_none();
+            if is_cursor && self.array_type == ElemType::Bits {
+                // only the bit under the cursor
+                for (col, c) in value.chars().enumerate() {
+                    if col as u32 == self.array_bit {
+                        row.queue(style::PrintStyledContent(self.theme.cursor.paint(c)))
+                            .unwrap();
+                    } else {
+                        row.queue(style::Print(c)).unwrap();
+                    }
+                }
+            } else if is_cursor {
+                row.queue(style::PrintStyledContent(self.theme.cursor.paint(value)))
+                    .unwrap();
+            } else {
+                row.queue(style::Print(value)).unwrap();
+            }
+            row.queue(style::Print("  ")).unwrap();
+        }
+        row.queue(Clear(ClearType::UntilNewLine)).unwrap();
+        self.put_row(y, row);
+    }
+
+    // address part and the visible cells of a line in the array view
+    fn format_array_line(&mut self, y: u16) -> Option<(String, Vec<String>)> {
+        let size = self.array_type.size() as u64;
+        let row = self.array_top + y as u64;
+        let first = row * self.array_per_row;
+        let addr = first * size;
+        if addr >= self.filesize {
+            return None;
+        }
+
+        // left pane: byte offset and element index
+        let mut linebuf = String::new();
+        if self.filesize > u32::MAX as u64 {
+            write!(linebuf, "{:10X} {:>10}  ", addr, first).unwrap();
+        } else {
+            write!(linebuf, "{:08X} {:>8}  ", addr, first).unwrap();
+        }
+
+        let mut cells = Vec::new();
+        let width = self.array_type.width();
+        let big_endian = self.endian == BigEndian;
+        let mut bytes = [0u8; 8];
+        let (first_col, last_col) = self.array_visible_columns();
+        for idx in first + first_col..first + last_col {
+            let pos = idx * size;
+            let value = if pos >= self.filesize {
+                format!("{:width$}", "")
+            } else if pos + size > self.filesize {
+                // incomplete element at end of file
+                format!("{:>width$}", "--")
+            } else {
+                for i in 0..size {
+                    bytes[i as usize] = self.at(pos + i);
+                }
+                self.array_type.format(&bytes[..size as usize], big_endian)
+            };
+            cells.push(value);
+        }
+        Some((linebuf, cells))
+    }
+
+    // default number of elements per row: as many as fit in the view
+    fn array_fit_per_row(&self) -> u64 {
+        let fit =
+            (self.view_width as usize - self.array_addr_width()) / (self.array_type.width() + 2);
+        let max = 16 / self.array_type.size();
+        fit.clamp(1, max) as u64
+    }
+
+    fn array_addr_width(&self) -> usize {
+        if self.filesize > u32::MAX as u64 {
+            23
+        } else {
+            19
+        }
+    }
+
+    // range of columns that fit on screen, taking horizontal scroll into account
+    fn array_visible_columns(&self) -> (u64, u64) {
+        let avail = (self.terminal_width as usize).saturating_sub(self.array_addr_width());
+        let fit = (avail / (self.array_type.width() + 2)).max(1) as u64;
+        let first = self.array_hscroll;
+        (first, (first + fit).min(self.array_per_row))
+    }
+
+    fn array_count(&self) -> u64 {
+        self.filesize.div_ceil(self.array_type.size() as u64)
+    }
+
+    fn enter_array_view(&mut self) {
+        let pos = self.cursor_pos();
+        let cursor_y = self.cursor_y as u64;
+        self.array_origin = pos;
+        self.array_view = true;
+        self.array_bit = 0;
+        self.array_cursor = pos / self.array_type.size() as u64;
+        // keep the cursor on about the same screen row
+        let row = self.array_cursor / self.array_per_row;
+        self.array_top = row.saturating_sub(cursor_y);
+        self.array_scroll_into_view();
+        self.update_needed = true;
+    }
+
+    fn leave_array_view(&mut self) {
+        let size = self.array_type.size() as u64;
+        let mut pos = self.array_cursor * size;
+        // return to the same byte, if the cursor did not move off it
+        if self.array_origin >= pos && self.array_origin < pos + size {
+            pos = self.array_origin;
+        }
+        self.array_view = false;
+        self.update_needed = true;
+        self.jump_to(pos);
+    }
+
+    fn key_array_view(&mut self) {
+        if self.array_view {
+            self.leave_array_view();
+        } else {
+            self.enter_array_view();
+        }
+    }
+
+    // the bit view is the array view of bits
+    fn key_bit_view(&mut self) {
+        if self.array_view && self.array_type == ElemType::Bits {
+            self.leave_array_view();
+        } else {
+            self.command_array("bits");
+        }
+    }
+
+    // number of the bit under the cursor in the bit view
+    fn bit_number(&self) -> u32 {
+        if self.msb0 {
+            self.array_bit
+        } else {
+            7 - self.array_bit
+        }
+    }
+
+    // move the cursor by a number of bits
+    fn bit_move(&mut self, delta: i64) {
+        let last = self.array_count() * 8 - 1;
+        let bit = self.array_cursor * 8 + self.array_bit as u64;
+        let new_bit = if delta < 0 {
+            bit.saturating_sub(delta.unsigned_abs())
+        } else {
+            bit.saturating_add(delta as u64).min(last)
+        };
+        self.array_bit = (new_bit % 8) as u32;
+        if new_bit / 8 != self.array_cursor {
+            self.array_set_cursor(new_bit / 8);
+        } else if !self.update_needed {
+            let row = self.array_cursor / self.array_per_row;
+            self.draw_array_line((row - self.array_top) as u16);
+            self.draw_bottom_pane();
+            self.stdout.flush().unwrap();
+        }
+    }
+
+    // space flips the bit under the cursor in the bit view
+    fn key_flip_bit(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        self.toggle_bit(7 - self.array_bit);
+    }
+
+    fn array_scroll_into_view(&mut self) {
+        let row = self.array_cursor / self.array_per_row;
+        let height = self.view_height as u64;
+        if row < self.array_top {
+            self.array_top = row;
+        } else if row >= self.array_top + height {
+            self.array_top = row + 1 - height;
+        }
+
+        // horizontal scroll
+        let col = self.array_cursor % self.array_per_row;
+        let (first_col, last_col) = self.array_visible_columns();
+        if col < first_col {
+            self.array_hscroll = col;
+        } else if col >= last_col {
+            let fit = last_col - first_col;
+            self.array_hscroll = col + 1 - fit;
+        }
+    }
+
+    // move cursor by a number of elements
+    fn array_move(&mut self, delta: i64) {
+        let last = self.array_count() - 1;
+        let new_cursor = if delta < 0 {
+            self.array_cursor.saturating_sub(delta.unsigned_abs())
         } else {
-            write!(linebuf, "  i16: {:<20}  u16: {:<20}  --     ", "--", "--").unwrap();
+            self.array_cursor.saturating_add(delta as u64).min(last)
+        };
+        self.array_set_cursor(new_cursor);
+    }
+
+    fn array_set_cursor(&mut self, idx: u64) {
+        if idx == self.array_cursor {
+            return;
+        }
+        let old_row = self.array_cursor / self.array_per_row;
+        let old_top = self.array_top;
+        let old_hscroll = self.array_hscroll;
+        self.array_cursor = idx;
+        self.array_scroll_into_view();
+
+        if self.array_top != old_top || self.array_hscroll != old_hscroll {
+            self.update_needed = true;
+            return;
+        }
+        if !self.update_needed {
+            let new_row = self.array_cursor / self.array_per_row;
+            self.draw_array_line((old_row - self.array_top) as u16);
+            self.draw_array_line((new_row - self.array_top) as u16);
+            self.draw_bottom_pane();
+            self.stdout.flush().unwrap();
+        }
+    }
+
+    fn array_key_event(&mut self, key_event: &KeyEvent, count: u64) -> bool {
+        let count = count.min(i64::MAX as u64) as i64;
+        let per_row = self.array_per_row as i64;
+        let one_page = per_row * self.view_height as i64;
+        let bits = self.array_type == ElemType::Bits;
+        match key_event.code {
+            KeyCode::Right if bits => self.bit_move(count),
+            KeyCode::Left if bits => self.bit_move(count.saturating_neg()),
+            KeyCode::Char(' ') if bits => self.key_flip_bit(),
+            KeyCode::Right => self.array_move(count),
+            KeyCode::Left => self.array_move(count.saturating_neg()),
+            KeyCode::Down => self.array_move(per_row.saturating_mul(count)),
+            KeyCode::Up => self.array_move(per_row.saturating_mul(-count)),
+            KeyCode::PageDown => self.array_move(one_page.saturating_mul(count)),
+            KeyCode::PageUp => self.array_move(one_page.saturating_mul(-count)),
+            KeyCode::Char('d') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.array_move((one_page / 2).max(per_row).saturating_mul(count))
+            }
+            KeyCode::Char('u') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.array_move((one_page / 2).max(per_row).saturating_mul(-count))
+            }
+            KeyCode::Home => self.array_set_cursor(0),
+            KeyCode::End => self.array_set_cursor(self.array_count() - 1),
+            _ => return false,
+        }
+        true
+    }
+
+    // :array TYPE [PER_ROW]
+    fn command_array(&mut self, arg: &str) {
+        let mut words = arg.split_whitespace();
+        let elem_type = match words.next() {
+            None => self.array_type,
+            Some(name) => match ElemType::from_name(name) {
+                Some(elem_type) => elem_type,
+                None => {
+                    self.message = Some(format!("unknown element type: {}", name));
+                    return;
+                }
+            },
+        };
+        let per_row = match words.next() {
+            None => None,
+            Some(word) => match parse_number(word) {
+                Some(n) if n > 0 && n <= 256 => Some(n),
+                _ => {
+                    self.message = Some(format!("invalid number of elements per row: {}", word));
+                    return;
+                }
+            },
+        };
+
+        // keep the cursor on the same byte while changing layout
+        let pos = self.cursor_pos();
+        if self.array_view {
+            self.array_view = false;
+            self.jump_to(pos);
+        }
+        self.array_type = elem_type;
+        self.array_per_row = per_row.unwrap_or_else(|| self.array_fit_per_row());
+        self.array_hscroll = 0;
+        self.enter_array_view();
+    }
+
+    fn draw_hexdump_line(&mut self, y: u16) {
+        let mut row = Vec::new();
+        if let Some(run) = self.squeezed_row(y) {
+            row.queue(style::Print(self.format_squeezed_line(run)))
+                .unwrap()
+                .queue(Clear(ClearType::UntilNewLine))
+                .unwrap();
+            self.put_row(y, row);
+            return;
+        }
+        let addr = self.row_addr(y);
+        let shaded = self.is_shaded(addr);
+        if let Some(linebuf) = self.format_hexdump_line(y) {
+            if shaded {
+                row.queue(style::PrintStyledContent(self.theme.shade.paint(&linebuf)))
+                    .unwrap();
+            } else {
+                row.queue(style::Print(&linebuf)).unwrap();
+            }
+        }
+        row.queue(Clear(ClearType::UntilNewLine)).unwrap();
+
+        // overdraw highlighted bytes
+        let (first_col, last_col) = self.visible_columns();
+        let end = (addr + last_col).min(self.filesize);
+        for pos in addr + first_col..end {
+            if self.byte_colors
+                || shaded
+                || self.in_crosshair(pos)
+                || self.is_selected(pos)
+                || self.is_match(pos)
+                || self.is_visible_match(pos)
+                || self.is_same_byte(pos)
+                || self.is_modified(pos)
+                || self.changed.contains(&pos)
+                || self.is_previewed(pos)
+            {
+                self.queue_cell(&mut row, pos, false);
+            } else if self.char_mode == CharMode::Letter {
+                let byte = self.at(pos);
+                if self.is_dim_char(self.preview_byte(pos, byte, false)) {
+                    self.queue_cell(&mut row, pos, false);
+                }
+            }
+        }
+        let cursor = self.cursor_pos();
+        if self.overlay.is_none() && (addr + first_col..end).contains(&cursor) {
+            self.queue_cell(&mut row, cursor, true);
+        }
+        self.put_row(y, row);
+    }
+
+    // width of the addresses in the left pane
+    fn addr_digits(&self) -> usize {
+        let last = self.filesize.saturating_sub(1);
+        match self.addr_base {
+            // extra-wide, or 8 hex digits
+            AddrBase::Hex if self.filesize > u32::MAX as u64 => 10,
+            AddrBase::Hex => 8,
+            AddrBase::Dec => last.to_string().len().max(8),
+            AddrBase::Oct => format!("{:o}", last).len().max(8),
+        }
+    }
+
+    fn format_addr(&self, addr: u64) -> String {
+        let width = self.addr_digits();
+        match self.addr_base {
+            AddrBase::Hex if self.filesize > u32::MAX as u64 => format!("{:10X}", addr),
+            AddrBase::Hex => format!("{:08X}", addr),
+            AddrBase::Dec => format!("{:>width$}", addr),
+            AddrBase::Oct => format!("{:0width$o}", addr),
+        }
+    }
+
+    // change the base of the addresses; the panes shift along
+    fn set_addr_base(&mut self, addr_base: AddrBase) {
+        self.addr_base = addr_base;
+        self.leftpane_width = self.addr_digits() as u16 + 2;
+        self.layout_columns();
+        self.update_needed = true;
+    }
+
+    // cycle the addresses between hexadecimal, decimal and octal
+    fn key_cycle_addr_base(&mut self) {
+        let addr_base = match self.addr_base {
+            AddrBase::Hex => AddrBase::Dec,
+            AddrBase::Dec => AddrBase::Oct,
+            AddrBase::Oct => AddrBase::Hex,
+        };
+        self.set_addr_base(addr_base);
+        self.message = Some(format!("{} addresses", self.addr_base.name()));
+    }
+
+    // text of a line in the hexdump view; None past end of file
+    fn format_hexdump_line(&mut self, y: u16) -> Option<String> {
+        let mut linebuf = String::new();
+
+        let addr = self.row_addr(y);
+        if addr >= self.filesize {
+            return None;
+        }
+
+        // left pane: address (also known as: offset)
+        write!(linebuf, "{} ", self.format_addr(addr)).unwrap();
+
+        // middle pane: hex bytes, with an extra space every 8 bytes
+        // each group is preceded by a space, or a record separator
+        // Only the columns that fit are shown; past the end of the row
+        // they are blank
+        let (first_col, last_col) = self.visible_columns();
+        let end = (addr + last_col).min(self.filesize);
+        let view_end = first_col + self.view_cols;
+        for first in (first_col..view_end).step_by(self.group_size as usize) {
+            if first > first_col && first % 8 == 0 {
+                write!(linebuf, " ").unwrap();
+            }
+            if addr + first >= end {
+                write!(linebuf, " ").unwrap();
+            } else {
+                write!(linebuf, "{}", self.record_sep(addr + first)).unwrap();
+            }
+            for slot in 0..self.group_size {
+                let offset = addr + first + self.group_index(slot);
+                if offset >= end {
+                    write!(linebuf, "{:1$}", "", self.cell_digits() as usize).unwrap();
+                } else {
+                    let byte = self.at(offset);
+                    let byte = self.preview_byte(offset, byte, true);
+                    write!(linebuf, "{}", self.format_cell(byte)).unwrap();
+                }
+            }
+        }
+        write!(linebuf, "  ").unwrap();
+
+        // right pane: character view
+        for x in first_col..view_end {
+            let offset = addr + x;
+            let c = if offset >= end {
+                ' '
+            } else {
+                let byte = self.at(offset);
+                self.display_char(self.preview_byte(offset, byte, false))
+            };
+            linebuf.push(c);
+        }
+        linebuf.push(' ');
+        Some(linebuf)
+    }
+
+    // the row of pos is tinted; this goes by the row in the file,
+    // so that it stays put while scrolling
+    fn is_shaded(&self, pos: u64) -> bool {
+        self.shade > 0 && (pos / self.row_bytes / self.shade) % 2 == 1
+    }
+
+    fn key_cycle_shade(&mut self) {
+        self.shade = match self.shade {
+            0 => 1,
+            1 => 4,
+            _ => 0,
+        };
+        self.message = Some(match self.shade {
+            0 => "shading off".to_owned(),
+            1 => "shading every other row".to_owned(),
+            n => format!("shading every other {} rows", n),
+        });
+        self.update_needed = true;
+    }
+
+    // address of the row at screen row y
+    fn row_addr(&self, y: u16) -> u64 {
+        if self.squeeze {
+            return self
+                .squeeze_rows
+                .get(y as usize)
+                .map_or(self.filesize, |&(row, _)| row);
+        }
+        self.offset + y as u64 * self.row_bytes
+    }
+
+    // the run of repeated rows at screen row y, if it is collapsed
+    fn squeezed_row(&self, y: u16) -> Option<(u64, u64)> {
+        if !self.squeeze {
+            return None;
+        }
+        match self.squeeze_rows.get(y as usize) {
+            Some(&(row, Some(end))) => Some((row, end)),
+            _ => None,
+        }
+    }
+
+    fn format_squeezed_line(&self, (start, end): (u64, u64)) -> String {
+        format!(
+            "{:<width$}{}-{}  ({} rows)",
+            "*",
+            self.format_addr(start),
+            self.format_addr(end - 1),
+            (end - start) / self.row_bytes,
+            width = self.leftpane_width as usize
+        )
+    }
+
+    // lay out the rows on screen; rows that repeat the row above are
+    // collapsed, except at the top and where the cursor is
+    fn squeeze_layout(&mut self) {
+        let cursor_row = self.cursor_pos() / self.row_bytes * self.row_bytes;
+        self.squeeze_rows.clear();
+        self.squeeze_expanded = None;
+        let mut addr = self.offset;
+        while self.squeeze_rows.len() < self.view_height as usize && addr < self.filesize {
+            let repeat = addr != self.offset && self.is_repeat(addr);
+            if repeat && addr != cursor_row {
+                let mut end = self.run_end(addr);
+                if (addr..end).contains(&cursor_row) {
+                    end = cursor_row;
+                }
+                self.squeeze_rows.push((addr, Some(end)));
+                addr = end;
+                continue;
+            }
+            if repeat {
+                self.squeeze_expanded = Some(addr);
+            }
+            self.squeeze_rows.push((addr, None));
+            addr += self.row_bytes;
+        }
+    }
+
+    // the cursor moved into or out of a run, so the rows on screen
+    // have to be laid out anew
+    fn squeeze_stale(&self) -> bool {
+        let row = self.cursor_pos() / self.row_bytes * self.row_bytes;
+        if self
+            .squeeze_expanded
+            .is_some_and(|expanded| expanded != row)
+        {
+            return true;
+        }
+        !self.squeeze_rows.contains(&(row, None))
+    }
+
+    // the row at addr is the same as the row above it
+    // The rows are next to each other, so this stays in the page buffer
+    fn is_repeat(&mut self, row: u64) -> bool {
+        if row < self.row_bytes || row + self.row_bytes > self.filesize {
+            return false;
+        }
+        (0..self.row_bytes).all(|i| self.at(row + i) == self.at(row - self.row_bytes + i))
+    }
+
+    // end of the run of rows that repeat the row above row
+    fn run_end(&mut self, row: u64) -> u64 {
+        self.run_bounds(row).1
+    }
+
+    // first row of the run that row, a repeated row, is in; that is
+    // the row that is shown
+    fn run_start(&mut self, row: u64) -> u64 {
+        self.run_bounds(row).0
+    }
+
+    // the run of equal rows that row, a repeated row, is in
+    // Scanning a long run on every redraw is slow, so the runs are
+    // remembered until the data changes
+    fn run_bounds(&mut self, row: u64) -> Range {
+        let key = (self.edits.version(), self.row_bytes);
+        if self.squeeze_runs_for != key {
+            self.squeeze_runs.clear();
+            self.squeeze_runs_for = key;
+        }
+        if let Some(&run) = self
+            .squeeze_runs
+            .iter()
+            .find(|&&(start, end)| start < row && row < end)
+        {
+            return run;
+        }
+        match (self.scan_run_start(row), self.scan_run_end(row)) {
+            (Ok(start), Ok(end)) => {
+                self.squeeze_runs.push((start, end));
+                (start, end)
+            }
+            // cancelled; collapse only this row
+            _ => (row - self.row_bytes, row + self.row_bytes),
+        }
+    }
+
+    // Long runs are read in chunks, bypassing the page buffer
+    fn scan_run_end(&mut self, row: u64) -> std::io::Result<u64> {
+        let pattern = self.read_bytes(row - self.row_bytes, self.row_bytes as usize);
+        let row_bytes = self.row_bytes;
+        // a short last row is never a repeat
+        let last = self.filesize / row_bytes * row_bytes;
+        let found = self.scan_range("squeeze", row, last, true, &mut |addr, data| {
+            data.iter()
+                .enumerate()
+                .position(|(i, &byte)| byte != pattern[((addr + i as u64) % row_bytes) as usize])
+                .map(|i| addr + i as u64)
+        })?;
+        Ok(found.map_or(last, |differs| differs / row_bytes * row_bytes))
+    }
+
+    fn scan_run_start(&mut self, row: u64) -> std::io::Result<u64> {
+        let pattern = self.read_bytes(row, self.row_bytes as usize);
+        let row_bytes = self.row_bytes;
+        let found = self.scan_range("squeeze", 0, row, false, &mut |addr, data| {
+            data.iter()
+                .enumerate()
+                .rposition(|(i, &byte)| byte != pattern[((addr + i as u64) % row_bytes) as usize])
+                .map(|i| addr + i as u64)
+        })?;
+        Ok(found.map_or(0, |differs| differs / row_bytes * row_bytes + row_bytes))
+    }
+
+    fn record_sep(&self, pos: u64) -> char {
+        match self.stride {
+            Some(stride) if pos.is_multiple_of(stride) => '│',
+            _ => ' ',
+        }
+    }
+
+    // the byte as shown, through the preview transform
+    // Without a range, the preview is only in the character pane
+    fn preview_byte(&self, pos: u64, byte: u8, hex: bool) -> u8 {
+        match (&self.preview, self.preview_range) {
+            (Some(transform), Some((start, end))) if pos >= start && pos < end => {
+                transform.apply(byte, pos - start)
+            }
+            (Some(transform), None) if !hex => transform.apply(byte, pos),
+            _ => byte,
+        }
+    }
+
+    fn is_previewed(&self, pos: u64) -> bool {
+        self.preview.is_some()
+            && self
+                .preview_range
+                .is_some_and(|(start, end)| pos >= start && pos < end)
+    }
+
+    // character as shown in the right pane
+    fn display_char(&self, byte: u8) -> char {
+        if let Some(c) = self.charset.decode(byte) {
+            return c;
+        }
+        if !self.charset.is_ascii_based() || byte >= 0x80 {
+            // control pictures and letters are for ASCII
+            return self.placeholder;
+        }
+        match self.char_mode {
+            CharMode::Glyph if byte < 0x20 => char::from_u32(0x2400 + byte as u32).unwrap(),
+            CharMode::Glyph if byte == 0x7f => '\u{2421}',
+            CharMode::Letter if byte < 0x20 => (byte + 0x40) as char,
+            CharMode::Letter if byte == 0x7f => '?',
+            _ => self.placeholder,
+        }
+    }
+
+    // letters for control characters are shown dimmed
+    fn is_dim_char(&self, byte: u8) -> bool {
+        self.char_mode == CharMode::Letter
+            && self.charset.is_ascii_based()
+            && (byte < 0x20 || byte == 0x7f)
+    }
+
+    fn key_charset(&mut self) {
+        self.charset = self.charset.next();
+        self.message = Some(format!("character set: {}", self.charset.name()));
+        self.update_needed = true;
+    }
+
+    fn key_char_mode(&mut self) {
+        self.char_mode = match self.char_mode {
+            CharMode::Dot => CharMode::Glyph,
+            CharMode::Glyph => CharMode::Letter,
+            CharMode::Letter => CharMode::Dot,
+        };
+        self.update_needed = true;
+    }
+
+    // the next of the cursor styles; the other pane keeps its style
+    fn key_cycle_cursor(&mut self) {
+        let styles: Vec<Style> = theme::CURSOR_STYLES
+            .iter()
+            .map(|spec| Style::parse(spec).unwrap())
+            .collect();
+        let next = styles
+            .iter()
+            .position(|&style| style == self.theme.cursor)
+            .map_or(0, |idx| (idx + 1) % styles.len());
+        self.theme.cursor = styles[next];
+        self.message = Some(format!("cursor: {}", theme::CURSOR_STYLES[next]));
+        self.update_needed = true;
+    }
+
+    fn key_crosshair(&mut self) {
+        self.crosshair = !self.crosshair;
+        self.update_needed = true;
+    }
+
+    fn key_same_bytes(&mut self) {
+        self.same_bytes = !self.same_bytes;
+        self.update_needed = true;
+    }
+
+    // scroll the values in the info pane by a line, back to the top
+    // after the last one
+    fn key_scroll_info(&mut self) {
+        let values = self.format_info_values(self.cursor_pos()).len();
+        self.info_scroll = (self.info_scroll + 1) % (values - INFO_VALUE_LINES + 1);
+        self.draw_bottom_pane();
+        self.stdout.flush().unwrap();
+    }
+
+    fn key_info_pane(&mut self) {
+        self.info_pane = !self.info_pane;
+        self.resize(self.terminal_width, self.terminal_height);
+    }
+
+    fn key_ruler(&mut self) {
+        self.ruler = !self.ruler;
+        self.resize(self.terminal_width, self.terminal_height);
+    }
+
+    // the column offsets, lined up with the hex and character cells
+    fn draw_ruler(&mut self) {
+        let y = match self.ruler_y {
+            Some(y) => y,
+            None => return,
+        };
+        let mut linebuf = String::new();
+        if !self.array_view {
+            let width = (self.leftpane_width + self.centerpane_width) as usize;
+            let mut cells = vec![' '; width];
+            let digits = self.cell_digits() as usize;
+            let (first_col, last_col) = self.visible_columns();
+            for x in first_col as u16..last_col as u16 {
+                // as wide as the cell, right aligned
+                let label = format!("{:>digits$}", format!("{:02X}", x), digits = digits);
+                let hex_x = self.column_x(x) as usize;
+                for (i, c) in label.chars().enumerate() {
+                    cells[hex_x + i] = c;
+                }
+            }
+            linebuf.extend(cells);
+            linebuf.extend((first_col..last_col).map(|x| {
+                std::char::from_digit((x % 16) as u32, 16)
+                    .unwrap()
+                    .to_ascii_uppercase()
+            }));
         }
         self.stdout
             .queue(cursor::MoveTo(0, y))
             .unwrap()
-            .queue(style::Print(&linebuf))
+            .queue(style::PrintStyledContent(self.theme.ruler.paint(linebuf)))
+            .unwrap()
+            .queue(Clear(ClearType::UntilNewLine))
             .unwrap();
-        linebuf.clear();
     }
 
-    fn draw_info_i32(&mut self, y: u16, pos: u64) {
-        let mut linebuf = String::new();
-
-        let mut f32_value = String::new();
+    fn key_byte_colors(&mut self) {
+        self.byte_colors = !self.byte_colors;
+        self.update_needed = true;
+    }
+
+    fn draw_overlay(&mut self) {
+        // the overlay covers the rows
+        self.forget_rows();
+        if let Some((_, overlay)) = &self.overlay {
+            overlay.draw(
+                &mut self.stdout,
+                &self.theme,
+                0,
+                self.view_top,
+                self.terminal_width,
+                self.view_height,
+            );
+        }
+    }
+
+    fn draw_statusline(&mut self) {
+        let y = self.terminal_height - 1;
+        let width = self.terminal_width as usize;
+
+        self.stdout.queue(cursor::MoveTo(0, y)).unwrap();
+
+        if let Some((_, prompt)) = &self.prompt {
+            // draw the input with a block cursor
+            let before: String = prompt.input.chars().take(prompt.cursor).collect();
+            let under = prompt.input.chars().nth(prompt.cursor).unwrap_or(' ');
+            let after: String = prompt.input.chars().skip(prompt.cursor + 1).collect();
+            let used = prompt.label.chars().count() + prompt.input.chars().count() + 1;
+            self.stdout
+                .queue(style::Print(format!("{}{}", prompt.label, before)))
+                .unwrap()
+                .queue(style::PrintStyledContent(self.theme.cursor.paint(under)))
+                .unwrap()
+                .queue(style::Print(format!(
+                    "{}{:pad$}",
+                    after,
+                    "",
+                    pad = width.saturating_sub(used)
+                )))
+                .unwrap();
+            return;
+        }
+
+        let mut linebuf = String::new();
+        if let Some(message) = &self.message {
+            write!(linebuf, "  {}", message).unwrap();
+        } else {
+            if self.read_only {
+                write!(linebuf, "  [RO]").unwrap();
+            }
+            if self.array_view {
+                write!(
+                    linebuf,
+                    "  array: {} x {}",
+                    self.array_type.name(),
+                    self.array_per_row
+                )
+                .unwrap();
+                let (first_col, last_col) = self.array_visible_columns();
+                if first_col > 0 || last_col < self.array_per_row {
+                    write!(
+                        linebuf,
+                        "  columns {}-{} of {}",
+                        first_col,
+                        last_col - 1,
+                        self.array_per_row
+                    )
+                    .unwrap();
+                }
+            } else {
+                let (first_col, last_col) = self.visible_columns();
+                if first_col > 0 || last_col < self.row_bytes {
+                    write!(
+                        linebuf,
+                        "  columns {}-{} of {}",
+                        first_col,
+                        last_col - 1,
+                        self.row_bytes
+                    )
+                    .unwrap();
+                }
+            }
+            if let Some((start, end)) = self.selection() {
+                write!(
+                    linebuf,
+                    "  selection: {:08X}-{:08X} ({} bytes)",
+                    start,
+                    end - 1,
+                    end - start
+                )
+                .unwrap();
+            }
+            if let Some(transform) = &self.preview {
+                write!(linebuf, "  preview: {}", transform).unwrap();
+                if let Some((start, end)) = self.preview_range {
+                    write!(linebuf, " on {:08X}-{:08X}", start, end - 1).unwrap();
+                }
+            }
+            if self.edit_mode {
+                if self.insert_mode {
+                    write!(linebuf, "  -- INSERT --").unwrap();
+                } else {
+                    write!(linebuf, "  -- EDIT --").unwrap();
+                }
+            }
+            if self
+                .last_search
+                .as_ref()
+                .is_some_and(|pattern| pattern.nocase)
+            {
+                write!(linebuf, "  [i]").unwrap();
+            }
+            if self
+                .last_search
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_regex())
+            {
+                write!(linebuf, "  [r]").unwrap();
+            }
+            if let Some((start, end)) = self.search_scope {
+                write!(linebuf, "  search in {:08X}-{:08X}", start, end - 1).unwrap();
+            }
+            if let Some(encoding) = self
+                .last_search
+                .as_ref()
+                .and_then(|pattern| pattern.encoding())
+            {
+                write!(linebuf, "  [{}]", encoding).unwrap();
+            }
+            if let Some(stride) = self.stride {
+                let pos = self.cursor_pos();
+                write!(
+                    linebuf,
+                    "  record {}, field offset +0x{:02X}",
+                    pos / stride,
+                    pos % stride
+                )
+                .unwrap();
+            }
+        }
+        let linebuf: String = linebuf.chars().take(width).collect();
+        self.stdout
+            .queue(style::Print(format!("{:<width$}", linebuf, width = width)))
+            .unwrap();
+    }
+
+    fn draw_bottom_pane(&mut self) {
+        let y = self.view_top + self.view_height; // screen position
+
+        let width = self.terminal_width as usize;
+        let lines = if self.info_pane {
+            self.format_bottom_pane()
+        } else {
+            Vec::new()
+        };
+        for (i, linebuf) in lines.iter().enumerate() {
+            let linebuf: String = linebuf.chars().take(width).collect();
+            self.stdout
+                .queue(cursor::MoveTo(0, y + i as u16))
+                .unwrap()
+                .queue(style::Print(linebuf))
+                .unwrap()
+                .queue(Clear(ClearType::UntilNewLine))
+                .unwrap();
+        }
+        // the status bar follows the cursor, and the title the changes
+        self.draw_status_bar();
+        self.draw_title();
+    }
+
+    // the path and size of the file, and the program name
+    fn draw_title(&mut self) {
+        let width = self.terminal_width as usize;
+        let right = format!("  rhex {} ", env!("CARGO_PKG_VERSION"));
+
+        let mut info = String::new();
+        if self.fd.is_some() {
+            write!(info, "  {}", human_size(self.filesize)).unwrap();
+            if !self.edits.is_empty() {
+                write!(info, "  [+]").unwrap();
+            }
+        }
+        // shorten the path from the left, so the size stays visible
+        let path = self.filename.clone().unwrap_or_default();
+        let room = width.saturating_sub(info.chars().count() + right.chars().count() + 1);
+        let count = path.chars().count();
+        let path: String = if count > room {
+            std::iter::once('…')
+                .chain(path.chars().skip(count + 1 - room.max(1)))
+                .collect()
+        } else {
+            path
+        };
+        let left = format!(" {}{}", path, info);
+        let linebuf = format!(
+            "{:<room$}{}",
+            left,
+            right,
+            room = width.saturating_sub(right.len())
+        );
+        let linebuf: String = linebuf.chars().take(width).collect();
+        self.stdout
+            .queue(cursor::MoveTo(0, 0))
+            .unwrap()
+            .queue(style::PrintStyledContent(self.theme.title.paint(format!(
+                "{:<width$}",
+                linebuf,
+                width = width
+            ))))
+            .unwrap();
+    }
+
+    fn draw_status_bar(&mut self) {
+        let y = match self.status_bar {
+            StatusBar::Off => return,
+            StatusBar::Top => 1,
+            StatusBar::Bottom => self.terminal_height - 2,
+        };
+        let width = self.terminal_width as usize;
+        let pos = self.cursor_pos();
+
+        let mut right = String::new();
+        match self.addr_base {
+            AddrBase::Hex => write!(right, "  0x{:08X}", pos).unwrap(),
+            AddrBase::Dec => write!(right, "  {}", pos).unwrap(),
+            AddrBase::Oct => write!(right, "  0o{:o}", pos).unwrap(),
+        }
+        write!(right, "  {:>3}%", self.percent(pos)).unwrap();
+        if !self.info_pane {
+            // the most used values of the hidden info pane
+            write!(right, "  u8: {}", self.at(pos)).unwrap();
+            if pos + 3 < self.filesize {
+                let bytes32 = [
+                    self.at(pos),
+                    self.at(pos + 1),
+                    self.at(pos + 2),
+                    self.at(pos + 3),
+                ];
+                let data_u32 = if self.endian == LittleEndian {
+                    u32::from_le_bytes(bytes32)
+                } else {
+                    u32::from_be_bytes(bytes32)
+                };
+                write!(right, "  u32: {}", data_u32).unwrap();
+            }
+        }
+        let endian = if self.endian == LittleEndian {
+            "LE"
+        } else {
+            "BE"
+        };
+        let mode = if self.insert_mode {
+            "insert"
+        } else if self.edit_mode {
+            "edit"
+        } else if self.array_view {
+            "array"
+        } else if self.bin_view {
+            "binary"
+        } else {
+            "hex"
+        };
+        write!(right, "  {}  {}", endian, mode).unwrap();
+        if !self.array_view {
+            // where keys type into, and what the cursor style tells
+            let pane = if self.char_pane { "text" } else { "hex" };
+            write!(right, "  pane: {}", pane).unwrap();
+        }
+        if self.read_only {
+            write!(right, "  [RO]").unwrap();
+        }
+        if !self.edits.is_empty() {
+            write!(right, "  [+]").unwrap();
+        }
+        right.push(' ');
+
+        // shorten the name rather than the rest
+        let name = self.filename.as_deref().map_or(String::new(), |filename| {
+            Path::new(filename)
+                .file_name()
+                .map_or(filename.to_owned(), |name| {
+                    name.to_string_lossy().into_owned()
+                })
+        });
+        let room = width.saturating_sub(right.chars().count() + 1);
+        let name: String = if name.chars().count() > room {
+            name.chars()
+                .take(room.saturating_sub(1))
+                .chain(std::iter::once('…'))
+                .collect()
+        } else {
+            name
+        };
+        let linebuf = format!(" {:<room$}{}", name, right, room = room);
+        let linebuf: String = linebuf.chars().take(width).collect();
+        self.stdout
+            .queue(cursor::MoveTo(0, y))
+            .unwrap()
+            .queue(style::PrintStyledContent(
+                self.theme
+                    .status_bar
+                    .paint(format!("{:<width$}", linebuf, width = width)),
+            ))
+            .unwrap();
+    }
+
+    // how far pos is through the file; the last byte is at 100%
+    fn percent(&self, pos: u64) -> u128 {
+        let last = self.filesize.saturating_sub(1).max(1);
+        pos as u128 * 100 / last as u128
+    }
+
+    fn format_bottom_pane(&mut self) -> Vec<String> {
+        let pos = self.cursor_pos();
+
+        let mut address = self.format_info_address(pos);
+        write!(address, " align: {}", self.align).unwrap();
+        if let Some((start, end)) = self.search_match {
+            write!(address, " match: {} bytes", end - start).unwrap();
+        }
+        if let Some(count) = self.count {
+            write!(address, " count: {}", count).unwrap();
+        }
+        if self.charset != Charset::Ascii {
+            write!(address, " charset: {}", self.charset.name()).unwrap();
+        }
+        if self.array_view && self.array_type == ElemType::Bits {
+            write!(address, " bit: {}", self.bit_number()).unwrap();
+        }
+        if !self.edits.is_empty() {
+            // unsaved changes
+            write!(address, " [+]").unwrap();
+        }
+        // the pane has room for five of the values
+        let values = self.format_info_values(pos);
+        let mut lines = vec![address];
+        lines.extend(
+            values
+                .into_iter()
+                .skip(self.info_scroll)
+                .take(INFO_VALUE_LINES),
+        );
+        lines
+    }
+
+    // the lines of decoded values, that scroll in the info pane
+    fn format_info_values(&mut self, pos: u64) -> Vec<String> {
+        let (uleb, sleb) = self.format_info_leb128(pos);
+        let mut values = vec![
+            self.format_info_i8(pos),
+            self.format_info_i16(pos),
+            self.format_info_i32(pos),
+            self.format_info_i64(pos),
+            self.format_info_f32_f64_and_endianness(pos),
+            self.format_info_f16(pos),
+        ];
+        values.extend(self.format_info_bits(pos));
+        values.extend([
+            self.format_info_fixed(pos),
+            self.format_info_utf8(pos),
+            self.format_info_i128(pos),
+            self.format_info_u128(pos),
+            uleb,
+            sleb,
+            self.format_info_time(pos),
+        ]);
+        values.extend(self.format_info_uuid(pos));
+        // mark the word size
+        let word_line = match self.word_size {
+            2 => 1,
+            4 => 2,
+            _ => 3,
+        };
+        values[word_line].replace_range(0..1, ">");
+        values
+    }
+
+    fn format_info_address(&mut self, pos: u64) -> String {
+        let mut linebuf = String::new();
+
+        let percent = format!("{}%", self.percent(pos));
+        // the address in the base of the left pane comes first
+        let wide = self.filesize > u32::MAX as u64;
+        let (primary, secondary) = match self.addr_base {
+            AddrBase::Hex if wide => (format!("0x{:10x}", pos), pos.to_string()),
+            AddrBase::Hex => (format!("0x{:08x}", pos), pos.to_string()),
+            AddrBase::Dec => (pos.to_string(), format!("0x{:x}", pos)),
+            AddrBase::Oct => (format!("0o{:o}", pos), pos.to_string()),
+        };
+        if wide {
+            write!(
+                linebuf,
+                "  @{:<12}  {:<10}  @{:<24}  size: {}",
+                primary, percent, secondary, self.filesize
+            )
+            .unwrap();
+        } else {
+            write!(
+                linebuf,
+                "  @{:<10}  {:<12}  @{:<24}  size: {} ",
+                primary, percent, secondary, self.filesize
+            )
+            .unwrap();
+        }
+        linebuf
+    }
+
+    fn format_info_i8(&mut self, pos: u64) -> String {
+        let mut linebuf = String::new();
+
+        if pos < self.filesize {
+            let data_i8 = self.at(pos) as i8;
+            let data_u8 = self.at(pos);
+            write!(
+                linebuf,
+                "  i8 : {:<20}  u8 : {:<20}  0x{:02x}  0b{:04b}_{:04b} ",
+                data_i8,
+                data_u8,
+                data_u8,
+                data_u8 >> 4,
+                data_u8 & 0x0f
+            )
+            .unwrap();
+            if let Some(c) = self.charset.decode(data_u8) {
+                write!(linebuf, " '{}'", c).unwrap();
+                if self.charset != Charset::Ascii {
+                    write!(linebuf, " U+{:04X}", c as u32).unwrap();
+                }
+            }
+        } else {
+            write!(
+                linebuf,
+                "  i8 : {:<20}  u8 : {:<20}  --    --          ",
+                "--", "--"
+            )
+            .unwrap();
+        }
+        linebuf
+    }
+
+    // the 2 bytes at the cursor as IEEE 754 half precision, and as
+    // bfloat16, which is the top half of an f32
+    fn format_info_f16(&mut self, pos: u64) -> String {
+        if pos + 1 >= self.filesize {
+            return format!("  f16: {:<20}  bf16: --", "--");
+        }
+        let bytes16 = [self.at(pos), self.at(pos + 1)];
+        let bits = if self.endian == LittleEndian {
+            u16::from_le_bytes(bytes16)
+        } else {
+            u16::from_be_bytes(bytes16)
+        };
+        let half = f16_to_f32(bits);
+        // f16 subnormals are normal numbers as f32
+        let half_subnormal = bits & 0x7c00 == 0 && bits & 0x03ff != 0;
+        let bfloat = f32::from_bits((bits as u32) << 16);
+        format!(
+            "  f16: {:<20}  bf16: {}",
+            format_small_float(half, half_subnormal),
+            format_small_float(bfloat, bfloat.is_subnormal())
+        )
+    }
+
+    // the UTF-8 sequence that starts at the cursor, as a character
+    fn format_info_utf8(&mut self, pos: u64) -> String {
+        if pos >= self.filesize {
+            return "  utf8: --".to_owned();
+        }
+        let lead = self.at(pos);
+        let len = utf8_len(lead);
+        if lead & 0xc0 == 0x80 {
+            // a continuation byte; see if a lead byte before it reaches
+            let back = (1..=3.min(pos)).find(|&back| self.at(pos - back) & 0xc0 != 0x80);
+            return match back {
+                Some(back) if utf8_len(self.at(pos - back)) > back as usize => format!(
+                    "  utf8: continuation byte, the sequence starts {} byte{} back",
+                    back,
+                    if back == 1 { "" } else { "s" }
+                ),
+                _ => "  utf8: stray continuation byte".to_owned(),
+            };
+        }
+        if len == 0 {
+            return "  utf8: invalid UTF-8".to_owned();
+        }
+        if pos + len as u64 > self.filesize {
+            return "  utf8: --".to_owned();
+        }
+        let bytes: Vec<u8> = (0..len as u64).map(|i| self.at(pos + i)).collect();
+        let c = match std::str::from_utf8(&bytes) {
+            Ok(text) => text.chars().next().unwrap(),
+            Err(_) => return "  utf8: invalid UTF-8".to_owned(),
+        };
+        let shown = if c.is_control() {
+            String::new()
+        } else {
+            format!("'{}' ", c)
+        };
+        format!(
+            "  utf8: {}U+{:04X}  {}  {} byte{}",
+            shown,
+            c as u32,
+            char_class(c),
+            len,
+            if len == 1 { "" } else { "s" }
+        )
+    }
+
+    // N bytes at the cursor, in little endian order, so they can
+    // be read with from_le_bytes() whatever the endianness
+    fn info_bytes<const N: usize>(&mut self, pos: u64) -> Option<[u8; N]> {
+        if pos + N as u64 > self.filesize {
+            return None;
+        }
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.at(pos + i as u64);
+        }
+        if self.endian == BigEndian {
+            bytes.reverse();
+        }
+        Some(bytes)
+    }
+
+    // the byte at the cursor bit by bit, below the bit numbers,
+    // which go by :set msb0
+    fn format_info_bits(&mut self, pos: u64) -> [String; 2] {
+        if pos >= self.filesize {
+            return ["  bit :".to_owned(), "  bits: --".to_owned()];
+        }
+        let byte = self.at(pos);
+        let mut numbers = String::from("  bit :");
+        let mut bits = String::from("  bits:");
+        for i in 0..8 {
+            if i == 4 {
+                numbers.push(' ');
+                bits.push(' ');
+            }
+            let number = if self.msb0 { i } else { 7 - i };
+            write!(numbers, " {}", number).unwrap();
+            write!(bits, " {}", byte >> (7 - i) & 1).unwrap();
+        }
+        write!(bits, "  {} set", byte.count_ones()).unwrap();
+        [numbers, bits]
+    }
+
+    fn format_info_i16(&mut self, pos: u64) -> String {
+        match self.info_bytes::<2>(pos) {
+            Some(bytes16) => {
+                let data_u16 = u16::from_le_bytes(bytes16);
+                format!(
+                    "  i16: {:<20}  u16: {:<20}  0x{:04x} ",
+                    data_u16 as i16, data_u16, data_u16
+                )
+            }
+            None => format!("  i16: {:<20}  u16: {:<20}  --     ", "--", "--"),
+        }
+    }
+
+    fn format_info_i32(&mut self, pos: u64) -> String {
+        match self.info_bytes::<4>(pos) {
+            Some(bytes32) => {
+                let data_u32 = u32::from_le_bytes(bytes32);
+                format!(
+                    "  i32: {:<20}  u32: {:<20}  0x{:08x} ",
+                    data_u32 as i32, data_u32, data_u32
+                )
+            }
+            None => format!("  i32: {:<20}  u32: {:<20}  --         ", "--", "--"),
+        }
+    }
+
+    // the i16 and i32 at the cursor as fixed-point numbers, in the
+    // formats of :set fixed
+    fn format_info_fixed(&mut self, pos: u64) -> String {
+        let mut linebuf = String::new();
+        for format in self.fixed_formats.clone() {
+            let value = match format.size() {
+                2 => self
+                    .info_bytes::<2>(pos)
+                    .map(|bytes| i16::from_le_bytes(bytes) as f64),
+                _ => self
+                    .info_bytes::<4>(pos)
+                    .map(|bytes| i32::from_le_bytes(bytes) as f64),
+            };
+            let text = match value {
+                Some(value) => format.format(value),
+                None => "--".to_owned(),
+            };
+            write!(linebuf, "  {}: {:<14}", format.name(), text).unwrap();
+        }
+        linebuf
+    }
+
+    fn format_info_i64(&mut self, pos: u64) -> String {
+        let mut linebuf = String::new();
+
+        if pos + 7 < self.filesize {
+            let bytes64 = [
+                self.at(pos),
+                self.at(pos + 1),
+                self.at(pos + 2),
+                self.at(pos + 3),
+                self.at(pos + 4),
+                self.at(pos + 5),
+                self.at(pos + 6),
+                self.at(pos + 7),
+            ];
+            let data_i64;
+            let data_u64;
+            if self.endian == LittleEndian {
+                data_i64 = i64::from_le_bytes(bytes64);
+                data_u64 = u64::from_le_bytes(bytes64);
+            } else {
+                data_i64 = i64::from_be_bytes(bytes64);
+                data_u64 = u64::from_be_bytes(bytes64);
+            }
+            write!(
+                linebuf,
+                "  i64: {:<20}  u64: {:<20}  0x{:016x} ",
+                data_i64, data_u64, data_u64
+            )
+            .unwrap();
+        } else {
+            write!(
+                linebuf,
+                "  i64: {:<20}  u64: {:<20}  --                 ",
+                "--", "--",
+            )
+            .unwrap();
+        }
+        linebuf
+    }
+
+    // i128 and u128 do not fit on one line, so they have a line each
+    fn format_info_i128(&mut self, pos: u64) -> String {
+        match self.info_bytes::<16>(pos) {
+            Some(bytes128) => format!("  i128: {}", i128::from_le_bytes(bytes128)),
+            None => "  i128: --".to_owned(),
+        }
+    }
+
+    fn format_info_u128(&mut self, pos: u64) -> String {
+        match self.info_bytes::<16>(pos) {
+            Some(bytes128) => format!("  u128: {}", u128::from_le_bytes(bytes128)),
+            None => "  u128: --".to_owned(),
+        }
+    }
+
+    // the bytes at the cursor as a LEB128 varint, unsigned on one line,
+    // and signed and zigzag on the other
+    fn format_info_leb128(&mut self, pos: u64) -> (String, String) {
+        let avail = (self.filesize - pos).min(10) as usize;
+        let bytes: Vec<u8> = (0..avail as u64).map(|i| self.at(pos + i)).collect();
+        match decode_leb128(&bytes) {
+            Some(Ok((value, len))) => {
+                let zigzag = (value >> 1) as i64 ^ -((value & 1) as i64);
+                (
+                    format!(
+                        "  uleb: {:<20}  {} byte{}",
+                        value,
+                        len,
+                        if len == 1 { "" } else { "s" }
+                    ),
+                    format!(
+                        "  sleb: {:<20}  zigzag: {}",
+                        sign_extend_leb128(value, len),
+                        zigzag
+                    ),
+                )
+            }
+            Some(Err(())) => (
+                "  uleb: invalid varint".to_owned(),
+                "  sleb: invalid varint".to_owned(),
+            ),
+            // it runs past the end of the file
+            None => ("  uleb: --".to_owned(), "  sleb: --".to_owned()),
+        }
+    }
+
+    // the 4 and 8 bytes at the cursor as Unix time in seconds, and
+    // the 8 bytes in milliseconds
+    fn format_info_time(&mut self, pos: u64) -> String {
+        let avail = (self.filesize - pos).min(8) as usize;
+        let mut bytes = [0u8; 8];
+        for (i, byte) in bytes.iter_mut().enumerate().take(avail) {
+            *byte = self.at(pos + i as u64);
+        }
+        let (value32, value64) = if self.endian == LittleEndian {
+            (
+                u32::from_le_bytes(bytes[..4].try_into().unwrap()),
+                u64::from_le_bytes(bytes),
+            )
+        } else {
+            (
+                u32::from_be_bytes(bytes[..4].try_into().unwrap()),
+                u64::from_be_bytes(bytes),
+            )
+        };
+        let show = |size: usize, secs: i64| {
+            if avail < size {
+                "--".to_owned()
+            } else {
+                datetime::format_iso_likely(secs).unwrap_or("unlikely".to_owned())
+            }
+        };
+        format!(
+            "  t32: {:<20}  t64: {:<20}  ms: {}",
+            show(4, value32 as i64),
+            show(8, value64 as i64),
+            show(8, (value64 as i64).div_euclid(1000))
+        )
+    }
+
+    // the 16 bytes at the cursor as a UUID in the byte order of
+    // RFC 4122, and as a Microsoft GUID, with the first three groups
+    // in little endian. A well-known one is marked with '*'
+    fn format_info_uuid(&mut self, pos: u64) -> [String; 2] {
+        if pos + 15 >= self.filesize {
+            return ["  uuid: --".to_owned(), "  guid: --".to_owned()];
+        }
+        let mut bytes = [0u8; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.at(pos + i as u64);
+        }
+        let uuid = format_uuid(&bytes);
+        bytes[0..4].reverse();
+        bytes[4..6].reverse();
+        bytes[6..8].reverse();
+        let guid = format_uuid(&bytes);
+        [("uuid", uuid), ("guid", guid)].map(|(label, text)| match magic::known_guid(&text) {
+            Some(name) => format!("* {}: {}  {}", label, text, name),
+            None => format!("  {}: {}", label, text),
+        })
+    }
+
+    fn format_info_f32_f64_and_endianness(&mut self, pos: u64) -> String {
+        let mut linebuf = String::new();
+
+        let mut f32_value = String::new();
+
+        if pos + 3 < self.filesize {
+            let bytes32 = [
+                self.at(pos),
+                self.at(pos + 1),
+                self.at(pos + 2),
+                self.at(pos + 3),
+            ];
+            let data_f32 = if self.endian == LittleEndian {
+                f32::from_le_bytes(bytes32)
+            } else {
+                f32::from_be_bytes(bytes32)
+            };
+            write!(f32_value, "{:20.20}", PrettyPrintFloat(data_f32 as f64)).unwrap();
+        } else {
+            write!(f32_value, "--").unwrap();
+        }
+
+        let mut f64_value = String::new();
+
+        if pos + 7 < self.filesize {
+            let bytes64 = [
+                self.at(pos),
+                self.at(pos + 1),
+                self.at(pos + 2),
+                self.at(pos + 3),
+                self.at(pos + 4),
+                self.at(pos + 5),
+                self.at(pos + 6),
+                self.at(pos + 7),
+            ];
+
+            let data_f64 = if self.endian == LittleEndian {
+                f64::from_le_bytes(bytes64)
+            } else {
+                f64::from_be_bytes(bytes64)
+            };
+            write!(f64_value, "{:20.20}", PrettyPrintFloat(data_f64)).unwrap();
+        } else {
+            write!(f64_value, "--").unwrap();
+        }
+
+        let s_endian = if self.endian == LittleEndian {
+            "little"
+        } else {
+            "big"
+        };
+        write!(
+            linebuf,
+            "  f32: {:<20}  f64: {:<20}  {} endian   ",
+            f32_value, f64_value, s_endian
+        )
+        .unwrap();
+        linebuf
+    }
+
+    // screen position of the hex cell and character cell of pos
+    // pos must be in view
+    fn cell_xy(&self, pos: u64) -> (u16, u16, u16) {
+        let rel = pos - self.offset;
+        let x = (rel % self.row_bytes) as u16;
+        let y = self.screen_y(pos);
+        let char_x = self.leftpane_width + self.centerpane_width + x - self.hscroll as u16;
+        (self.column_x(x), char_x, y)
+    }
+
+    // screen row of pos, which must be in view
+    fn screen_y(&self, pos: u64) -> u16 {
+        let row = pos / self.row_bytes * self.row_bytes;
+        if self.squeeze {
+            if let Some(y) = self.squeeze_rows.iter().position(|&(start, end)| {
+                start == row || end.is_some_and(|end| (start..end).contains(&row))
+            }) {
+                return y as u16;
+            }
+        }
+        ((row - self.offset) / self.row_bytes) as u16
+    }
+
+    // screen column of the hex cell of byte x in a row, which must be
+    // one of the visible columns
+    fn column_x(&self, x: u16) -> u16 {
+        // groups are 2 hex digits per byte plus a space wide, and there
+        // is an extra space every 8 bytes
+        let size = self.group_size as u16;
+        let slot = self.group_index(x as u64 % self.group_size) as u16;
+        let digits = self.cell_digits();
+        let first = self.hscroll as u16;
+        let rel = x - first;
+        self.leftpane_width + rel / size * (size * digits + 1) + slot * digits + x / 8 - first / 8
+    }
+
+    // (over)draw a single byte in both the hex and the character pane
+    fn draw_cell(&mut self, pos: u64, is_cursor: bool) {
+        let (first_col, last_col) = self.visible_columns();
+        if !(first_col..last_col).contains(&(pos % self.row_bytes)) {
+            return;
+        }
+        let (_, _, y) = self.cell_xy(pos);
+        if self.squeezed_row(y).is_some() {
+            // the byte is not on screen, but the rows may be laid out anew
+            self.update_needed = true;
+            return;
+        }
+        let mut cell = Vec::new();
+        cell.queue(cursor::MoveTo(0, self.view_top + y)).unwrap();
+        self.queue_cell(&mut cell, pos, is_cursor);
+        self.stdout.write_all(&cell).unwrap();
+        // the row on screen now differs from how it was drawn
+        if let Some(row) = self.drawn_rows.get_mut(y as usize) {
+            *row = None;
+        }
+    }
+
+    // queue a cell at its column, on the current row
+    fn queue_cell(&mut self, out: &mut Vec<u8>, pos: u64, is_cursor: bool) {
+        let (hex_x, char_x, _) = self.cell_xy(pos);
+        let byte = self.at(pos);
+        let char_byte = self.preview_byte(pos, byte, false);
+        let hex_byte = self.preview_byte(pos, byte, true);
+        let mut hex = self.format_cell(hex_byte).stylize();
+        let mut c = format!("{}", self.display_char(char_byte)).stylize();
+
+        // highlights below override the class color
+        if self.byte_colors {
+            let printable = self.charset.decode(hex_byte).is_some();
+            hex = self.theme.byte_class(hex_byte, printable).apply(hex);
+            let printable = self.charset.decode(char_byte).is_some();
+            c = self.theme.byte_class(char_byte, printable).apply(c);
+        }
+
+        if is_cursor {
+            // the other pane shows where the cursor is, too
+            if self.char_pane {
+                hex = self.theme.cursor_other.apply(hex);
+                c = self.theme.cursor.apply(c);
+            } else {
+                hex = self.theme.cursor.apply(hex);
+                c = self.theme.cursor_other.apply(c);
+            }
+        } else {
+            // highlights have a background of their own
+            if self.is_shaded(pos) {
+                hex = self.theme.shade.apply(hex);
+                c = self.theme.shade.apply(c);
+            }
+            if self.is_dim_char(char_byte) {
+                c = self.theme.dim_char.apply(c);
+            }
+            // modified bytes keep their color, also when highlighted
+            let modified = self.is_modified(pos);
+            if modified {
+                hex = self.theme.modified.apply(hex);
+                c = self.theme.modified.apply(c);
+            }
+            let highlight = if self.is_selected(pos) {
+                Some(self.theme.selection)
+            } else if self.is_match(pos) && modified {
+                Some(self.theme.search_match.background())
+            } else if self.is_match(pos) {
+                Some(self.theme.search_match)
+            } else if self.is_visible_match(pos) {
+                Some(self.theme.visible_match)
+            } else if self.is_same_byte(pos) && modified {
+                Some(self.theme.same_byte.background())
+            } else if self.is_same_byte(pos) {
+                Some(self.theme.same_byte)
+            } else if !modified && self.changed.contains(&pos) {
+                Some(self.theme.changed)
+            } else if !modified && self.is_previewed(pos) {
+                Some(self.theme.preview)
+            } else {
+                None
+            };
+            if let Some(style) = highlight {
+                hex = style.apply(hex);
+                c = style.apply(c);
+            }
+            // the crosshair shades what has no background of its own
+            if self.in_crosshair(pos)
+                && !self.is_selected(pos)
+                && !self.is_match(pos)
+                && !self.is_visible_match(pos)
+                && !self.is_same_byte(pos)
+            {
+                hex = self.theme.crosshair.apply(hex);
+                c = self.theme.crosshair.apply(c);
+            }
+        }
+
+        out.queue(cursor::MoveToColumn(hex_x))
+            .unwrap()
+            .queue(style::PrintStyledContent(hex))
+            .unwrap()
+            .queue(cursor::MoveToColumn(char_x))
+            .unwrap()
+            .queue(style::PrintStyledContent(c))
+            .unwrap();
+    }
+
+    // the byte differs from the file; a byte that was set back to
+    // what it was, is not modified
+    fn is_modified(&mut self, pos: u64) -> bool {
+        match self.edits.origin(pos) {
+            Origin::File(_) => false,
+            Origin::Added(value) => match self.edits.base(pos) {
+                Some(offset) => value != self.file_byte(offset),
+                None => true,
+            },
+        }
+    }
+
+    fn erase_cursor(&mut self) {
+        // erase cursor via overdraw
+        let pos = self.cursor_pos();
+        self.draw_cell(pos, false);
+    }
+
+    fn draw_cursor(&mut self) {
+        let pos = self.cursor_pos();
+        assert!(pos < self.filesize);
+        if self.crosshair || self.same_bytes {
+            // the crosshair and the same bytes move along; only rows that
+            // changed are printed
+            for y in 0..self.view_height {
+                self.draw_hexdump_line(y);
+            }
+            return;
+        }
+        // draw cursor via overdraw
+        self.draw_cell(pos, true);
+    }
+
+    // pos is in the row or the column of the cursor
+    fn in_crosshair(&self, pos: u64) -> bool {
+        if !self.crosshair {
+            return false;
+        }
+        let cursor = self.cursor_pos();
+        pos / self.row_bytes == cursor / self.row_bytes
+            || pos % self.row_bytes == cursor % self.row_bytes
+    }
+
+    fn key_event(&mut self, key_event: &KeyEvent) {
+        let old_pos = self.cursor_pos();
+        self.dispatch_key_event(key_event);
+
+        // the selection follows the cursor
+        if self.selection_anchor.is_some() && self.cursor_pos() != old_pos {
+            self.update_needed = true;
+        }
+    }
+
+    fn dispatch_key_event(&mut self, key_event: &KeyEvent) {
+        if self.prompt.is_some() {
+            self.prompt_key_event(key_event);
+            return;
+        }
+        if self.overlay.is_some() {
+            self.overlay_key_event(key_event);
+            return;
+        }
+        if self.edit_mode && self.edit_key_event(key_event) {
+            return;
+        }
+
+        let mut key_event = vim_key(key_event);
+        if self.pending_key == Some('g') {
+            self.pending_key = None;
+            if key_event.code != KeyCode::Char('g') {
+                return;
+            }
+            // gg goes to the start of the file
+            key_event = KeyEvent::new(KeyCode::Home, KeyModifiers::NONE);
+        }
+        let key_event = &key_event;
+
+        // a count before a movement key repeats it
+        if let KeyCode::Char(c @ '0'..='9') = key_event.code {
+            if key_event.modifiers.is_empty() && (c != '0' || self.count.is_some()) {
+                let digit = c as u64 - '0' as u64;
+                let count = self.count.unwrap_or(0);
+                // a count that would overflow stays as it was
+                self.count = Some(
+                    count
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(digit))
+                        .unwrap_or(count),
+                );
+                self.draw_bottom_pane();
+                self.stdout.flush().unwrap();
+                return;
+            }
+        }
+        let had_count = self.count.is_some();
+        let count = self.count.take().unwrap_or(1);
+        let register = self.register.take();
+        if had_count && key_event.code == KeyCode::Esc {
+            // cancel the count
+            self.draw_bottom_pane();
+            self.stdout.flush().unwrap();
+            return;
+        }
+
+        if self.array_view && self.array_key_event(key_event, count) {
+            return;
+        }
+
+        if self.message.is_some() {
+            self.message = None;
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+        }
+
+        // highlighted changes fade after navigating
+        if !self.changed.is_empty() && is_movement_key(key_event) {
+            self.changed.clear();
+            self.update_needed = true;
+        }
+
+        if let Some(prefix) = self.pending_key.take() {
+            match (prefix, key_event.code) {
+                (']', KeyCode::Char('c')) => self.key_next_change(true),
+                ('[', KeyCode::Char('c')) => self.key_next_change(false),
+                (']', KeyCode::Char('r')) => self.key_next_record(true),
+                ('[', KeyCode::Char('r')) => self.key_next_record(false),
+                ('m', KeyCode::Char(c)) if c.is_ascii_alphabetic() => self.set_mark(c),
+                ('\'', KeyCode::Char(c)) if c.is_ascii_alphabetic() => self.goto_mark(c),
+                ('"', KeyCode::Char(c)) if c.is_ascii_lowercase() || c == '"' => {
+                    self.register = Some(c)
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match key_event.code {
+            KeyCode::Char('g') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.open_prompt(PromptKind::Goto, "goto: ")
+            }
+            KeyCode::Char('d') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.key_half_page(true, count)
+            }
+            KeyCode::Char('u') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.key_half_page(false, count)
+            }
+            KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.save()
+            }
+            KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.redo()
+            }
+            KeyCode::Char('a') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.key_increment(count, true)
+            }
+            KeyCode::Char('x') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.key_increment(count, false)
+            }
+            KeyCode::Esc if self.search_job.is_some() => {
+                self.search_job = None;
+                self.message = Some("search cancelled".to_owned());
+                self.update_needed = true;
+            }
+            KeyCode::Esc if self.selection_anchor.is_some() => self.clear_selection(),
+            KeyCode::Esc
+                if self.search_match.is_some()
+                    || self.highlight_search
+                    || self.search_scope.is_some() =>
+            {
+                self.search_match = None;
+                self.highlight_search = false;
+                self.search_scope = None;
+                self.update_needed = true;
+            }
+            KeyCode::Esc | KeyCode::Char('q') => self.key_quit(),
+            KeyCode::Right => self.key_right(count),
+            KeyCode::Left => self.key_left(count),
+            KeyCode::Up => self.key_up(count),
+            KeyCode::Down => self.key_down(count),
+            KeyCode::PageUp => self.key_pageup(count),
+            KeyCode::PageDown => self.key_pagedown(count),
+            KeyCode::Home if key_event.modifiers.contains(KeyModifiers::CONTROL) => self.key_home(),
+            KeyCode::End if key_event.modifiers.contains(KeyModifiers::CONTROL) => self.key_end(),
+            KeyCode::Home if self.home_row => self.key_row_start(),
+            KeyCode::End if self.home_row => self.key_row_end(),
+            KeyCode::Home => self.key_home(),
+            KeyCode::End => self.key_end(),
+            KeyCode::Char('e') => self.toggle_endianness(),
+            KeyCode::Char('g') => self.pending_key = Some('g'),
+            KeyCode::Char('L') => self.key_little_endian(),
+            KeyCode::Char('B') => self.key_big_endian(),
+            KeyCode::Char('t') => self.key_preview(),
+            KeyCode::Char('T') => self.clear_preview(),
+            KeyCode::Char(':') => self.open_prompt(PromptKind::Command, ":"),
+            KeyCode::Char('+') => self.open_prompt_with(PromptKind::Goto, "goto: ", "+"),
+            KeyCode::Char('%') => self.open_prompt(PromptKind::Percent, "goto percent: "),
+            KeyCode::Char('A') => self.key_array_view(),
+            KeyCode::Char('F') => self.key_bit_view(),
+            KeyCode::Char('|') => self.key_cycle_cols(),
+            KeyCode::Char('I') => self.key_file_info(),
+            KeyCode::Char('i') | KeyCode::Enter => self.key_edit_mode(true),
+            KeyCode::Insert => self.key_insert_mode(),
+            KeyCode::Char('x') | KeyCode::Delete => self.key_delete(count),
+            KeyCode::Char('p') => self.key_paste(false, register),
+            KeyCode::Char('P') => self.key_paste(true, register),
+            KeyCode::Tab => self.key_switch_pane(),
+            KeyCode::F(5) => {
+                self.reload();
+            }
+            KeyCode::Char('v') => self.key_select(),
+            KeyCode::Char(c @ (']' | '[' | 'm' | '\'' | '"')) => self.pending_key = Some(c),
+            KeyCode::Char('M') => self.list_marks(),
+            KeyCode::Char('~') if had_count => self.key_toggle_bit(count),
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('E') => self.command_stats(),
+            KeyCode::Char('y') => self.key_copy_hex(),
+            KeyCode::Char('Y') => {
+                let size = if had_count { count } else { self.word_size };
+                self.key_yank(register.unwrap_or('"'), size)
+            }
+            KeyCode::Char('R') => self.key_read_only(),
+            KeyCode::Char('C') => self.key_char_mode(),
+            KeyCode::Char('K') => self.key_byte_colors(),
+            KeyCode::Char('X') => self.key_crosshair(),
+            KeyCode::Char('&') => self.key_same_bytes(),
+            KeyCode::Char('o') => self.key_cycle_cursor(),
+            KeyCode::Char('f') => self.key_cycle_shade(),
+            KeyCode::Char('U') => self.key_ruler(),
+            KeyCode::Char('V') => self.key_info_pane(),
+            KeyCode::Char('_') => self.key_scroll_info(),
+            KeyCode::Char('O') => self.key_cycle_addr_base(),
+            KeyCode::Char('D') => self.key_charset(),
+            KeyCode::Char('J') => self.key_cycle_group(),
+            KeyCode::Char('H') => self.key_binary_view(),
+            KeyCode::Char('w') => self.key_word(true, count),
+            KeyCode::Char('b') => self.key_word(false, count),
+            KeyCode::Char('W') => self.key_word_size(),
+            KeyCode::Char('z') => self.key_skip_padding(true),
+            KeyCode::Char('Z') => self.key_skip_padding(false),
+            KeyCode::Char('c') => self.key_center(),
+            KeyCode::Char('/') => self.open_search_prompt(true),
+            KeyCode::Char('?') => self.open_search_prompt(false),
+            KeyCode::Char('n') => self.key_search_next(false),
+            KeyCode::Char('N') => self.key_search_next(true),
+            KeyCode::Char('*') => self.key_find_byte(true),
+            KeyCode::Char('#') => self.key_find_byte(false),
+            KeyCode::Char('}') => self.key_align(true, count),
+            KeyCode::Char('{') => self.key_align(false, count),
+            KeyCode::Char('=') => self.key_cycle_align(),
+            KeyCode::Char('s') => self.key_next_string(true),
+            KeyCode::Char('S') => self.key_next_string(false),
+            _ => {}
+        }
+    }
+
+    fn overlay_key_event(&mut self, key_event: &KeyEvent) {
+        if let Some((OverlayKind::Browse(dir, _), _)) = &self.overlay {
+            match key_event.code {
+                KeyCode::Backspace => {
+                    let parent = dir.parent().unwrap_or(dir).to_path_buf();
+                    self.open_browser(&parent);
+                    return;
+                }
+                KeyCode::Char('.') => {
+                    let dir = dir.clone();
+                    self.browse_hidden = !self.browse_hidden;
+                    self.open_browser(&dir);
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        let (_, overlay) = self.overlay.as_mut().unwrap();
+        match overlay.key_event(key_event, self.view_height as usize - 1) {
+            OverlayResult::Continue => {
+                self.draw_overlay();
+                self.stdout.flush().unwrap();
+            }
+            OverlayResult::Close => {
+                self.overlay = None;
+                self.update_needed = true;
+                // closing the browser without a file quits
+                if self.fd.is_none() {
+                    self.quit = true;
+                }
+            }
+            OverlayResult::Select(idx) => {
+                let (kind, _) = self.overlay.take().unwrap();
+                match kind {
+                    OverlayKind::Jump(offsets) => self.jump_to(offsets[idx]),
+                    OverlayKind::Info => {}
+                    OverlayKind::Digest => self.hash_range(hash::ALGORITHMS[idx]),
+                    OverlayKind::Browse(dir, entries) => self.browse_select(dir, &entries[idx]),
+                    OverlayKind::Stride(strides) => self.set_stride(Some(strides[idx])),
+                }
+                self.update_needed = true;
+            }
+        }
+    }
+
+    fn open_prompt(&mut self, kind: PromptKind, label: &str) {
+        self.open_prompt_with(kind, label, "");
+    }
+
+    // open a prompt with some input already filled in
+    fn open_prompt_with(&mut self, kind: PromptKind, label: &str, input: &str) {
+        let mut prompt = Prompt::new(label);
+        prompt.input = input.to_owned();
+        prompt.cursor = input.chars().count();
+        self.prompt = Some((kind, prompt));
+        self.draw_statusline();
+        self.stdout.flush().unwrap();
+    }
+
+    fn prompt_key_event(&mut self, key_event: &KeyEvent) {
+        let (kind, prompt) = self.prompt.as_mut().unwrap();
+        let is_search = matches!(kind, PromptKind::Search { .. });
+        let before = prompt.input.clone();
+        match prompt.key_event(key_event) {
+            PromptResult::Continue => {
+                if is_search && self.prompt.as_ref().unwrap().1.input != before {
+                    self.incremental_search();
+                }
+            }
+            PromptResult::Complete => self.complete_prompt(),
+            PromptResult::Cancel => {
+                let (_, prompt) = self.prompt.take().unwrap();
+                if let Some(saved) = prompt.saved {
+                    self.search_match = None;
+                    self.restore_view(saved);
+                }
+            }
+            PromptResult::Done(answer) => {
+                let (kind, prompt) = self.prompt.take().unwrap();
+                if let Some(saved) = prompt.saved {
+                    self.restore_view(saved);
+                }
+                match kind {
+                    PromptKind::Preview => self.set_preview(&answer),
+                    PromptKind::Command => self.run_command(&answer),
+                    PromptKind::Goto => self.command_goto(&answer),
+                    PromptKind::Percent => self.goto_percent(&answer),
+                    PromptKind::Search { forward } => self.search_prompt(&answer, forward),
+                    PromptKind::Extract => self.command_extract(&answer),
+                    PromptKind::Xxd(start, end) => self.xxd_to(&answer, start, end),
+                    PromptKind::Confirm(action) => {
+                        if matches!(answer.trim(), "y" | "Y" | "yes") {
+                            self.confirmed(action);
+                        }
+                    }
+                }
+            }
+        }
+        self.draw_statusline();
+        self.stdout.flush().unwrap();
+    }
+
+    fn complete_prompt(&mut self) {
+        let (kind, prompt) = self.prompt.as_ref().unwrap();
+        if *kind != PromptKind::Command {
+            return;
+        }
+        let (command, prefix) = match prompt.input.split_once(' ') {
+            Some((command, prefix)) => (command.to_owned(), prefix.trim_start().to_owned()),
+            None => return,
+        };
+        let names: Vec<&str> = match command.as_str() {
+            "sym" => {
+                if self.load_symbols().is_err() {
+                    return;
+                }
+                self.symbols
+                    .as_ref()
+                    .unwrap()
+                    .iter()
+                    .map(|sym| sym.name.as_str())
+                    .collect()
+            }
+            "crc" => self
+                .crc_presets
+                .iter()
+                .map(|preset| preset.name.as_str())
+                .chain(crc::CATALOGUE.iter().map(|(name, _)| *name))
+                .collect(),
+            _ => return,
+        };
+
+        // complete the longest common prefix of all matching names
+        let mut common: Option<&str> = None;
+        for name in names {
+            if !name.starts_with(&prefix) {
+                continue;
+            }
+            common = Some(match common {
+                None => name,
+                Some(common) => {
+                    let n = common
+                        .char_indices()
+                        .zip(name.chars())
+                        .take_while(|((_, a), b)| a == b)
+                        .last()
+                        .map_or(0, |((i, a), _)| i + a.len_utf8());
+                    &common[..n]
+                }
+            });
+        }
+        if let Some(common) = common {
+            let input = format!("{} {}", command, common);
+            let (_, prompt) = self.prompt.as_mut().unwrap();
+            prompt.cursor = input.chars().count();
+            prompt.input = input;
+        }
+    }
+
+    fn run_command(&mut self, line: &str) {
+        let line = line.trim();
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+        match command {
+            "" => {}
+            "sym" => self.command_sym(arg),
+            "array" => self.command_array(arg),
+            "export" => self.command_export(arg),
+            "identify" => self.command_identify(),
+            "reload" => {
+                self.reload();
+            }
+            "set" => self.command_set(arg),
+            "crc" => self.command_crc(arg),
+            "hash" => self.command_hash(arg),
+            "stats" => self.command_stats(),
+            "open" => self.command_open(arg),
+            "goto" => self.command_goto(arg),
+            "time" => self.command_time(),
+            "stride" => self.command_stride(arg),
+            "w" | "write" if arg.is_empty() => self.save(),
+            "w" | "write" => self.command_write_as(arg, false),
+            "saveas" => self.command_write_as(arg, true),
+            "fill" => self.command_fill(arg),
+            "xor" => self.command_xor(arg),
+            "truncate" => self.command_truncate(),
+            "copy" => self.command_copy(arg),
+            "extract" => self.command_extract(arg),
+            "xxd" => self.command_xxd(arg),
+            "patch" => self.command_patch(arg),
+            "e!" | "revert" => self.command_revert(),
+            "q" | "quit" => self.command_quit(),
+            "q!" | "quit!" => self.quit = true,
+            "wq" => {
+                self.save();
+                if self.edits.is_empty() {
+                    self.quit = true;
+                }
+            }
+            _ => self.message = Some(format!("unknown command: {}", command)),
+        }
+    }
+
+    // :set NAME, :set noNAME, :set NAME=VALUE
+    fn command_set(&mut self, arg: &str) {
+        for word in arg.split_whitespace() {
+            if let Err(err) = self.set_option(word) {
+                self.message = Some(err);
+                return;
+            }
+        }
+    }
+
+    // options are the same for :set and the config file
+    fn set_option(&mut self, word: &str) -> Result<(), String> {
+        if let Some((name, value)) = word.split_once('=') {
+            match name {
+                "placeholder" => {
+                    let mut chars = value.chars();
+                    self.placeholder = match (chars.next(), chars.next()) {
+                        (Some(c), None) => c,
+                        _ => return Err(format!("placeholder must be one character: {}", value)),
+                    };
+                }
+                "charmode" => {
+                    self.char_mode = match value {
+                        "dot" => CharMode::Dot,
+                        "glyph" => CharMode::Glyph,
+                        "letter" => CharMode::Letter,
+                        _ => return Err(format!("invalid charmode: {}", value)),
+                    };
+                }
+                "minlen" => {
+                    self.min_string = match parse_number(value) {
+                        Some(n) if n > 0 => n,
+                        _ => return Err(format!("invalid minlen: {}", value)),
+                    };
+                }
+                "align" => {
+                    self.align = match parse_number(value) {
+                        Some(n) if n > 0 => n,
+                        _ => return Err(format!("invalid alignment: {}", value)),
+                    };
+                }
+                "undolevels" => {
+                    self.undo_levels = match parse_number(value) {
+                        Some(n) => n as usize,
+                        _ => return Err(format!("invalid undolevels: {}", value)),
+                    };
+                    let excess = self.undo_stack.len().saturating_sub(self.undo_levels);
+                    self.undo_stack.drain(..excess);
+                }
+                "cols" => {
+                    let row_bytes =
+                        parse_number(value).ok_or_else(|| format!("invalid cols: {}", value))?;
+                    self.set_row_bytes(row_bytes)?;
+                }
+                "charset" => {
+                    self.charset = Charset::from_name(value)
+                        .ok_or_else(|| format!("invalid charset: {}", value))?;
+                }
+                "group" => {
+                    let group_size =
+                        parse_number(value).ok_or_else(|| format!("invalid group: {}", value))?;
+                    self.set_group_size(group_size)?;
+                }
+                "statusbar" => {
+                    self.status_bar = StatusBar::from_name(value)
+                        .ok_or_else(|| format!("invalid statusbar: {}", value))?;
+                    self.resize(self.terminal_width, self.terminal_height);
+                }
+                "theme" => self.set_theme(value)?,
+                "shade" => {
+                    self.shade = match parse_number(value) {
+                        Some(n) if [0, 1, 4].contains(&n) => n,
+                        _ => return Err(format!("invalid shade: {}", value)),
+                    };
+                }
+                "cursor" => {
+                    self.theme.cursor =
+                        Style::parse(value).map_err(|err| format!("invalid cursor: {}", err))?;
+                }
+                "cursorother" => {
+                    self.theme.cursor_other = Style::parse(value)
+                        .map_err(|err| format!("invalid cursorother: {}", err))?;
+                }
+                "addr" => {
+                    let addr_base = AddrBase::from_name(value)
+                        .ok_or_else(|| format!("invalid addr: {}", value))?;
+                    self.set_addr_base(addr_base);
+                }
+                "fixed" => {
+                    self.fixed_formats = value
+                        .split(',')
+                        .map(|name| {
+                            FixedPoint::from_name(name)
+                                .ok_or_else(|| format!("invalid fixed-point format: {}", name))
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                "tz" => {
+                    self.tz = datetime::TimeZone::parse(value)
+                        .ok_or_else(|| format!("invalid timezone: {}", value))?;
+                }
+                _ => return Err(format!("unknown option: {}", name)),
+            }
+            self.update_needed = true;
+            return Ok(());
+        }
+
+        let (name, value) = match word.strip_prefix("no") {
+            Some(name) => (name, false),
+            None => (word, true),
+        };
+        match name {
+            "autoreload" => self.autoreload = value,
+            "color" => {
+                self.byte_colors = value;
+                self.update_needed = true;
+            }
+            "infopane" => {
+                self.info_pane = value;
+                self.resize(self.terminal_width, self.terminal_height);
+            }
+            "ruler" => {
+                self.ruler = value;
+                self.resize(self.terminal_width, self.terminal_height);
+            }
+            "crosshair" => {
+                self.crosshair = value;
+                self.update_needed = true;
+            }
+            "samebytes" => {
+                self.same_bytes = value;
+                self.update_needed = true;
+            }
+            "groupendian" => {
+                self.group_endian = value;
+                self.update_needed = true;
+            }
+            "readonly" => {
+                self.read_only = value;
+                self.edit_mode &= !value;
+                self.insert_mode &= !value;
+            }
+            "homerow" => self.home_row = value,
+            "msb0" => {
+                self.msb0 = value;
+                self.update_needed = true;
+            }
+            "wrapscan" => self.wrap_scan = value,
+            "savehistory" => self.save_history = value,
+            "squeeze" => {
+                self.squeeze = value;
+                self.update_needed = true;
+            }
+            "scrollbar" => {
+                self.scrollbar = value;
+                self.update_needed = true;
+            }
+            "changes" => {
+                self.highlight_changes = value;
+                if !value {
+                    self.changed.clear();
+                    self.update_needed = true;
+                }
+            }
+            _ => return Err(format!("unknown option: {}", word)),
+        }
+        Ok(())
+    }
+
+    // :goto EXPR
+    fn command_goto(&mut self, arg: &str) {
+        let arg = arg.trim();
+        if arg.is_empty() {
+            return;
+        }
+        let vars = expr::Vars {
+            here: self.cursor_pos(),
+            end: self.filesize,
+        };
+        // "+N" and "-N" are relative to the cursor
+        if let Some(sign @ ('+' | '-')) = arg.chars().next() {
+            match expr::eval(&arg[1..], &vars) {
+                Ok(delta) => {
+                    let pos = if sign == '+' {
+                        vars.here.saturating_add(delta)
+                    } else {
+                        vars.here.saturating_sub(delta)
+                    };
+                    self.jump_to(pos.min(self.filesize - 1));
+                }
+                Err(err) => {
+                    let err = expr::ExprError {
+                        pos: err.pos + 1,
+                        msg: err.msg,
+                    };
+                    self.message = Some(format!("error: {}", err));
+                }
+            }
+            return;
+        }
+        match expr::eval(arg, &vars) {
+            Ok(pos) => {
+                self.jump_to(pos.min(self.filesize - 1));
+                if pos >= self.filesize {
+                    self.message = Some(format!("0x{:x} is beyond the end of the file", pos));
+                }
+            }
+            Err(err) => self.message = Some(format!("error: {}", err)),
+        }
+    }
+
+    // move the view to a percentage of the file, on a row boundary
+    fn goto_percent(&mut self, answer: &str) {
+        let percent = match answer.trim().trim_end_matches('%').parse::<u64>() {
+            Ok(percent) if percent <= 100 => percent,
+            _ => {
+                self.message = Some(format!("invalid percentage: {}", answer));
+                return;
+            }
+        };
+        if percent == 0 {
+            self.key_home();
+            return;
+        }
+        if percent == 100 {
+            self.key_end();
+            return;
+        }
+
+        let pos = (self.filesize as u128 * percent as u128 / 100) as u64 / self.row_bytes
+            * self.row_bytes;
+        if self.array_view {
+            self.jump_to(pos);
+            return;
+        }
+        self.offset = pos.min(self.end_offset());
+        self.cursor_x = 0;
+        self.cursor_y = ((pos - self.offset) / self.row_bytes) as u16;
+        self.page_fault(self.offset);
+        self.update_needed = true;
+    }
+
+    // :open [PATH]
+    // without path, browse the directory of the current file
+    fn command_open(&mut self, arg: &str) {
+        let path = if arg.is_empty() {
+            let filename = self.filename.clone().unwrap();
+            let path = std::fs::canonicalize(&filename).unwrap_or(PathBuf::from(filename));
+            path.parent().unwrap_or(Path::new("/")).to_path_buf()
+        } else {
+            PathBuf::from(expand_path(arg))
+        };
+        if path.is_dir() {
+            self.open_browser(&path);
+            return;
+        }
+        if !self.edits.is_empty() {
+            self.message = Some("there are unsaved changes".to_owned());
+            return;
+        }
+        match self.open_file(&path.to_string_lossy()) {
+            Ok(_) => self.update_needed = true,
+            Err(err) => self.message = Some(format!("error: {}", err)),
+        }
+    }
+
+    // :crc NAME | PARAMS | save NAME PARAMS | search
+    fn command_crc(&mut self, arg: &str) {
+        let (sub, rest) = match arg.split_once(char::is_whitespace) {
+            Some((sub, rest)) => (sub, rest.trim()),
+            None => (arg, ""),
+        };
+        match sub {
+            "" => {
+                self.message =
+                    Some("usage: :crc NAME | PARAMS | save NAME PARAMS | search".to_owned())
+            }
+            "search" => self.crc_search(),
+            "save" => self.crc_save(rest),
+            _ => {
+                let (name, params) = match self.find_crc_preset(arg) {
+                    Some(preset) => (preset.name, preset.params),
+                    None if !arg.contains('=') => {
+                        self.message = Some(format!("unknown CRC preset: {}", arg));
+                        return;
+                    }
+                    None => match CrcParams::parse(arg) {
+                        Ok(params) => (format!("CRC-{}", params.width), params),
+                        Err(err) => {
+                            self.message = Some(err);
+                            return;
+                        }
+                    },
+                };
+                let (start, end) = self.selection().unwrap_or((0, self.filesize));
+                match self.crc_range(&params, start, end) {
+                    Ok(value) => {
+                        self.message = Some(format!(
+                            "{}: 0x{:0digits$X}  over {} bytes at {:08X}",
+                            name,
+                            value,
+                            end - start,
+                            start,
+                            digits = params.digits()
+                        ))
+                    }
+                    Err(err) => self.message = Some(range_error(&err)),
+                }
+            }
+        }
+    }
+
+    // :hash [ALGORITHM]
+    // without algorithm, pick one from a list
+    fn command_hash(&mut self, arg: &str) {
+        if arg.is_empty() {
+            let items = hash::ALGORITHMS
+                .iter()
+                .map(|alg| OverlayItem {
+                    text: format!("{:<8}  {}", alg.name(), alg.description()),
+                    key: alg.name().to_owned(),
+                    enabled: true,
+                })
+                .collect();
+            let overlay = Overlay::new("hash algorithm", items, false);
+            self.overlay = Some((OverlayKind::Digest, overlay));
+            self.update_needed = true;
+            return;
+        }
+        match hash::Algorithm::from_name(arg) {
+            Some(alg) => self.hash_range(alg),
+            None => self.message = Some(format!("unknown hash algorithm: {}", arg)),
+        }
+    }
+
+    // hash the selection, or the whole file
+    fn hash_range(&mut self, alg: hash::Algorithm) {
+        let (start, end) = self.selection().unwrap_or((0, self.filesize));
+        let mut hasher = alg.hasher();
+        match self.read_range(alg.name(), start, end, &mut |data| hasher.update(data)) {
+            Ok(_) => {
+                self.message = Some(format!(
+                    "{}: 0x{}  over {} bytes at {:08X}",
+                    alg.name(),
+                    hasher.finish(),
+                    end - start,
+                    start
+                ))
+            }
+            Err(err) => self.message = Some(range_error(&err)),
+        }
+    }
+
+    // user presets take precedence over the catalogue
+    fn find_crc_preset(&self, name: &str) -> Option<crc::Preset> {
+        if let Some(preset) = self
+            .crc_presets
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name))
+        {
+            return Some(preset.clone());
+        }
+        crc::CATALOGUE
+            .iter()
+            .find(|(preset_name, _)| preset_name.eq_ignore_ascii_case(name))
+            .map(|(name, params)| crc::Preset {
+                name: name.to_string(),
+                params: params.clone(),
+            })
+    }
+
+    fn crc_range(&mut self, params: &CrcParams, start: u64, end: u64) -> std::io::Result<u64> {
+        let mut crc = Crc::new(params);
+        self.read_range("crc", start, end, &mut |data| crc.update(data))?;
+        Ok(crc.finish())
+    }
+
+    fn crc_save(&mut self, arg: &str) {
+        let (name, args) = match arg.split_once(char::is_whitespace) {
+            Some((name, args)) => (name, args.trim()),
+            None => {
+                self.message = Some("usage: :crc save NAME PARAMS".to_owned());
+                return;
+            }
+        };
+        if name.contains('"') {
+            self.message = Some(format!("invalid preset name: {}", name));
+            return;
+        }
+        let params = match CrcParams::parse(args) {
+            Ok(params) => params,
+            Err(err) => {
+                self.message = Some(err);
+                return;
+            }
+        };
+        match config::append(&params.to_toml(name)) {
+            Ok(path) => {
+                self.message = Some(format!("saved CRC preset {} in {}", name, path.display()))
+            }
+            Err(err) => {
+                self.message = Some(format!("error: {}", err));
+                return;
+            }
+        }
+        self.crc_presets.retain(|preset| preset.name != name);
+        self.crc_presets.push(crc::Preset {
+            name: name.to_owned(),
+            params,
+        });
+    }
+
+    // try all presets on the selection, and compare with the value
+    // that directly follows the selection
+    fn crc_search(&mut self) {
+        let (start, end) = match self.selection() {
+            Some(range) => range,
+            None => {
+                self.message = Some("select the data first (press 'v')".to_owned());
+                return;
+            }
+        };
+
+        let mut presets = self.crc_presets.clone();
+        presets.extend(crc::CATALOGUE.iter().map(|(name, params)| crc::Preset {
+            name: name.to_string(),
+            params: params.clone(),
+        }));
+
+        let mut items = Vec::new();
+        let mut offsets = Vec::new();
+        for preset in presets {
+            let size = preset.params.width as usize / 8;
+            let stored = self.read_bytes(end, size);
+            if stored.len() < size {
+                continue;
+            }
+            let value = match self.crc_range(&preset.params, start, end) {
+                Ok(value) => value,
+                Err(err) => {
+                    self.message = Some(range_error(&err));
+                    return;
+                }
+            };
+            let little = stored
+                .iter()
+                .rev()
+                .fold(0u64, |acc, &b| acc << 8 | b as u64);
+            let big = stored.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+            let order = if value == little {
+                "little endian"
+            } else if value == big {
+                "big endian"
+            } else {
+                continue;
+            };
+            items.push(OverlayItem {
+                text: format!(
+                    "{:<18}  0x{:0digits$X}  {}",
+                    preset.name,
+                    value,
+                    if size == 1 { "" } else { order },
+                    digits = preset.params.digits()
+                ),
+                key: preset.name.clone(),
+                enabled: true,
+            });
+            offsets.push(end);
+        }
+        if items.is_empty() {
+            self.message = Some("no CRC preset matches the value after the selection".to_owned());
+            return;
+        }
+        let overlay = Overlay::new(
+            "CRC presets matching the value after the selection",
+            items,
+            false,
+        );
+        self.overlay = Some((OverlayKind::Jump(offsets), overlay));
+        self.update_needed = true;
+    }
+
+    fn load_symbols(&mut self) -> Result<(), String> {
+        if self.symbols.is_some() {
+            return Ok(());
+        }
+        let mut fd = self.fd.as_ref().unwrap();
+        let elf = elf::Elf::parse(&mut fd)?;
+        let symbols = elf.symbols(&mut fd)?;
+        self.symbols = Some(symbols);
+        Ok(())
+    }
+
+    fn command_sym(&mut self, name: &str) {
+        if let Err(msg) = self.load_symbols() {
+            self.message = Some(msg);
+            return;
+        }
+        let symbols = self.symbols.as_ref().unwrap();
+
+        // jump directly on a unique exact match
+        let mut exact = symbols.iter().filter(|sym| sym.name == name);
+        if let (Some(sym), None) = (exact.next(), exact.next()) {
+            match sym.file_offset {
+                Some(offset) => self.jump_to(offset),
+                None => self.message = Some(format!("symbol {} is not in the file", name)),
+            }
+            return;
+        }
+
+        let wide = symbols.iter().any(|sym| sym.value > u32::MAX as u64);
+        let mut items = Vec::new();
+        let mut offsets = Vec::new();
+        for sym in symbols {
+            let value = if wide {
+                format!("{:016x}", sym.value)
+            } else {
+                format!("{:08x}", sym.value)
+            };
+            let mut text = format!(
+                "{:<40} {}  {:>8}  {}",
+                sym.name, value, sym.size, sym.section
+            );
+            if sym.file_offset.is_none() {
+                text.push_str("  (not in file)");
+            }
+            items.push(OverlayItem {
+                text,
+                key: sym.name.clone(),
+                enabled: sym.file_offset.is_some(),
+            });
+            offsets.push(sym.file_offset.unwrap_or(0));
+        }
+        let mut overlay = Overlay::new("symbols", items, true);
+        overlay.set_filter(name);
+        self.overlay = Some((OverlayKind::Jump(offsets), overlay));
+        self.update_needed = true;
+    }
+
+    // the visible view as plain text, exactly as on screen
+    fn format_view(&mut self, with_info: bool) -> String {
+        let mut text = String::new();
+        for y in 0..self.view_height {
+            let line = if self.array_view {
+                self.format_array_line(y).map(|(mut linebuf, cells)| {
+                    for value in cells {
+                        linebuf.push_str(&value);
+                        linebuf.push_str("  ");
+                    }
+                    linebuf
+                })
+            } else {
+                self.format_hexdump_line(y)
+            };
+            match line {
+                Some(line) => {
+                    text.push_str(&line);
+                    text.push('\n');
+                }
+                None => break,
+            }
+        }
+        if with_info {
+            for line in self.format_bottom_pane() {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    // :export [-i] [FILE]
+    // without filename, copy to clipboard
+    fn command_export(&mut self, arg: &str) {
+        let mut with_info = false;
+        let mut path = arg;
+        if let Some(rest) = arg.strip_prefix("-i") {
+            if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+                with_info = true;
+                path = rest.trim_start();
+            }
+        }
+
+        let text = self.format_view(with_info);
+        if path.is_empty() {
+            match clipboard::copy(&mut self.stdout, text.as_bytes()) {
+                Ok(_) => self.message = Some(format!("copied {} bytes to clipboard", text.len())),
+                Err(err) => self.message = Some(format!("error: clipboard: {}", err)),
+            }
+            return;
+        }
+
+        let path = expand_path(path);
+        match std::fs::write(&path, &text) {
+            Ok(_) => self.message = Some(format!("exported view to {}", path)),
+            Err(err) => self.message = Some(format!("error: {}: {}", path, err)),
+        }
+    }
+
+    // list all known signatures at or near the cursor
+    fn command_identify(&mut self) {
+        let pos = self.cursor_pos();
+        let start = pos.saturating_sub(IDENTIFY_BEFORE);
+        let end = (pos + IDENTIFY_AFTER).min(self.filesize);
+        let max_extent = magic::MAGIC_TABLE
+            .iter()
+            .map(|magic| magic.at as usize + magic.bytes.len())
+            .max()
+            .unwrap_or(0);
+        let data = self.read_bytes(start, (end - start) as usize + max_extent);
+
+        let mut found = Vec::new();
+        for addr in start..end {
+            let idx = (addr - start) as usize;
+            for magic in magic::MAGIC_TABLE {
+                if magic.is_weak() && addr != pos {
+                    continue;
+                }
+                let at = idx + magic.at as usize;
+                if at < data.len() && magic.matches(&data[at..]) {
+                    found.push((addr, magic.name));
+                }
+            }
+        }
+        if found.is_empty() {
+            self.message = Some("no known signature near the cursor".to_owned());
+            return;
+        }
+        found.sort_by_key(|&(addr, _)| (addr.abs_diff(pos), addr));
+
+        let mut items = Vec::new();
+        let mut offsets = Vec::new();
+        for (addr, name) in found {
+            let distance = if addr == pos {
+                "at cursor".to_owned()
+            } else if addr > pos {
+                format!("+0x{:x}", addr - pos)
+            } else {
+                format!("-0x{:x}", pos - addr)
+            };
+            items.push(OverlayItem {
+                text: format!("{:08X}  {:<10}  {}", addr, distance, name),
+                key: name.to_owned(),
+                enabled: true,
+            });
+            offsets.push(addr);
+        }
+        let overlay = Overlay::new("signatures near cursor", items, false);
+        self.overlay = Some((OverlayKind::Jump(offsets), overlay));
+        self.update_needed = true;
+    }
+
+    fn key_file_info(&mut self) {
+        let filename = self.filename.clone().unwrap();
+        let metadata = match std::fs::metadata(&filename) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                self.message = Some(format!("error: {}: {}", filename, err));
+                self.draw_statusline();
+                self.stdout.flush().unwrap();
+                return;
+            }
+        };
+
+        let mut lines = Vec::new();
+        let path = std::fs::canonicalize(&filename)
+            .map_or(filename.clone(), |path| path.display().to_string());
+        lines.push(format!("path      {}", path));
+        lines.push(format!(
+            "size      {} bytes ({})",
+            metadata.len(),
+            human_size(metadata.len())
+        ));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let mode = metadata.mode();
+            lines.push(format!(
+                "mode      {} ({:04o})",
+                mode_string(mode),
+                mode & 0o7777
+            ));
+            lines.push(format!(
+                "owner     {} ({}) group {} ({})",
+                unix_name("/etc/passwd", metadata.uid()),
+                metadata.uid(),
+                unix_name("/etc/group", metadata.gid()),
+                metadata.gid()
+            ));
+            lines.push(format!(
+                "inode     {}  device {:#x}  links {}",
+                metadata.ino(),
+                metadata.dev(),
+                metadata.nlink()
+            ));
+            // st_blocks is in units of 512 bytes
+            let allocated = metadata.blocks() * 512;
+            let sparse = if allocated < metadata.len() {
+                "  (sparse)"
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "blocks    {} ({} allocated){}",
+                metadata.blocks(),
+                human_size(allocated),
+                sparse
+            ));
+        }
+
+        let times = [
+            ("modified", metadata.modified()),
+            ("accessed", metadata.accessed()),
+            ("created ", metadata.created()),
+        ];
+        for (label, time) in times {
+            let time = time.map_or("--".to_owned(), |time| {
+                datetime::format_system_time(time, self.tz)
+            });
+            lines.push(format!("{}  {}", label, time));
+        }
+        let mode = if self.read_only {
+            "read-only"
+        } else {
+            "read-write"
+        };
+        let changes = match self.edits.modified_len() {
+            _ if self.edits.is_empty() => "no unsaved changes".to_owned(),
+            // only deletions
+            0 => "unsaved changes".to_owned(),
+            count => format!(
+                "unsaved changes, {} byte{} modified",
+                count,
+                if count == 1 { "" } else { "s" }
+            ),
+        };
+        lines.push(format!("state     opened {}, {}", mode, changes));
+
+        self.open_info_overlay("file information", lines);
+    }
+
+    // the value under the cursor, read as various kinds of timestamp
+    fn command_time(&mut self) {
+        let pos = self.cursor_pos();
+        let mut bytes = [0u8; 8];
+        let avail = (self.filesize - pos).min(8) as usize;
+        for (i, byte) in bytes.iter_mut().enumerate().take(avail) {
+            *byte = self.at(pos + i as u64);
+        }
+        let (value32, value64) = if self.endian == LittleEndian {
+            (
+                u32::from_le_bytes(bytes[..4].try_into().unwrap()),
+                u64::from_le_bytes(bytes),
+            )
+        } else {
+            (
+                u32::from_be_bytes(bytes[..4].try_into().unwrap()),
+                u64::from_be_bytes(bytes),
+            )
+        };
+
+        let tz = self.tz;
+        // "--" when the value runs past the end of the file
+        let show32 = |text: Option<String>| {
+            if avail < 4 {
+                "--".to_owned()
+            } else {
+                text.unwrap_or("invalid".to_owned())
+            }
+        };
+        let show64 = |text: String| if avail < 8 { "--".to_owned() } else { text };
+        let lines = vec![
+            format!(
+                "unix32    {}",
+                show32(Some(datetime::format_time(value32 as i64, tz)))
+            ),
+            format!(
+                "unix64    {}",
+                show64(datetime::format_time(value64 as i64, tz))
+            ),
+            format!(
+                "unix ms   {}",
+                show64(datetime::format_time((value64 as i64).div_euclid(1000), tz))
+            ),
+            format!(
+                "FILETIME  {}",
+                show64(datetime::format_time(
+                    (value64 / 10_000_000) as i64 - datetime::FILETIME_EPOCH,
+                    tz
+                ))
+            ),
+            format!(
+                "HFS+      {}",
+                show32(Some(datetime::format_time(
+                    value32 as i64 - datetime::HFS_EPOCH,
+                    tz
+                )))
+            ),
+            format!("DOS       {}", show32(datetime::format_dos(value32))),
+        ];
+        let endian = if self.endian == LittleEndian {
+            "little"
+        } else {
+            "big"
+        };
+        let title = format!("timestamps @0x{:x}, {} endian, tz {}", pos, endian, tz);
+        self.open_info_overlay(&title, lines);
+    }
+
+    fn open_info_overlay(&mut self, title: &str, lines: Vec<String>) {
+        let items = lines
+            .into_iter()
+            .map(|text| OverlayItem {
+                text,
+                key: String::new(),
+                enabled: true,
+            })
+            .collect();
+        self.overlay = Some((OverlayKind::Info, Overlay::new(title, items, false)));
+        self.update_needed = true;
+    }
+
+    // statistics over the selection, or the whole file
+    fn command_stats(&mut self) {
+        let (start, end, title) = match self.selection() {
+            Some((start, end)) => (start, end, "selection statistics"),
+            None => (0, self.filesize, "file statistics"),
+        };
+        let mut stats = stats::Stats::new(start);
+        let mut crc32 = hash::Algorithm::Crc32.hasher();
+        if let Err(err) = self.read_range("stats", start, end, &mut |data| {
+            stats.update(data);
+            crc32.update(data);
+        }) {
+            self.message = Some(range_error(&err));
+            return;
+        }
+
+        let mut lines = Vec::new();
+        let mut range = format!(
+            "range        {:08X}-{:08X}  ({} bytes",
+            start,
+            end - 1,
+            stats.len
+        );
+        if stats.len >= 1024 {
+            write!(range, ", {}", human_size(stats.len)).unwrap();
+        }
+        range.push(')');
+        lines.push(range);
+        let sum = stats.sum();
+        lines.push(format!("sum          {}  (0x{:X})", sum, sum));
+        lines.push(format!("crc32        {}", crc32.finish()));
+        let entropy = stats.entropy();
+        lines.push(format!(
+            "entropy      {:.3} bits per byte{}",
+            entropy,
+            if entropy > 7.5 {
+                "  (compressed or encrypted)"
+            } else {
+                ""
+            }
+        ));
+        lines.push(format!(
+            "set bits     {} of {}  ({:.1}%)",
+            stats.set_bits,
+            stats.len * 8,
+            stats.set_bits as f64 * 100.0 / (stats.len * 8) as f64
+        ));
+        let (value, len, offset) = stats.longest_run;
+        lines.push(format!(
+            "longest run  0x{:02X} x {}  at {:08X}",
+            value, len, offset
+        ));
+        match stats.guess_stride() {
+            Some((stride, score)) => lines.push(format!(
+                "stride       data repeats with period {} bytes  ({:.0}% match)",
+                stride,
+                score * 100.0
+            )),
+            None => lines.push("stride       no repeating pattern found".to_owned()),
+        }
+        self.open_info_overlay(title, lines);
+    }
+
+    // in read-only mode, tell so
+    fn check_writable(&mut self) -> bool {
+        if self.read_only {
+            self.message = Some("read-only; press 'R' to allow changes".to_owned());
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+            return false;
+        }
+        true
+    }
+
+    // 'R' switches read-only mode on and off
+    fn key_read_only(&mut self) {
+        self.read_only = !self.read_only;
+        if self.read_only {
+            self.edit_mode = false;
+            self.insert_mode = false;
+            self.message = Some("read-only".to_owned());
+        } else if let Some(filename) = self.filename.as_deref().filter(|f| !is_writable(f)) {
+            self.message = Some(format!("warning: can not write to {}", filename));
+        } else {
+            self.message = Some("changes allowed".to_owned());
+        }
+        self.draw_statusline();
+        self.stdout.flush().unwrap();
+    }
+
+    fn key_edit_mode(&mut self, on: bool) {
+        if on && !self.check_writable() {
+            return;
+        }
+        if on && self.array_view {
+            self.message = Some("can not edit in the array view".to_owned());
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+            return;
+        }
+        self.edit_mode = on;
+        self.insert_mode = false;
+        self.low_nibble = false;
+        self.draw_statusline();
+        self.stdout.flush().unwrap();
+    }
+
+    // Insert starts editing in insert mode; when editing, it switches
+    // between inserting and overwriting
+    fn key_insert_mode(&mut self) {
+        if !self.edit_mode {
+            self.key_edit_mode(true);
+            if !self.edit_mode {
+                return;
+            }
+        }
+        self.insert_mode = !self.insert_mode;
+        self.low_nibble = false;
+        self.draw_statusline();
+        self.stdout.flush().unwrap();
+    }
+
+    // Tab moves the cursor between the hex and the character pane
+    fn key_switch_pane(&mut self) {
+        if self.array_view {
+            return;
+        }
+        self.char_pane = !self.char_pane;
+        self.low_nibble = false;
+        self.update_cursor();
+    }
+
+    // In edit mode, hex digits overwrite the byte under the cursor, high
+    // nibble first, and move on to the next byte
+    // In the character pane, printable ASCII characters do
+    // In insert mode, a new byte goes in before the cursor instead
+    // Other keys that would type something are ignored, so they can not
+    // change the data by accident; keys that move still work
+    // Returns false if the key is to be handled as usual
+    fn edit_key_event(&mut self, key_event: &KeyEvent) -> bool {
+        if !key_event
+            .modifiers
+            .difference(KeyModifiers::SHIFT)
+            .is_empty()
+        {
+            return false;
+        }
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => self.key_edit_mode(false),
+            KeyCode::Tab => self.key_switch_pane(),
+            KeyCode::Insert => self.key_insert_mode(),
+            KeyCode::Backspace if self.insert_mode => self.edit_backspace(),
+            KeyCode::Char(c) if self.char_pane => {
+                if c == ' ' || c.is_ascii_graphic() {
+                    self.edit_char(c as u8);
+                } else {
+                    self.message = Some("only printable ASCII can be typed here".to_owned());
+                    self.draw_statusline();
+                    self.stdout.flush().unwrap();
+                }
+            }
+            KeyCode::Char('i') => self.key_edit_mode(false),
+            KeyCode::Char(c) if c.is_ascii_hexdigit() => {
+                self.edit_nibble(c.to_digit(16).unwrap() as u8)
+            }
+            KeyCode::Char(_) => {
+                self.message = Some("type hex digits, or Esc to stop editing".to_owned());
+                self.draw_statusline();
+                self.stdout.flush().unwrap();
+            }
+            _ => {
+                // moving starts at the high nibble again
+                self.low_nibble = false;
+                return false;
+            }
+        }
+        true
+    }
+
+    fn edit_char(&mut self, value: u8) {
+        let pos = self.cursor_pos();
+        let len = if self.insert_mode { 0 } else { 1 };
+        let change = self.edits.splice(pos, len, &[value]);
+        self.push_undo(vec![change]);
+        if self.insert_mode {
+            self.size_changed();
+        }
+        self.redraw_edit();
+        if pos + 1 < self.filesize {
+            self.key_right(1);
+        }
+    }
+
+    fn edit_nibble(&mut self, digit: u8) {
+        let pos = self.cursor_pos();
+        // the high nibble inserts a new byte, the low nibble completes it
+        let inserting = self.insert_mode && !self.low_nibble;
+        let byte = if inserting { 0 } else { self.at(pos) };
+        let value = if self.low_nibble {
+            byte & 0xf0 | digit
+        } else {
+            byte & 0x0f | digit << 4
+        };
+        let len = if inserting { 0 } else { 1 };
+        let change = self.edits.splice(pos, len, &[value]);
+        // both nibbles are undone as one
+        match self.undo_stack.last_mut() {
+            Some(group) if self.low_nibble => group.push(change),
+            _ => self.push_undo(vec![change]),
+        }
+        if inserting {
+            self.size_changed();
+        }
+        self.redraw_edit();
+        if self.low_nibble && pos + 1 < self.filesize {
+            self.key_right(1);
+        }
+        self.low_nibble = !self.low_nibble;
+    }
+
+    // 1~ to 8~ flip bit 0 to 7 of the byte under the cursor
+    // The digits are taken as a count, so there is no key per bit
+    fn key_toggle_bit(&mut self, count: u64) {
+        if !self.check_writable() {
+            return;
+        }
+        if !(1..=8).contains(&count) {
+            self.message = Some("bit number must be 1 to 8".to_owned());
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+            return;
+        }
+        self.toggle_bit(count as u32 - 1);
+    }
+
+    // Ctrl+A and Ctrl+X add to and subtract from the byte under the cursor,
+    // wrapping around
+    fn key_increment(&mut self, count: u64, up: bool) {
+        if !self.check_writable() {
+            return;
+        }
+        let pos = self.cursor_pos();
+        let delta = (count % 256) as u8;
+        let value = if up {
+            self.at(pos).wrapping_add(delta)
+        } else {
+            self.at(pos).wrapping_sub(delta)
+        };
+        let change = self.edits.splice(pos, 1, &[value]);
+        self.push_undo(vec![change]);
+        self.redraw_edit();
+    }
+
+    fn toggle_bit(&mut self, bit: u32) {
+        let pos = self.cursor_pos();
+        let value = self.at(pos) ^ (1 << bit);
+        let change = self.edits.splice(pos, 1, &[value]);
+        self.push_undo(vec![change]);
+        self.redraw_edit();
+    }
+
+    // ask a yes/no question at the prompt
+    fn confirm(&mut self, action: Confirm, question: &str) {
+        self.open_prompt(PromptKind::Confirm(action), &format!("{} (y/n) ", question));
+    }
+
+    fn confirmed(&mut self, action: Confirm) {
+        match action {
+            Confirm::Fill(start, end, value) => self.fill(start, end, value),
+            Confirm::Xor(start, end, key) => self.xor(start, end, &key),
+            Confirm::Truncate(pos) => self.truncate(pos),
+            Confirm::Revert => self.revert(),
+            Confirm::WriteAs(path, switch) => self.write_as(&path, switch),
+            Confirm::Extract(path, start, end) => self.extract(&path, start, end),
+            Confirm::Xxd(path, start, end) => self.xxd(&path, start, end),
+            Confirm::Quit => self.quit = true,
+        }
+    }
+
+    // 'q' and Esc ask before throwing away unsaved changes
+    fn key_quit(&mut self) {
+        if self.edits.is_empty() {
+            self.quit = true;
+        } else {
+            self.confirm(Confirm::Quit, "discard unsaved changes and quit?");
+        }
+    }
+
+    // :q refuses to throw away unsaved changes; :q! does
+    fn command_quit(&mut self) {
+        if self.edits.is_empty() {
+            self.quit = true;
+        } else {
+            self.message = Some("unsaved changes, :w to save or :q! to discard".to_owned());
+        }
+    }
+
+    // :e! throws away all changes, after asking
+    fn command_revert(&mut self) {
+        if self.edits.is_empty() {
+            self.message = Some("no changes".to_owned());
+            return;
+        }
+        self.confirm(Confirm::Revert, "discard all changes?");
+    }
+
+    fn revert(&mut self) {
+        // the file may have changed on disk as well
+        // The changes are kept when it can not be read
+        if !self.reload() {
+            return;
+        }
+        self.edits = Edits::new(self.edits.file_len());
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.low_nibble = false;
+        self.size_changed();
+        self.message = Some("changes discarded".to_owned());
+    }
+
+    // :fill VALUE [START..END] fills the range, or the selection
+    fn command_fill(&mut self, arg: &str) {
+        if !self.check_writable() {
+            return;
+        }
+        let (value, range) = match arg.split_once(char::is_whitespace) {
+            Some((value, range)) => (value, Some(range.trim())),
+            None => (arg, None),
+        };
+        let value = match parse_number(value) {
+            Some(value) if value <= 0xff => value as u8,
+            _ => {
+                self.message = Some(format!("invalid byte value: {}", value));
+                return;
+            }
+        };
+        let (start, end) = match range.map(|range| self.parse_range(range)) {
+            Some(Ok(range)) => range,
+            Some(Err(err)) => {
+                self.message = Some(err);
+                return;
+            }
+            None => match self.selection() {
+                Some(range) => range,
+                None => {
+                    self.message = Some("select a range, or give one as START..END".to_owned());
+                    return;
+                }
+            },
+        };
+        if end - start > FILL_CONFIRM {
+            let question = format!("fill {} with 0x{:02X}?", human_size(end - start), value);
+            self.confirm(Confirm::Fill(start, end, value), &question);
+            return;
+        }
+        self.fill(start, end, value);
+    }
+
+    // the range is overwritten a chunk at a time, and undone as one
+    fn fill(&mut self, start: u64, end: u64, value: u8) {
+        let chunk = vec![value; READ_CHUNKSIZE];
+        let mut changes = Vec::new();
+        let mut pos = start;
+        while pos < end {
+            let n = (end - pos).min(READ_CHUNKSIZE as u64);
+            changes.push(self.edits.splice(pos, n, &chunk[..n as usize]));
+            pos += n;
+        }
+        self.push_undo(changes);
+        self.message = Some(format!(
+            "filled {} byte{} with 0x{:02X}",
+            end - start,
+            if end - start == 1 { "" } else { "s" },
+            value
+        ));
+        self.update_needed = true;
+    }
+
+    // :xor [-p] KEY [START..END]
+    // With -p, the range only shows XORed, as a preview
+    fn command_xor(&mut self, arg: &str) {
+        let (preview, arg) = match arg.strip_prefix("-p") {
+            Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+                (true, rest.trim())
+            }
+            _ => (false, arg),
+        };
+        if !preview && !self.check_writable() {
+            return;
+        }
+        let (key, range) = match arg.split_once(char::is_whitespace) {
+            Some((key, range)) => (key, Some(range.trim())),
+            None => (arg, None),
+        };
+        if key.is_empty() {
+            self.message = Some("usage: :xor [-p] KEY [START..END]".to_owned());
+            return;
+        }
+        let key = match transform::parse_key(key) {
+            Ok(key) => key,
+            Err(err) => {
+                self.message = Some(err);
+                return;
+            }
+        };
+        let (start, end) = match range.map(|range| self.parse_range(range)) {
+            Some(Ok(range)) => range,
+            Some(Err(err)) => {
+                self.message = Some(err);
+                return;
+            }
+            None => match self.selection() {
+                Some(range) => range,
+                None => {
+                    self.message = Some("select a range, or give one as START..END".to_owned());
+                    return;
+                }
+            },
+        };
+        if preview {
+            self.preview = Some(Transform::Xor(key));
+            self.preview_range = Some((start, end));
+            self.update_needed = true;
+            return;
+        }
+        if end - start > FILL_CONFIRM {
+            let question = format!("XOR {}?", human_size(end - start));
+            self.confirm(Confirm::Xor(start, end, key), &question);
+            return;
+        }
+        self.xor(start, end, &key);
+    }
+
+    // like fill, the range is changed a chunk at a time, and undone as one
+    fn xor(&mut self, start: u64, end: u64, key: &[u8]) {
+        let mut changes = Vec::new();
+        if let Err(err) = self.xor_chunks(start, end, key, &mut changes) {
+            // do not leave it half done
+            for change in changes.iter().rev() {
+                self.edits.undo(change);
+            }
+            self.message = Some(range_error(&err));
+            self.update_needed = true;
+            return;
+        }
+        let len = end - start;
+        self.push_undo(changes);
+        let key: String = key.iter().map(|byte| format!("{:02X}", byte)).collect();
+        self.message = Some(format!(
+            "XORed {} byte{} with {}",
+            len,
+            if len == 1 { "" } else { "s" },
+            key
+        ));
+        self.update_needed = true;
+    }
+
+    fn xor_chunks(
+        &mut self,
+        start: u64,
+        end: u64,
+        key: &[u8],
+        changes: &mut Vec<Change>,
+    ) -> std::io::Result<()> {
+        let mut buf = vec![0u8; READ_CHUNKSIZE];
+        let mut pos = start;
+        let mut last_update = Instant::now();
+        while pos < end {
+            let want = (end - pos).min(READ_CHUNKSIZE as u64) as usize;
+            let n = self
+                .edits
+                .read(self.fd.as_ref().unwrap(), pos, &mut buf[..want])?;
+            if n == 0 {
+                break;
+            }
+            let key_offset = ((pos - start) % key.len() as u64) as usize;
+            for (i, byte) in buf[..n].iter_mut().enumerate() {
+                *byte ^= key[(key_offset + i) % key.len()];
+            }
+            changes.push(self.edits.splice(pos, n as u64, &buf[..n]));
+            pos += n as u64;
+            self.progress("xor", pos - start, end - start, &mut last_update)?;
+        }
+        Ok(())
+    }
+
+    // :truncate makes the file end at the cursor, after asking
+    fn command_truncate(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        let pos = self.cursor_pos();
+        if pos == 0 {
+            self.message = Some("can not truncate to an empty file".to_owned());
+            return;
+        }
+        let mut question = format!("truncate to {} bytes", pos);
+        if pos >= 1024 {
+            write!(question, " ({})", human_size(pos)).unwrap();
+        }
+        question.push('?');
+        self.confirm(Confirm::Truncate(pos), &question);
+    }
+
+    fn truncate(&mut self, pos: u64) {
+        let cut = self.filesize - pos;
+        let change = self.edits.splice(pos, cut, &[]);
+        self.push_undo(vec![change]);
+        self.size_changed();
+        self.message = Some(format!(
+            "cut off {} byte{}",
+            cut,
+            if cut == 1 { "" } else { "s" }
+        ));
+    }
+
+    // 'y' copies the selection, or the byte under the cursor, to the
+    // clipboard as hex
+    fn key_copy_hex(&mut self) {
+        self.copy_selection(COPY_LIMIT, clipboard::format_hex);
+    }
+
+    // :copy FORMAT
+    fn command_copy(&mut self, arg: &str) {
+        match arg {
+            "hex" => self.copy_selection(COPY_LIMIT, clipboard::format_hex),
+            "c" => self.copy_selection(LITERAL_LIMIT, clipboard::format_c_array),
+            "rust" => self.copy_selection(LITERAL_LIMIT, clipboard::format_rust_array),
+            "base64" => self.copy_base64(false),
+            "base64url" => self.copy_base64(true),
+            "" => self.message = Some("usage: :copy hex|c|rust|base64|base64url".to_owned()),
+            _ => self.message = Some(format!("unknown format: {}", arg)),
+        }
+    }
+
+    // the selection, or the current row, is encoded while it is read
+    fn copy_base64(&mut self, url_safe: bool) {
+        let pos = self.cursor_pos();
+        let row = pos - pos % self.row_bytes;
+        let (start, end) = self
+            .selection()
+            .unwrap_or((row, (row + self.row_bytes).min(self.filesize)));
+        if end - start > COPY_LIMIT {
+            self.message = Some(format!(
+                "selection too large to copy: {} (at most {})",
+                human_size(end - start),
+                human_size(COPY_LIMIT)
+            ));
+            return;
+        }
+        let mut encoder = clipboard::Base64::new(url_safe);
+        if let Err(err) = self.read_range("base64", start, end, &mut |buf| encoder.push(buf)) {
+            self.message = Some(range_error(&err));
+            return;
+        }
+        let text = encoder.finish();
+        match clipboard::copy(&mut self.stdout, text.as_bytes()) {
+            Ok(_) => {
+                self.message = Some(format!(
+                    "copied {} bytes as {} characters of base64",
+                    end - start,
+                    text.len()
+                ))
+            }
+            Err(err) => self.message = Some(format!("error: clipboard: {}", err)),
+        }
+    }
+
+    fn copy_selection(&mut self, limit: u64, format: fn(&[u8]) -> String) {
+        let pos = self.cursor_pos();
+        let (start, end) = self.selection().unwrap_or((pos, pos + 1));
+        if end - start > limit {
+            self.message = Some(format!(
+                "selection too large to copy: {} (at most {})",
+                human_size(end - start),
+                human_size(limit)
+            ));
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+            return;
+        }
+        let mut data = Vec::with_capacity((end - start) as usize);
+        if let Err(err) =
+            self.read_range("copy", start, end, &mut |buf| data.extend_from_slice(buf))
+        {
+            self.message = Some(range_error(&err));
+            self.update_needed = true;
+            return;
+        }
+        let text = format(&data);
+        match clipboard::copy(&mut self.stdout, text.as_bytes()) {
+            Ok(_) => {
+                self.message = Some(format!(
+                    "copied {} byte{}",
+                    data.len(),
+                    if data.len() == 1 { "" } else { "s" }
+                ))
+            }
+            Err(err) => self.message = Some(format!("error: clipboard: {}", err)),
+        }
+        self.draw_statusline();
+        self.stdout.flush().unwrap();
+    }
+
+    // 'Y' yanks 1, 2, 4 or 8 bytes at the cursor into a register
+    fn key_yank(&mut self, register: char, size: u64) {
+        let pos = self.cursor_pos();
+        if !matches!(size, 1 | 2 | 4 | 8) {
+            self.message = Some("can only yank 1, 2, 4 or 8 bytes".to_owned());
+        } else if pos + size > self.filesize {
+            self.message = Some("not enough bytes to yank".to_owned());
+        } else {
+            let bytes = self.read_bytes(pos, size as usize);
+            let value = bytes.iter().enumerate().fold(0u64, |value, (i, &byte)| {
+                if self.endian == BigEndian {
+                    value << 8 | byte as u64
+                } else {
+                    value | (byte as u64) << (8 * i)
+                }
+            });
+            let mut message = format!(
+                "\"{}: {} = 0x{:0width$X}",
+                register,
+                clipboard::format_hex(&bytes),
+                value,
+                width = size as usize * 2
+            );
+            if size > 1 {
+                message.push_str(if self.endian == BigEndian {
+                    " (big endian)"
+                } else {
+                    " (little endian)"
+                });
+            }
+            self.message = Some(message);
+            self.registers.insert(register, bytes);
+        }
+        self.draw_statusline();
+        self.stdout.flush().unwrap();
+    }
+
+    // 'p' overwrites bytes at the cursor with the hex on the clipboard,
+    // and makes the file longer when it runs past the end
+    // 'P' inserts them before the cursor
+    // With a register, the bytes come from there
+    fn key_paste(&mut self, insert: bool, register: Option<char>) {
+        if !self.check_writable() {
+            return;
+        }
+        if self.array_view {
+            self.message = Some("can not paste in the array view".to_owned());
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+            return;
+        }
+        let bytes = match register {
+            Some(register) => self
+                .registers
+                .get(&register)
+                .cloned()
+                .ok_or_else(|| format!("register {} is empty", register)),
+            None => match clipboard::paste() {
+                Ok(data) => clipboard::parse_hex(&String::from_utf8_lossy(&data)),
+                Err(err) => Err(format!("clipboard: {}", err)),
+            },
+        };
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                self.message = Some(format!("error: {}", err));
+                self.draw_statusline();
+                self.stdout.flush().unwrap();
+                return;
+            }
+        };
+        let pos = self.cursor_pos();
+        let n = bytes.len() as u64;
+        let len = if insert {
+            0
+        } else {
+            n.min(self.filesize - pos)
+        };
+        let change = self.edits.splice(pos, len, &bytes);
+        self.push_undo(vec![change]);
+        if len != n {
+            self.size_changed();
+        }
+        self.jump_to((pos + n).min(self.filesize - 1));
+        self.message = Some(format!(
+            "pasted {} byte{}",
+            n,
+            if n == 1 { "" } else { "s" }
+        ));
+        self.update_needed = true;
+    }
+
+    // Backspace in insert mode deletes the byte before the cursor
+    fn edit_backspace(&mut self) {
+        let pos = self.cursor_pos();
+        if pos == 0 || self.filesize == 1 {
+            return;
+        }
+        let change = self.edits.splice(pos - 1, 1, &[]);
+        self.push_undo(vec![change]);
+        self.size_changed();
+        self.jump_to(pos - 1);
+    }
+
+    // 'x' and Delete remove the selection, or count bytes at the cursor
+    fn key_delete(&mut self, count: u64) {
+        if !self.check_writable() {
+            return;
+        }
+        if self.array_view {
+            self.message = Some("can not delete in the array view".to_owned());
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+            return;
+        }
+        let pos = self.cursor_pos();
+        let (start, end) = self
+            .selection()
+            .unwrap_or((pos, pos.saturating_add(count).min(self.filesize)));
+        if end - start >= self.filesize {
+            self.message = Some("can not delete all of the data".to_owned());
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+            return;
+        }
+        let change = self.edits.splice(start, end - start, &[]);
+        self.push_undo(vec![change]);
+        self.selection_anchor = None;
+        self.size_changed();
+        self.jump_to(start.min(self.filesize - 1));
+    }
+
+    // the data got longer or shorter; everything after the change moved
+    fn size_changed(&mut self) {
+        // the screen is redrawn as a whole, also the cursor
+        self.update_needed = true;
+        self.filesize = self.edits.data_len();
+        self.leftpane_width = self.addr_digits() as u16 + 2;
+        self.layout_columns();
+        if let Some(anchor) = self.selection_anchor {
+            self.selection_anchor = Some(anchor.min(self.filesize - 1));
+        }
+        self.search_match = None;
+        self.changed.clear();
+        if self.offset > self.end_offset() {
+            self.offset = self.end_offset();
+        }
+        if self.cursor_pos() >= self.filesize {
+            self.jump_to(self.filesize - 1);
+        }
+    }
+
+    // a new change can not be redone after undoing
+    fn push_undo(&mut self, group: Vec<Change>) {
+        self.redo_stack.clear();
+        self.undo_stack.push(group);
+        if self.undo_stack.len() > self.undo_levels {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    fn undo(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        let changes = match self.undo_stack.pop() {
+            Some(changes) => changes,
+            None => {
+                self.message = Some("nothing to undo".to_owned());
+                self.draw_statusline();
+                self.stdout.flush().unwrap();
+                return;
+            }
+        };
+        for change in changes.iter().rev() {
+            self.edits.undo(change);
+        }
+        if changes.iter().any(|change| change.resizes()) {
+            self.size_changed();
+        }
+        self.jump_to(changes[0].pos.min(self.filesize - 1));
+        self.redraw_changes(&changes);
+        self.redo_stack.push(changes);
+    }
+
+    // Ctrl+R
+    fn redo(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        let changes = match self.redo_stack.pop() {
+            Some(changes) => changes,
+            None => {
+                self.message = Some("nothing to redo".to_owned());
+                self.draw_statusline();
+                self.stdout.flush().unwrap();
+                return;
+            }
+        };
+        for change in changes.iter() {
+            self.edits.redo(change);
+        }
+        if changes.iter().any(|change| change.resizes()) {
+            self.size_changed();
+        }
+        self.jump_to(changes[0].pos.min(self.filesize - 1));
+        self.redraw_changes(&changes);
+        self.undo_stack.push(changes);
+    }
+
+    // write the changes into the file; Ctrl+S and :w
+    // On error, the changes stay as they are
+    fn save(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        if self.edits.is_empty() {
+            self.message = Some("no changes to write".to_owned());
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+            return;
+        }
+        let filename = self.filename.clone().unwrap();
+        let old_len = self.edits.file_len();
+        match self.write_file(&filename) {
+            Ok(count) => {
+                let mut message = format!(
+                    "wrote {} byte{} to {}",
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    filename
+                );
+                if self.filesize < old_len {
+                    write!(message, ", truncated to {} bytes", self.filesize).unwrap();
+                }
+                self.message = Some(message);
+            }
+            Err(err) => {
+                self.message = Some(format!("error: failed to write '{}': {}", filename, err));
+            }
+        }
+        self.update_needed = true;
+    }
+
+    // Overwritten bytes are written in place, but when the length
+    // changed the file is written anew
+    // Returns the number of bytes written
+    fn write_file(&mut self, filename: &str) -> std::io::Result<u64> {
+        // undo refers to data in the file, that is about to change
+        self.detach_history()?;
+
+        let count = if self.edits.is_in_place() {
+            let mut fd = OpenOptions::new().write(true).open(filename)?;
+            self.edits.write(&mut fd)?;
+            self.edits.modified_len()
+        } else {
+            // a symbolic link stays a link
+            write_replace(
+                &self.edits,
+                self.fd.as_ref().unwrap(),
+                &std::fs::canonicalize(filename)?,
+            )?;
+            self.fd = Some(File::open(filename)?);
+            self.edits.data_len()
+        };
+        self.file_saved()?;
+        Ok(count)
+    }
+
+    // undo and redo may refer to data in the file; copy it, so
+    // that they keep working when the file changes
+    fn detach_history(&mut self) -> std::io::Result<()> {
+        let fd = self.fd.as_ref().unwrap();
+        for change in self
+            .undo_stack
+            .iter_mut()
+            .chain(self.redo_stack.iter_mut())
+            .flatten()
+        {
+            self.edits.detach(fd, change)?;
+        }
+        Ok(())
+    }
+
+    // the file that fd refers to now holds the changes
+    // Undo keeps working; it changes the data back
+    fn file_saved(&mut self) -> std::io::Result<()> {
+        let metadata = self.fd.as_ref().unwrap().metadata()?;
+        self.edits.saved(metadata.len());
+        self.file_stamp = metadata
+            .modified()
+            .ok()
+            .map(|mtime| (metadata.len(), mtime));
+        self.symbols = None;
+        self.page_fault(self.page_address);
+        Ok(())
+    }
+
+    // :w PATH writes a copy, and stays with the file
+    // :saveas PATH does the same, and goes on with the copy
+    fn command_write_as(&mut self, arg: &str, switch: bool) {
+        if !self.check_writable() {
+            return;
+        }
+        if arg.is_empty() {
+            self.message = Some("usage: :saveas PATH".to_owned());
+            return;
+        }
+        let path = PathBuf::from(expand_path(arg));
+        if path.is_dir() {
+            self.message = Some(format!("{} is a directory", path.display()));
+            return;
+        }
+        if path.exists() {
+            let question = format!("overwrite {}?", path.display());
+            self.confirm(Confirm::WriteAs(path, switch), &question);
+            return;
+        }
+        self.write_as(&path, switch);
+    }
+
+    fn write_as(&mut self, path: &Path, switch: bool) {
+        match self.write_copy_as(path, switch) {
+            Ok(_) => {
+                let n = self.filesize;
+                self.message = Some(format!(
+                    "wrote {} byte{} to {}",
+                    n,
+                    if n == 1 { "" } else { "s" },
+                    path.display()
+                ));
+            }
+            Err(err) => {
+                self.message = Some(format!(
+                    "error: failed to write '{}': {}",
+                    path.display(),
+                    err
+                ));
+            }
+        }
+        self.update_needed = true;
+    }
+
+    fn write_copy_as(&mut self, path: &Path, switch: bool) -> std::io::Result<()> {
+        let filename = self.filename.clone().unwrap();
+        if std::fs::canonicalize(path).ok() == std::fs::canonicalize(&filename).ok() {
+            return self.write_file(&filename).map(|_| ());
+        }
+        if switch {
+            self.detach_history()?;
+        }
+        write_replace(&self.edits, self.fd.as_ref().unwrap(), path)?;
+        if switch {
+            self.fd = Some(File::open(path)?);
+            self.filename = Some(path.to_string_lossy().into_owned());
+            self.file_saved()?;
+        }
+        Ok(())
+    }
+
+    // :extract PATH writes the selected bytes to a new file
+    // Without PATH, it asks for one
+    fn command_extract(&mut self, arg: &str) {
+        let (start, end) = match self.selection() {
+            Some(range) => range,
+            None => {
+                self.message = Some("select a range to extract".to_owned());
+                return;
+            }
+        };
+        let arg = arg.trim();
+        if arg.is_empty() {
+            self.open_prompt(PromptKind::Extract, "extract to: ");
+            return;
+        }
+        let path = PathBuf::from(expand_path(arg));
+        if path.is_dir() {
+            self.message = Some(format!("{} is a directory", path.display()));
+            return;
+        }
+        if path.exists() {
+            let question = format!("overwrite {}?", path.display());
+            self.confirm(Confirm::Extract(path, start, end), &question);
+            return;
+        }
+        self.extract(&path, start, end);
+    }
+
+    // the bytes are read, with the changes, and written in chunks
+    fn extract(&mut self, path: &Path, start: u64, end: u64) {
+        let mut out = match File::create(path) {
+            Ok(out) => out,
+            Err(err) => {
+                self.message = Some(format!("error: {}: {}", path.display(), err));
+                return;
+            }
+        };
+        let mut write_result = Ok(());
+        let result = self.read_range("extract", start, end, &mut |buf| {
+            if write_result.is_ok() {
+                write_result = out.write_all(buf);
+            }
+        });
+        match result.and(write_result).and_then(|_| out.sync_all()) {
+            Ok(_) => {
+                let n = end - start;
+                self.message = Some(format!(
+                    "wrote {} byte{} to {}",
+                    n,
+                    if n == 1 { "" } else { "s" },
+                    path.display()
+                ));
+            }
+            Err(err) => {
+                _ = std::fs::remove_file(path);
+                self.message = Some(if err.kind() == std::io::ErrorKind::Interrupted {
+                    "cancelled".to_owned()
+                } else {
+                    format!("error: {}: {}", path.display(), err)
+                });
+            }
+        }
+        self.update_needed = true;
+    }
+
+    // :xxd [START..END] [PATH] writes a hexdump in the format of xxd, of
+    // the range, the selection, or the whole file
+    fn command_xxd(&mut self, arg: &str) {
+        let arg = arg.trim();
+        let (range, path) = match arg.split_once(char::is_whitespace) {
+            Some((first, rest)) if first.contains("..") => (Some(first), rest.trim()),
+            None if arg.contains("..") => (Some(arg), ""),
+            _ => (None, arg),
+        };
+        let (start, end) = match range.map(|range| self.parse_range(range)) {
+            Some(Ok(range)) => range,
+            Some(Err(err)) => {
+                self.message = Some(err);
+                return;
+            }
+            None => self.selection().unwrap_or((0, self.filesize)),
+        };
+        if path.is_empty() {
+            self.open_prompt(PromptKind::Xxd(start, end), "xxd to: ");
+            return;
+        }
+        self.xxd_to(path, start, end);
+    }
+
+    fn xxd_to(&mut self, path: &str, start: u64, end: u64) {
+        let path = PathBuf::from(expand_path(path.trim()));
+        if path.as_os_str().is_empty() {
+            return;
+        }
+        if path.is_dir() {
+            self.message = Some(format!("{} is a directory", path.display()));
+            return;
+        }
+        if path.exists() {
+            let question = format!("overwrite {}?", path.display());
+            self.confirm(Confirm::Xxd(path, start, end), &question);
+            return;
+        }
+        self.xxd(&path, start, end);
+    }
+
+    fn xxd(&mut self, path: &Path, start: u64, end: u64) {
+        let mut out = match File::create(path) {
+            Ok(out) => BufWriter::new(out),
+            Err(err) => {
+                self.message = Some(format!("error: {}: {}", path.display(), err));
+                return;
+            }
+        };
+        let mut write_result = Ok(());
+        let mut addr = start;
+        let result = self.read_range("xxd", start, end, &mut |buf| {
+            if write_result.is_ok() {
+                write_result = xxd::write_lines(&mut out, addr, buf);
+            }
+            addr += buf.len() as u64;
+        });
+        match result.and(write_result).and_then(|_| out.flush()) {
+            Ok(_) => {
+                self.message = Some(format!(
+                    "wrote hexdump of {} bytes to {}",
+                    end - start,
+                    path.display()
+                ))
+            }
+            Err(err) => {
+                _ = std::fs::remove_file(path);
+                self.message = Some(if err.kind() == std::io::ErrorKind::Interrupted {
+                    "cancelled".to_owned()
+                } else {
+                    format!("error: {}: {}", path.display(), err)
+                });
+            }
+        }
+        self.update_needed = true;
+    }
+
+    // :patch FILE applies a hexdump in the format of xxd, as a single
+    // change for undo
+    fn command_patch(&mut self, arg: &str) {
+        if !self.check_writable() {
+            return;
+        }
+        let path = expand_path(arg.trim());
+        if path.is_empty() {
+            self.message = Some("usage: :patch FILE".to_owned());
+            return;
+        }
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) => {
+                self.message = Some(format!("error: {}: {}", path, err));
+                return;
+            }
+        };
+        let result = xxd::parse(&text).and_then(|runs| patch_edits(&mut self.edits, &runs));
+        match result {
+            Ok((changes, count)) => {
+                let resized = changes.iter().any(|change| change.resizes());
+                self.push_undo(changes);
+                if resized {
+                    self.size_changed();
+                }
+                self.message = Some(format!("patched {} bytes from {}", count, path));
+            }
+            Err(errors) if errors.len() == 1 => {
+                self.message = Some(format!("error: {}", errors[0]));
+            }
+            Err(errors) => {
+                self.message = Some(format!("{} errors in {}", errors.len(), path));
+                self.open_info_overlay("patch errors", errors);
+            }
+        }
+        self.update_needed = true;
+    }
+
+    // show a modified byte under the cursor
+    // Collapsed runs may have changed, so squeezed rows are laid out anew
+    fn redraw_edit(&mut self) {
+        if self.array_view || self.squeeze || self.update_needed {
+            self.update_needed = true;
+            return;
+        }
+        self.draw_cursor();
+        self.draw_bottom_pane();
+        self.stdout.flush().unwrap();
+    }
+
+    // redraw the rows on screen that hold changed bytes
+    fn redraw_changes(&mut self, changes: &[Change]) {
+        if self.array_view || self.squeeze || self.update_needed {
+            self.update_needed = true;
+            return;
+        }
+        let top = self.offset / self.row_bytes;
+        let bottom = top + self.view_height as u64;
+        let mut rows = BTreeSet::new();
+        for change in changes {
+            let first = (change.pos / self.row_bytes).max(top);
+            let last = (change.end().saturating_sub(1) / self.row_bytes).min(bottom - 1);
+            rows.extend(first..=last);
+        }
+        for row in rows {
+            self.draw_hexdump_line((row - top) as u16);
+        }
+        self.draw_cursor();
+        self.draw_bottom_pane();
+        self.stdout.flush().unwrap();
+    }
+
+    // ]c and [c jump between modified ranges, wrapping around
+    fn key_next_change(&mut self, forward: bool) {
+        if self.edits.is_empty() {
+            self.message = Some("no changes".to_owned());
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
+            return;
+        }
+
+        let pos = self.cursor_pos();
+        let found = if forward {
+            self.edits.next_range(pos)
+        } else {
+            self.edits.prev_range(pos)
+        };
+        let wrapped = found.is_none();
+        let (start, _) = match found {
+            Some(range) => range,
+            None if forward => self.edits.ranges().next().unwrap(),
+            None => self.edits.ranges().last().unwrap(),
+        };
+        let ranges: Vec<(u64, u64)> = self.edits.ranges().collect();
+        let idx = ranges.iter().position(|&(s, _)| s == start).unwrap();
+
+        self.jump_to(start);
+        let mut message = format!("change {}/{}", idx + 1, ranges.len());
+        if wrapped {
+            message.push_str(if forward {
+                "  (wrapped to start)"
+            } else {
+                "  (wrapped to end)"
+            });
+        }
+        self.message = Some(message);
+        self.draw_statusline();
+        self.stdout.flush().unwrap();
+    }
+
+    // :stride suggests record sizes, :stride N sets one, :stride off clears it
+    fn command_stride(&mut self, arg: &str) {
+        match arg {
+            "" => self.suggest_stride(),
+            "off" => self.set_stride(None),
+            _ => match parse_number(arg) {
+                Some(0) => self.set_stride(None),
+                Some(stride) => self.set_stride(Some(stride)),
+                None => self.message = Some(format!("invalid record size: {}", arg)),
+            },
+        }
+    }
+
+    fn suggest_stride(&mut self) {
+        let end = self.filesize.min(stats::STRIDE_SAMPLE as u64);
+        let mut stats = stats::Stats::new(0);
+        if let Err(err) = self.read_range("stride", 0, end, &mut |data| stats.update(data)) {
+            self.message = Some(range_error(&err));
+            return;
+        }
+        let candidates = stats.stride_candidates(8);
+        if candidates.is_empty() {
+            self.message = Some("no record size found; set one with :stride N".to_owned());
+            return;
+        }
+
+        let items = candidates
+            .iter()
+            .map(|&(stride, score)| OverlayItem {
+                text: format!("{:>5} bytes  {:3.0}% match", stride, score * 100.0),
+                key: String::new(),
+                enabled: true,
+            })
+            .collect();
+        let strides = candidates
+            .iter()
+            .map(|&(stride, _)| stride as u64)
+            .collect();
+        self.overlay = Some((
+            OverlayKind::Stride(strides),
+            Overlay::new("record size", items, false),
+        ));
+        self.update_needed = true;
+    }
+
+    fn set_stride(&mut self, stride: Option<u64>) {
+        self.stride = stride;
+        self.message = Some(match stride {
+            Some(stride) => format!("record size {} (0x{:x})", stride, stride),
+            None => "record size off".to_owned(),
+        });
+        self.update_needed = true;
+    }
+
+    // ]r and [r move to the next or previous record boundary
+    fn key_next_record(&mut self, forward: bool) {
+        let stride = match self.stride {
+            Some(stride) => stride,
+            None => {
+                self.message = Some("no record size set; use :stride".to_owned());
+                self.draw_statusline();
+                self.stdout.flush().unwrap();
+                return;
+            }
+        };
+        let pos = self.cursor_pos();
+        let record = pos / stride;
+        let target = if forward {
+            (record + 1) * stride
+        } else if pos.is_multiple_of(stride) {
+            record.saturating_sub(1) * stride
+        } else {
+            record * stride
+        };
+        if target >= self.filesize {
+            return;
+        }
+        self.jump_to(target);
+    }
+
+    fn key_select(&mut self) {
+        if self.selection_anchor.is_some() {
+            self.clear_selection();
+        } else {
+            self.selection_anchor = Some(self.cursor_pos());
+            self.update_needed = true;
+        }
+    }
+
+    fn clear_selection(&mut self) {
+        self.selection_anchor = None;
+        self.update_needed = true;
+    }
+
+    fn key_preview(&mut self) {
+        self.open_prompt(
+            PromptKind::Preview,
+            "preview (xor KEY, rot13, add N, sub N): ",
+        );
+    }
+
+    fn set_preview(&mut self, answer: &str) {
+        if answer.trim().is_empty() {
+            return;
+        }
+        match Transform::parse(answer) {
+            Ok(transform) => {
+                self.preview = Some(transform);
+                self.preview_range = None;
+                self.update_needed = true;
+            }
+            Err(msg) => self.message = Some(format!("error: {}", msg)),
+        }
+    }
+
+    fn clear_preview(&mut self) {
+        if self.preview.is_none() {
+            return;
+        }
+        self.preview = None;
+        self.preview_range = None;
+        self.update_needed = true;
+    }
+
+    fn toggle_endianness(&mut self) {
+        if self.endian == LittleEndian {
+            self.endian = BigEndian;
+        } else {
+            self.endian = LittleEndian;
+        }
+        if self.array_view || (self.group_endian && self.group_size > 1) {
+            // the groups change order, too
+            self.update_needed = true;
+            return;
+        }
+        self.draw_bottom_pane();
+        self.stdout.flush().unwrap();
+    }
+
+    fn key_little_endian(&mut self) {
+        if self.endian == LittleEndian {
+            return;
+        }
+        self.toggle_endianness();
+    }
+
+    fn key_big_endian(&mut self) {
+        if self.endian == BigEndian {
+            return;
+        }
+        self.toggle_endianness();
+    }
+
+    fn key_right(&mut self, count: u64) {
+        // cursor can not go beyond EOF
+        let pos = self.cursor_pos();
+        self.move_cursor(pos.saturating_add(count).min(self.filesize - 1));
+    }
+
+    fn key_left(&mut self, count: u64) {
+        let pos = self.cursor_pos();
+        self.move_cursor(pos.saturating_sub(count));
+    }
+
+    fn key_down(&mut self, count: u64) {
+        if self.squeeze {
+            self.squeeze_down(count);
+            return;
+        }
+        // put cursor position at EOF when going past the end
+        let pos = self.cursor_pos();
+        let delta = count.saturating_mul(self.row_bytes);
+        self.move_cursor(pos.saturating_add(delta).min(self.filesize - 1));
+    }
+
+    fn key_up(&mut self, count: u64) {
+        if self.squeeze {
+            self.squeeze_up(count);
+            return;
+        }
+        // put cursor position at start when going past the start
+        let pos = self.cursor_pos();
+        self.move_cursor(pos.saturating_sub(count.saturating_mul(self.row_bytes)));
+    }
 
-        if pos + 3 < self.filesize {
-            let bytes32 = [
-                self.at(pos),
-                self.at(pos + 1),
-                self.at(pos + 2),
-                self.at(pos + 3),
-            ];
-            let data_i32;
-            let data_u32;
-            if self.endian == LittleEndian {
-                data_i32 = i32::from_le_bytes(bytes32);
-                data_u32 = u32::from_le_bytes(bytes32);
-            } else {
-                data_i32 = i32::from_be_bytes(bytes32);
-                data_u32 = u32::from_be_bytes(bytes32);
+    // down by rows on screen; a collapsed run is one step
+    fn squeeze_down(&mut self, count: u64) {
+        let pos = self.cursor_pos();
+        let last_row = (self.filesize - 1) / self.row_bytes * self.row_bytes;
+        let mut row = pos / self.row_bytes * self.row_bytes;
+        for _ in 0..count {
+            if row >= last_row {
+                // put cursor position at EOF when going past the end
+                self.move_cursor(self.filesize - 1);
+                return;
             }
-            write!(
-                linebuf,
-                "  i32: {:<20}  u32: {:<20}  0x{:08x} ",
-                data_i32, data_u32, data_u32
-            )
-            .unwrap();
+            row += self.row_bytes;
+            if self.is_repeat(row) {
+                row = self.run_end(row).min(last_row);
+            }
+        }
+        self.move_cursor((row + pos % self.row_bytes).min(self.filesize - 1));
+    }
 
-            let data_f32;
-            if self.endian == LittleEndian {
-                data_f32 = f32::from_le_bytes(bytes32);
-            } else {
-                data_f32 = f32::from_be_bytes(bytes32);
+    // up by rows on screen; a collapsed run is one step
+    fn squeeze_up(&mut self, count: u64) {
+        let pos = self.cursor_pos();
+        let mut row = pos / self.row_bytes * self.row_bytes;
+        for _ in 0..count {
+            if row == 0 {
+                // put cursor position at start when going past the start
+                self.move_cursor(0);
+                return;
+            }
+            row -= self.row_bytes;
+            if self.is_repeat(row) {
+                row = self.run_start(row);
             }
-            write!(f32_value, "{:20.20}", PrettyPrintFloat(data_f32 as f64)).unwrap();
-        } else {
-            write!(
-                linebuf,
-                "  i32: {:<20}  u32: {:<20}  --         ",
-                "--", "--",
-            )
-            .unwrap();
         }
-        self.stdout
-            .queue(cursor::MoveTo(0, y))
-            .unwrap()
-            .queue(style::Print(&linebuf))
-            .unwrap();
-        linebuf.clear();
+        self.move_cursor(row + pos % self.row_bytes);
     }
 
-    fn draw_info_i64(&mut self, y: u16, pos: u64) {
-        let mut linebuf = String::new();
+    // move to the next byte that differs from the one under the cursor,
+    // skipping over padding like runs of 00 or FF
+    fn key_skip_padding(&mut self, forward: bool) {
+        let pos = self.cursor_pos();
+        let padding = self.at(pos);
+        let result = if forward {
+            self.scan_range("scan", pos + 1, self.filesize, true, &mut |addr, data| {
+                data.iter()
+                    .position(|&byte| byte != padding)
+                    .map(|i| addr + i as u64)
+            })
+        } else {
+            self.scan_range("scan", 0, pos, false, &mut |addr, data| {
+                data.iter()
+                    .rposition(|&byte| byte != padding)
+                    .map(|i| addr + i as u64)
+            })
+        };
+        match result {
+            Ok(Some(found)) => self.jump_to(found),
+            Ok(None) => self.message = Some("no more data".to_owned()),
+            Err(err) => self.message = Some(range_error(&err)),
+        }
+        self.update_needed = true;
+    }
 
-        if pos + 7 < self.filesize {
-            let bytes64 = [
-                self.at(pos),
-                self.at(pos + 1),
-                self.at(pos + 2),
-                self.at(pos + 3),
-                self.at(pos + 4),
-                self.at(pos + 5),
-                self.at(pos + 6),
-                self.at(pos + 7),
-            ];
-            let data_i64;
-            let data_u64;
-            if self.endian == LittleEndian {
-                data_i64 = i64::from_le_bytes(bytes64);
-                data_u64 = u64::from_le_bytes(bytes64);
-            } else {
-                data_i64 = i64::from_be_bytes(bytes64);
-                data_u64 = u64::from_be_bytes(bytes64);
-            }
-            write!(
-                linebuf,
-                "  i64: {:<20}  u64: {:<20}  0x{:016x} ",
-                data_i64, data_u64, data_u64
-            )
-            .unwrap();
+    // move to the start of the next run of printable characters that
+    // is at least min_string long; the string under the cursor is skipped
+    fn key_next_string(&mut self, forward: bool) {
+        let pos = self.cursor_pos();
+        let min_len = self.min_string;
+        let charset = self.charset;
+        let is_printable = move |byte: u8| charset.decode(byte).is_some();
+        let mut skipping = is_printable(self.at(pos));
+        let mut run_len = 0u64;
+
+        let result = if forward {
+            let mut run_start = 0;
+            self.scan_range("scan", pos + 1, self.filesize, true, &mut |addr, data| {
+                for (i, &byte) in data.iter().enumerate() {
+                    if !is_printable(byte) {
+                        skipping = false;
+                        run_len = 0;
+                        continue;
+                    }
+                    if skipping {
+                        continue;
+                    }
+                    if run_len == 0 {
+                        run_start = addr + i as u64;
+                    }
+                    run_len += 1;
+                    if run_len >= min_len {
+                        return Some(run_start);
+                    }
+                }
+                None
+            })
         } else {
-            write!(
-                linebuf,
-                "  i64: {:<20}  u64: {:<20}  --                 ",
-                "--", "--",
-            )
-            .unwrap();
+            // going backward, a run is found once it has ended
+            self.scan_range("scan", 0, pos, false, &mut |addr, data| {
+                for (i, &byte) in data.iter().enumerate().rev() {
+                    if is_printable(byte) {
+                        if !skipping {
+                            run_len += 1;
+                        }
+                        continue;
+                    }
+                    if run_len >= min_len {
+                        return Some(addr + i as u64 + 1);
+                    }
+                    skipping = false;
+                    run_len = 0;
+                }
+                None
+            })
+            .map(|found| found.or((run_len >= min_len).then_some(0)))
+        };
+        match result {
+            Ok(Some(found)) => self.show_near_top(found),
+            Ok(None) => self.message = Some("no more strings".to_owned()),
+            Err(err) => self.message = Some(range_error(&err)),
         }
-        self.stdout
-            .queue(cursor::MoveTo(0, y))
-            .unwrap()
-            .queue(style::Print(&linebuf))
-            .unwrap();
-        linebuf.clear();
+        self.update_needed = true;
     }
 
-    fn draw_info_f32_f64_and_endianness(&mut self, y: u16, pos: u64) {
-        let mut linebuf = String::new();
+    // the search prompt remembers the view, to get back to it after
+    // searching while typing
+    fn open_search_prompt(&mut self, forward: bool) {
+        let label = if forward {
+            "search: "
+        } else {
+            "search backward: "
+        };
+        self.open_prompt(PromptKind::Search { forward }, label);
+        let saved = (self.offset, self.cursor_pos());
+        let prompt = &mut self.prompt.as_mut().unwrap().1;
+        prompt.saved = Some(saved);
+        prompt.set_history(&self.search_history);
+        self.search_match = None;
+        self.highlight_search = false;
+        self.update_needed = true;
+    }
 
-        let mut f32_value = String::new();
+    fn restore_view(&mut self, (offset, pos): (u64, u64)) {
+        if !self.array_view {
+            self.offset = offset;
+        }
+        self.jump_to(pos);
+        self.update_needed = true;
+    }
 
-        if pos + 3 < self.filesize {
-            let bytes32 = [
-                self.at(pos),
-                self.at(pos + 1),
-                self.at(pos + 2),
-                self.at(pos + 3),
-            ];
-            let data_f32;
-            if self.endian == LittleEndian {
-                data_f32 = f32::from_le_bytes(bytes32);
-            } else {
-                data_f32 = f32::from_be_bytes(bytes32);
+    // move to the first match of what is typed so far, from where the
+    // search started; this looks only a little ahead, so that typing
+    // does not stall on a big file
+    fn incremental_search(&mut self) {
+        let (input, saved, forward) = match &self.prompt {
+            Some((PromptKind::Search { forward }, prompt)) => {
+                (prompt.input.clone(), prompt.saved, *forward)
             }
-            write!(f32_value, "{:20.20}", PrettyPrintFloat(data_f32 as f64)).unwrap();
+            _ => return,
+        };
+        let saved = match saved {
+            Some(saved) => saved,
+            None => return,
+        };
+        self.search_match = None;
+        self.restore_view(saved);
+        let (input, scope) = match self.parse_search_scope(&input) {
+            Ok((input, scope)) => (input.to_owned(), scope.or(self.selection())),
+            Err(_) => return,
+        };
+        let pattern = match search::Pattern::parse(&input, self.endian) {
+            Ok(pattern) => pattern,
+            Err(_) => return,
+        };
+        let pos = saved.1;
+        let (lo, hi) = scope.unwrap_or((0, self.filesize));
+        let range = if forward {
+            let end = hi.min(pos + 1 + INCREMENTAL_WINDOW);
+            (lo.max(pos + 1), end, end)
         } else {
-            write!(f32_value, "{}", "--").unwrap();
+            let start = lo.max(pos.saturating_sub(INCREMENTAL_WINDOW));
+            (start, hi.min(pos + pattern.max_len() as u64 - 1), pos)
+        };
+        if let Ok(Some((found, len))) = self.find_range(&pattern, range, forward) {
+            self.jump_to(found);
+            self.search_match = Some((found, found + len));
         }
+    }
 
-        let mut f64_value = String::new();
+    // repeats of the last search are not added again
+    fn add_history(&mut self, answer: &str) {
+        let answer = answer.trim();
+        if self
+            .search_history
+            .last()
+            .is_some_and(|last| last == answer)
+        {
+            return;
+        }
+        self.search_history.push(answer.to_owned());
+        if self.search_history.len() > HISTORY_SIZE {
+            self.search_history.remove(0);
+        }
+        if self.save_history {
+            if let Err(err) = config::save_history(&self.search_history) {
+                self.message = Some(format!("error: {}", err));
+            }
+        }
+    }
 
-        if pos + 7 < self.filesize {
-            let bytes64 = [
-                self.at(pos),
-                self.at(pos + 1),
-                self.at(pos + 2),
-                self.at(pos + 3),
-                self.at(pos + 4),
-                self.at(pos + 5),
-                self.at(pos + 6),
-                self.at(pos + 7),
-            ];
+    // "START..END" as [START, END), where both may be expressions
+    fn parse_range(&self, text: &str) -> Result<(u64, u64), String> {
+        let vars = expr::Vars {
+            here: self.cursor_pos(),
+            end: self.filesize,
+        };
+        let (start, end) = text.split_once("..").ok_or("expected START..END")?;
+        let start = expr::eval(start, &vars).map_err(|err| format!("range start: {}", err))?;
+        let end = expr::eval(end, &vars)
+            .map_err(|err| format!("range end: {}", err))?
+            .min(self.filesize);
+        if start >= end {
+            return Err("empty range".to_owned());
+        }
+        Ok((start, end))
+    }
 
-            let data_f64;
-            if self.endian == LittleEndian {
-                data_f64 = f64::from_le_bytes(bytes64);
-            } else {
-                data_f64 = f64::from_be_bytes(bytes64);
+    // "PATTERN @START..END" keeps the search in [START, END)
+    fn parse_search_scope<'a>(&self, input: &'a str) -> Result<(&'a str, Option<Range>), String> {
+        match input.rsplit_once(" @") {
+            Some((pattern, range)) if range.contains("..") => {
+                Ok((pattern, Some(self.parse_range(range)?)))
             }
-            write!(f64_value, "{:20.20}", PrettyPrintFloat(data_f64)).unwrap();
-        } else {
-            write!(f64_value, "{}", "--").unwrap();
+            _ => Ok((input, None)),
         }
+    }
 
-        let s_endian;
-        if self.endian == LittleEndian {
-            s_endian = "little";
-        } else {
-            s_endian = "big";
+    // a search without a range stays in the selection, if any,
+    // and n and N stay there too
+    fn search_prompt(&mut self, answer: &str, forward: bool) {
+        if answer.trim().is_empty() {
+            return;
         }
-        write!(
-            linebuf,
-            "  f32: {:<20}  f64: {:<20}  {} endian   ",
-            f32_value, f64_value, s_endian
-        )
-        .unwrap();
-        self.stdout
-            .queue(cursor::MoveTo(0, y))
-            .unwrap()
-            .queue(style::Print(&linebuf))
-            .unwrap();
-        linebuf.clear();
+        self.add_history(answer);
+        let (answer, scope) = match self.parse_search_scope(answer) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                self.message = Some(format!("error: {}", err));
+                return;
+            }
+        };
+        let pattern = match search::Pattern::parse(answer, self.endian) {
+            Ok(pattern) => pattern,
+            Err(err) => {
+                self.message = Some(format!("error: {}", err));
+                return;
+            }
+        };
+        self.search_scope = scope.or(self.selection());
+        if self.search_scope.is_some() && self.selection_anchor.is_some() {
+            // the cursor moves, so the selection can not stay
+            self.selection_anchor = None;
+            self.update_needed = true;
+        }
+        self.search(&pattern, forward);
+        self.last_search = Some(pattern);
+        self.search_forward = forward;
     }
 
-    fn erase_cursor(&mut self) {
-        // erase cursor via overdraw
+    // move to the next match of pattern
+    // The search runs on a worker thread, so that the screen stays
+    // alive; it needs a file handle of its own
+    fn search(&mut self, pattern: &search::Pattern, forward: bool) {
+        let pos = self.cursor_pos();
+        if let Some(file) = self
+            .filename
+            .as_ref()
+            .and_then(|name| File::open(name).ok())
+        {
+            let spans = self.search_spans(pattern, pos, forward);
+            self.search_job = Some(search::Job::spawn(
+                file,
+                self.edits.clone(),
+                pattern.clone(),
+                spans,
+                forward,
+            ));
+            return;
+        }
+        let result = self.find(pattern, pos, forward);
+        self.search_done(pattern, result);
+    }
 
-        // cursor position in the hex dump view
-        let mut xpos = self.leftpane_width + self.cursor_x * 3;
-        if self.cursor_x >= 8 {
-            xpos += 1;
+    // check on the search running in the background
+    fn poll_search(&mut self) {
+        let job = match &self.search_job {
+            Some(job) => job,
+            None => return,
+        };
+        let mut progress = None;
+        let mut result = None;
+        while let Some(event) = job.poll() {
+            match event {
+                search::JobEvent::Progress(done, total) => progress = Some((done, total)),
+                search::JobEvent::Done(done) => {
+                    result = Some(done);
+                    break;
+                }
+            }
+        }
+        if let Some(result) = result {
+            let job = self.search_job.take().unwrap();
+            self.search_done(&job.pattern, result);
+        } else if let Some((done, total)) = progress {
+            let percent = done * 100 / total.max(1);
+            self.draw_progress(&format!(
+                "search: {}%, 0x{:x} bytes  (Esc to cancel)",
+                percent, done
+            ));
         }
-        let ypos = self.cursor_y;
-        let data_pos = self.offset + self.cursor_y as u64 * 16 + self.cursor_x as u64;
+    }
 
-        let byte = self.at(data_pos);
-        self.stdout
-            .queue(cursor::MoveTo(xpos, ypos))
-            .unwrap()
-            .queue(style::Print(format!("{:02X}", byte)))
-            .unwrap();
+    fn search_done(
+        &mut self,
+        pattern: &search::Pattern,
+        result: std::io::Result<Option<(u64, u64, bool)>>,
+    ) {
+        match result {
+            Ok(Some((found, len, wrapped))) => {
+                self.jump_to(found);
+                self.search_match = Some((found, found + len));
+                self.highlight_search = true;
+                let mut notes = Vec::new();
+                if let Some(encoding) = pattern.match_encoding(len as usize) {
+                    notes.push(format!("{} match", encoding));
+                }
+                if wrapped {
+                    notes.push("search wrapped".to_owned());
+                }
+                if !notes.is_empty() {
+                    self.message = Some(notes.join(", "));
+                }
+            }
+            Ok(None) if self.wrap_scan => self.message = Some("pattern not found".to_owned()),
+            Ok(None) => self.message = Some("no more matches".to_owned()),
+            Err(err) => self.message = Some(range_error(&err)),
+        }
+        self.update_needed = true;
+    }
 
-        // cursor position in right pane: ascii view
-        xpos = self.leftpane_width + self.centerpane_width + self.cursor_x;
+    // n repeats the last search, N searches the other way
+    fn key_search_next(&mut self, reverse: bool) {
+        let mut pattern = match self.last_search.take() {
+            Some(pattern) => pattern,
+            None => {
+                self.message = Some("no previous search".to_owned());
+                self.draw_statusline();
+                self.stdout.flush().unwrap();
+                return;
+            }
+        };
+        pattern.set_endian(self.endian);
+        self.search(&pattern, self.search_forward != reverse);
+        self.last_search = Some(pattern);
+    }
 
-        let mut c = self.at(data_pos) as char;
-        if !(c >= ' ' && c <= '~') {
-            c = '.';
+    // * and # find the byte under the cursor
+    fn key_find_byte(&mut self, forward: bool) {
+        let pos = self.cursor_pos();
+        let byte = self.at(pos);
+        let pattern = search::Pattern::new(&[byte]);
+        let result = self.find(&pattern, pos, forward);
+        self.last_search = Some(pattern);
+        self.search_forward = forward;
+        match result {
+            Ok(Some((found, _, wrapped))) => {
+                self.jump_to(found);
+                let distance = if found >= pos {
+                    format!("+0x{:x}", found - pos)
+                } else {
+                    format!("-0x{:x}", pos - found)
+                };
+                let mut message = format!("{:02X} at 0x{:08X} ({})", byte, found, distance);
+                if wrapped {
+                    message.push_str(", search wrapped");
+                }
+                self.message = Some(message);
+            }
+            Ok(None) => self.message = Some(format!("{:02X} not found", byte)),
+            Err(err) => self.message = Some(range_error(&err)),
         }
-        self.stdout
-            .queue(cursor::MoveTo(xpos, ypos))
-            .unwrap()
-            .queue(style::Print(format!("{c}")))
-            .unwrap();
+        self.update_needed = true;
     }
 
-    fn draw_cursor(&mut self) {
-        // draw cursor via overdraw
+    // move to the next or previous multiple of the alignment
+    fn key_align(&mut self, forward: bool, count: u64) {
+        let align = self.align;
+        let pos = self.cursor_pos();
+        let block = pos / align;
+        let target = if forward {
+            block.saturating_add(count).saturating_mul(align)
+        } else if pos.is_multiple_of(align) {
+            block.saturating_sub(count) * align
+        } else {
+            block.saturating_sub(count - 1) * align
+        };
+        self.jump_to(target.min(self.filesize - 1));
+    }
 
-        // cursor position in the hex dump view
-        let mut xpos = self.leftpane_width + self.cursor_x * 3;
-        if self.cursor_x >= 8 {
-            xpos += 1;
+    // change the number of bytes per row; the cursor stays on its byte
+    fn set_row_bytes(&mut self, row_bytes: u64) -> Result<(), String> {
+        if ![8, 16, 24, 32].contains(&row_bytes) {
+            return Err(format!(
+                "invalid cols: {} (must be 8, 16, 24 or 32)",
+                row_bytes
+            ));
+        }
+        if self.bin_view {
+            return Err("the binary view has its own bytes per row".to_owned());
         }
-        let ypos = self.cursor_y;
-        let data_pos = self.offset + self.cursor_y as u64 * 16 + self.cursor_x as u64;
+        self.relayout(row_bytes);
+        Ok(())
+    }
 
-        assert!(data_pos < self.filesize);
+    // lay out the panes for a number of bytes per row
+    fn relayout(&mut self, row_bytes: u64) {
+        let pos = self.cursor_pos();
+        self.row_bytes = row_bytes;
+        self.hscroll = 0;
+        self.layout_columns();
+        self.offset = self.offset / row_bytes * row_bytes;
+        if self.filesize > 0 {
+            self.jump_to(pos.min(self.filesize - 1));
+        }
+        self.update_needed = true;
+    }
 
-        let byte = self.at(data_pos);
-        self.stdout
-            .queue(cursor::MoveTo(xpos, ypos))
-            .unwrap()
-            .queue(style::PrintStyledContent(format!("{:02X}", byte).reverse()))
-            .unwrap();
+    // as many byte columns as fit the terminal: the whole row, or else
+    // a multiple of 8, that the panes scroll sideways by
+    fn layout_columns(&mut self) {
+        let fits = |n: u64| {
+            let (centerpane_width, rightpane_width) = self.pane_widths(n, self.group_size.min(n));
+            self.leftpane_width + centerpane_width + rightpane_width <= self.terminal_width
+        };
+        self.view_cols = if fits(self.row_bytes) {
+            self.row_bytes
+        } else {
+            // very narrow terminals get fewer than 8
+            (1..self.row_bytes / 8)
+                .rev()
+                .map(|n| n * 8)
+                .chain([4, 2])
+                .find(|&n| fits(n))
+                .unwrap_or(1)
+        };
+        // a group can not be wider than the view
+        self.group_size = self.group_size.min(self.view_cols);
+        (self.centerpane_width, self.rightpane_width) =
+            self.pane_widths(self.view_cols, self.group_size);
+        let step = self.hscroll_step();
+        self.hscroll = self.hscroll.min(self.row_bytes - 1) / step * step;
+        self.hscroll_to_cursor();
+        self.update_needed = true;
+    }
 
-        // cursor position in right pane: ascii view
-        xpos = self.leftpane_width + self.centerpane_width + self.cursor_x;
+    // the horizontal scroll moves by this many columns
+    fn hscroll_step(&self) -> u64 {
+        self.view_cols.min(8)
+    }
 
-        let mut c = self.at(data_pos) as char;
-        if !(c >= ' ' && c <= '~') {
-            c = '.';
-        }
-        self.stdout
-            .queue(cursor::MoveTo(xpos, ypos))
-            .unwrap()
-            .queue(style::PrintStyledContent(format!("{c}").reverse()))
-            .unwrap();
+    // range of byte columns in a row that are on screen
+    fn visible_columns(&self) -> (u64, u64) {
+        (
+            self.hscroll,
+            (self.hscroll + self.view_cols).min(self.row_bytes),
+        )
     }
 
-    fn key_event(&mut self, key_event: &KeyEvent) {
-        match key_event.code {
-            KeyCode::Right => self.key_right(),
-            KeyCode::Left => self.key_left(),
-            KeyCode::Up => self.key_up(),
-            KeyCode::Down => self.key_down(),
-            KeyCode::PageUp => self.key_pageup(),
-            KeyCode::PageDown => self.key_pagedown(),
-            KeyCode::Home => self.key_home(),
-            KeyCode::End => self.key_end(),
-            KeyCode::Char('e') => self.toggle_endianness(),
-            KeyCode::Char('l') => self.key_little_endian(),
-            KeyCode::Char('b') => self.key_big_endian(),
-            _ => {}
+    // scroll the hex and character panes sideways, to show the cursor
+    // Returns true if they scrolled
+    fn hscroll_to_cursor(&mut self) -> bool {
+        let col = self.cursor_x as u64;
+        let step = self.hscroll_step();
+        let old_hscroll = self.hscroll;
+        if col < self.hscroll {
+            self.hscroll = col / step * step;
+        } else if col >= self.hscroll + self.view_cols {
+            self.hscroll = (col + 1 - self.view_cols).div_ceil(step) * step;
         }
+        self.hscroll != old_hscroll
     }
 
-    fn toggle_endianness(&mut self) {
-        if self.endian == LittleEndian {
-            self.endian = BigEndian;
+    // widths of the hex pane and the character pane
+    fn pane_widths(&self, row_bytes: u64, group_size: u64) -> (u16, u16) {
+        let n = row_bytes as u16;
+        // hex bytes, a space per group, an extra space every 8 bytes,
+        // and spacing: 2
+        let centerpane_width = n * self.cell_digits() + n / group_size as u16 + (n - 1) / 8 + 1;
+        let rightpane_width = n + 1; // ascii + spacing: 1
+        (centerpane_width, rightpane_width)
+    }
+
+    // width of a byte in the centre pane
+    fn cell_digits(&self) -> u16 {
+        if self.bin_view {
+            8
         } else {
-            self.endian = LittleEndian;
+            2
         }
-        self.draw_bottom_pane();
-        self.stdout.flush().unwrap();
     }
 
-    fn key_little_endian(&mut self) {
-        if self.endian == LittleEndian {
-            return;
+    fn format_cell(&self, byte: u8) -> String {
+        if self.bin_view {
+            format!("{:08b}", byte)
+        } else {
+            format!("{:02X}", byte)
         }
-        self.toggle_endianness();
     }
 
-    fn key_big_endian(&mut self) {
-        if self.endian == BigEndian {
-            return;
+    // the binary view shows as many bytes per row as fit: 8, 6 or 4
+    fn bin_row_bytes(&self) -> u64 {
+        [8, 6, 4]
+            .into_iter()
+            .find(|&row_bytes| {
+                let (centerpane_width, rightpane_width) = self.pane_widths(row_bytes, 1);
+                self.leftpane_width + centerpane_width + rightpane_width <= self.terminal_width
+            })
+            .unwrap_or(4)
+    }
+
+    // the terminal changed width; rows that do not fit scroll sideways
+    fn fit_width(&mut self) {
+        if self.bin_view {
+            self.relayout(self.bin_row_bytes());
+        } else {
+            self.layout_columns();
         }
-        self.toggle_endianness();
     }
 
-    fn key_right(&mut self) {
-        // cursor can not go beyond EOF
-        let pos = self.offset + self.cursor_y as u64 * 16 + self.cursor_x as u64 + 1;
-        if pos >= self.filesize {
+    // switch the centre pane between hex and binary; the cursor stays on
+    // its byte
+    fn key_binary_view(&mut self) {
+        if self.array_view {
             return;
         }
+        self.bin_view = !self.bin_view;
+        let (row_bytes, group_size) = if self.bin_view {
+            self.hex_layout = (self.row_bytes, self.group_size);
+            (self.bin_row_bytes(), 1)
+        } else {
+            self.hex_layout
+        };
+        self.group_size = group_size;
+        self.relayout(row_bytes);
+    }
 
-        self.erase_cursor();
+    // position of a byte in its group, when written out at the given slot
+    // In little endian order, the groups may show the last byte first
+    fn group_index(&self, slot: u64) -> u64 {
+        if self.group_endian && self.endian == LittleEndian {
+            self.group_size - 1 - slot
+        } else {
+            slot
+        }
+    }
 
-        self.cursor_x += 1;
-        if self.cursor_x >= 16 {
-            self.cursor_x = 0;
-            self.cursor_y += 1;
-            if self.cursor_y >= self.view_height {
-                self.cursor_y = self.view_height - 1;
-                // scroll
-                self.offset += 16;
-                self.update_needed = true;
-            }
+    fn set_group_size(&mut self, group_size: u64) -> Result<(), String> {
+        if ![1, 2, 4, 8].contains(&group_size) {
+            return Err(format!(
+                "invalid group: {} (must be 1, 2, 4 or 8)",
+                group_size
+            ));
         }
+        if self.bin_view {
+            return Err("bytes are not grouped in the binary view".to_owned());
+        }
+        self.group_size = group_size;
+        self.layout_columns();
+        Ok(())
+    }
 
-        if !self.update_needed {
-            self.update_cursor();
+    // cycle the groups of the hex pane between 1, 2, 4 and 8 bytes
+    fn key_cycle_group(&mut self) {
+        let group_size = match self.group_size {
+            1 => 2,
+            2 => 4,
+            4 => 8,
+            _ => 1,
+        };
+        if let Err(msg) = self.set_group_size(group_size) {
+            self.message = Some(msg);
+            return;
         }
+        self.message = Some(if group_size == 1 {
+            "bytes are not grouped".to_owned()
+        } else {
+            format!("groups of {} bytes", group_size)
+        });
     }
 
-    fn key_left(&mut self) {
-        let pos = self.offset + self.cursor_y as u64 * 16 + self.cursor_x as u64;
-        if pos == 0 {
+    // cycle the bytes per row between 8, 16, 24 and 32
+    fn key_cycle_cols(&mut self) {
+        if self.bin_view {
             return;
         }
+        let row_bytes = self.row_bytes % 32 + 8;
+        if self.set_row_bytes(row_bytes).is_ok() {
+            self.message = Some(format!("{} bytes per row", self.row_bytes));
+        }
+    }
 
-        self.erase_cursor();
+    // the terminal was resized; the screen is drawn anew
+    fn resize(&mut self, width: u16, height: u16) {
+        self.terminal_width = width.max(MIN_WIDTH);
+        self.terminal_height = height.max(10);
+        self.view_width = self.terminal_width.min(80);
+        self.layout_rows();
+        self.full_redraw = true;
+        self.update_needed = true;
+        if self.fd.is_none() {
+            return;
+        }
 
-        if self.cursor_x == 0 {
-            if self.cursor_y == 0 {
-                // scroll
-                self.offset -= 16;
-                self.update_needed = true;
-            } else {
-                self.cursor_y -= 1;
-            }
-            self.cursor_x = 15;
+        self.fit_width();
+        if self.array_view {
+            self.array_scroll_into_view();
         } else {
-            self.cursor_x -= 1;
+            // keep the cursor on screen, on its byte
+            let pos = self.cursor_pos();
+            let row = pos / self.row_bytes * self.row_bytes;
+            let one_page = self.view_height as u64 * self.row_bytes;
+            if row >= self.offset + one_page {
+                self.offset = row + self.row_bytes - one_page;
+            }
+            self.offset = self.offset.min(self.end_offset());
+            self.cursor_y = ((row - self.offset) / self.row_bytes) as u16;
+            self.cursor_x = (pos % self.row_bytes) as u16;
         }
+    }
 
-        if !self.update_needed {
-            self.update_cursor();
+    // a theme from the config file, or a built-in one
+    fn set_theme(&mut self, name: &str) -> Result<(), String> {
+        self.theme = self
+            .themes
+            .iter()
+            .find(|theme| theme.name == name)
+            .cloned()
+            .or_else(|| Theme::builtin(name))
+            .ok_or_else(|| format!("unknown theme: {}", name))?;
+        self.full_redraw = true;
+        self.update_needed = true;
+        Ok(())
+    }
+
+    // which screen rows the view takes, below the title line and
+    // around the status bar, the ruler and the info pane
+    fn layout_rows(&mut self) {
+        let bar = (self.status_bar != StatusBar::Off) as u16;
+        let pane = if self.info_pane { 6 } else { 0 };
+        self.view_top = 1 + (self.status_bar == StatusBar::Top) as u16;
+        self.view_height = self.terminal_height - 2 - pane - bar;
+        self.ruler_y = None;
+        // leave at least one row for the hexdump
+        if self.ruler && self.view_height > 1 {
+            self.ruler_y = Some(self.view_top);
+            self.view_top += 1;
+            self.view_height -= 1;
         }
     }
 
-    fn key_down(&mut self) {
-        // cursor can not go beyond EOF
-        let pos = self.offset + (self.cursor_y as u64 + 1) * 16 + self.cursor_x as u64;
-        if pos >= self.filesize {
-            // put cursor position at EOF
-            let pos = (self.filesize - 1 - self.offset) as u16;
-            let cy = pos / 16;
-            let cx = pos % 16;
+    fn key_cycle_align(&mut self) {
+        self.align = match self.align {
+            16 => 256,
+            256 => 512,
+            512 => 4096,
+            _ => 16,
+        };
+        self.draw_bottom_pane();
+        self.stdout.flush().unwrap();
+    }
 
-            if self.cursor_x != cx || self.cursor_y != cy {
-                self.erase_cursor();
-                self.cursor_x = cx;
-                self.cursor_y = cy;
-                self.update_cursor();
-            }
+    // scroll so that the cursor row is in the middle of the view
+    fn key_center(&mut self) {
+        let pos = self.cursor_pos();
+        let row = pos / self.row_bytes * self.row_bytes;
+        let half = self.view_height as u64 / 2 * self.row_bytes;
+        let offset = row.saturating_sub(half).min(self.end_offset());
+        if offset == self.offset {
             return;
         }
+        self.offset = offset;
+        self.cursor_y = ((row - offset) / self.row_bytes) as u16;
+        self.cursor_x = (pos % self.row_bytes) as u16;
+        self.update_needed = true;
+    }
 
-        self.erase_cursor();
-
-        self.cursor_y += 1;
-        if self.cursor_y >= self.view_height {
-            self.cursor_y = self.view_height - 1;
-            // scroll
-            self.offset += 16;
-            self.update_needed = true;
+    // move the cursor to pos, and scroll so that a few rows of
+    // context show above it
+    fn show_near_top(&mut self, pos: u64) {
+        if self.array_view {
+            self.jump_to(pos);
+            return;
         }
+        let row = pos / self.row_bytes * self.row_bytes;
+        self.offset = row
+            .saturating_sub(2 * self.row_bytes)
+            .min(self.end_offset());
+        self.cursor_y = ((row - self.offset) / self.row_bytes) as u16;
+        self.cursor_x = (pos % self.row_bytes) as u16;
+        self.update_needed = true;
+    }
 
-        if !self.update_needed {
-            self.update_cursor();
+    fn set_mark(&mut self, name: char) {
+        let pos = self.cursor_pos();
+        self.marks.insert(name, pos);
+        self.message = Some(format!("mark {} at 0x{:08X}", name, pos));
+        self.draw_statusline();
+        self.stdout.flush().unwrap();
+    }
+
+    fn goto_mark(&mut self, name: char) {
+        match self.marks.get(&name) {
+            Some(&pos) => self.jump_to(pos),
+            None => {
+                self.message = Some(format!("mark {} is not set", name));
+                self.draw_statusline();
+                self.stdout.flush().unwrap();
+            }
         }
     }
 
-    fn key_up(&mut self) {
-        let pos = self.offset + self.cursor_y as u64 * 16 + self.cursor_x as u64;
-        if pos == 0 {
+    fn list_marks(&mut self) {
+        if self.marks.is_empty() {
+            self.message = Some("no marks set; set one with m<letter>".to_owned());
+            self.draw_statusline();
+            self.stdout.flush().unwrap();
             return;
         }
+        let mut marks: Vec<(char, u64)> = self.marks.iter().map(|(&c, &pos)| (c, pos)).collect();
+        marks.sort();
+
+        let items = marks
+            .iter()
+            .map(|&(name, pos)| OverlayItem {
+                text: format!("{}  0x{:08X}  {}", name, pos, pos),
+                key: name.to_string(),
+                enabled: true,
+            })
+            .collect();
+        let offsets = marks.iter().map(|&(_, pos)| pos).collect();
+        self.overlay = Some((
+            OverlayKind::Jump(offsets),
+            Overlay::new("marks", items, false),
+        ));
+        self.update_needed = true;
+    }
 
-        self.erase_cursor();
+    // move by words, snapping to word alignment
+    fn key_word(&mut self, forward: bool, count: u64) {
+        let size = self.word_size;
+        let pos = self.cursor_pos();
+        let word = pos / size;
+        let target = if forward {
+            word.saturating_add(count).saturating_mul(size)
+        } else if pos.is_multiple_of(size) {
+            word.saturating_sub(count) * size
+        } else {
+            word.saturating_sub(count - 1) * size
+        };
+        let target = target.min(self.filesize - 1);
+        if self.array_view {
+            self.jump_to(target);
+        } else {
+            self.move_cursor(target);
+        }
+    }
 
-        if pos < 16 {
-            // put cursor position at start
-            self.offset = 0;
-            self.cursor_x = 0;
-            self.cursor_y = 0;
+    fn key_word_size(&mut self) {
+        self.word_size = match self.word_size {
+            2 => 4,
+            4 => 8,
+            _ => 2,
+        };
+        self.draw_bottom_pane();
+        self.stdout.flush().unwrap();
+    }
 
-            self.update_cursor();
+    // move the cursor, scrolling just enough to keep it in view
+    fn move_cursor(&mut self, pos: u64) {
+        if pos == self.cursor_pos() {
             return;
         }
+        let row = pos / self.row_bytes * self.row_bytes;
+        let one_page = self.view_height as u64 * self.row_bytes;
 
-        if self.cursor_y == 0 {
-            // scroll
-            self.offset -= 16;
+        if row < self.offset {
+            // scroll up
+            self.offset = row;
+            self.update_needed = true;
+        } else if row >= self.offset + one_page {
+            // scroll down
+            self.offset = row + self.row_bytes - one_page;
             self.update_needed = true;
         } else {
-            self.cursor_y -= 1;
+            self.erase_cursor();
         }
+        self.cursor_y = ((row - self.offset) / self.row_bytes) as u16;
+        self.cursor_x = (pos % self.row_bytes) as u16;
 
         if !self.update_needed {
             self.update_cursor();
         }
     }
 
-    fn key_pageup(&mut self) {
-        let one_page = self.view_height as u64 * 16;
-        let pos = self.offset + self.cursor_y as u64 * 16;
-
-        if pos < one_page {
-            if self.cursor_y == 0 {
-                if self.cursor_x == 0 {
-                    return;
-                }
+    fn key_pageup(&mut self, count: u64) {
+        let rows = self.view_height as u64 * count;
+        self.scroll_rows(-(rows.min(i64::MAX as u64) as i64));
+    }
 
-                self.erase_cursor();
-                self.cursor_x = 0;
-                self.update_cursor();
-                return;
-            }
+    fn key_pagedown(&mut self, count: u64) {
+        let one_page = self.view_height as u64 * self.row_bytes;
+        let step = one_page.saturating_mul(count);
+        let end_offset = self.end_offset();
 
-            self.erase_cursor();
-            self.cursor_y = 0;
-            self.update_cursor();
+        if self.offset.saturating_add(step) >= end_offset {
+            self.key_end();
             return;
         }
 
-        if pos < one_page * 2 {
-            self.offset = 0;
-            self.cursor_y = ((pos - one_page) / 16) as u16;
-            self.update_needed = true;
-            return;
-        }
+        self.scroll_rows((step / self.row_bytes) as i64);
+    }
 
-        assert!(self.offset >= one_page);
-        self.offset -= one_page;
-        self.update_needed = true;
+    // Ctrl-D and Ctrl-U scroll by half a page
+    fn key_half_page(&mut self, forward: bool, count: u64) {
+        let rows = (self.view_height as u64 / 2).max(1).saturating_mul(count);
+        let rows = rows.min(i64::MAX as u64) as i64;
+        self.scroll_rows(if forward { rows } else { -rows });
     }
 
-    fn key_pagedown(&mut self) {
-        let one_page = self.view_height as u64 * 16;
-        let end_offset = if self.filesize <= one_page {
-            0
+    // scroll the view by a number of rows; the cursor moves along,
+    // keeping its place on screen unless the view hits the start or end
+    fn scroll_rows(&mut self, rows: i64) {
+        if self.squeeze {
+            // the cursor steps over collapsed runs instead
+            if rows < 0 {
+                self.squeeze_up(rows.unsigned_abs());
+            } else {
+                self.squeeze_down(rows as u64);
+            }
+            return;
+        }
+        let pos = self.cursor_pos();
+        let delta = rows.unsigned_abs().saturating_mul(self.row_bytes);
+        let (offset, target) = if rows < 0 {
+            let target = if pos / self.row_bytes * self.row_bytes >= delta {
+                pos - delta
+            } else if pos >= self.row_bytes {
+                // first row of the file, same column
+                pos % self.row_bytes
+            } else {
+                0
+            };
+            (self.offset.saturating_sub(delta), target)
         } else {
-            ((self.filesize + 15) / 16 * 16) - one_page
+            (
+                self.offset.saturating_add(delta).min(self.end_offset()),
+                pos.saturating_add(delta).min(self.filesize - 1),
+            )
         };
-
-        if self.offset + one_page >= end_offset {
-            self.key_end();
+        if target == pos && offset == self.offset {
             return;
         }
 
-        self.offset += one_page;
-        self.update_needed = true;
+        if offset != self.offset {
+            self.offset = offset;
+            self.update_needed = true;
+        } else {
+            self.erase_cursor();
+        }
+        self.cursor_y =
+            ((target / self.row_bytes * self.row_bytes - self.offset) / self.row_bytes) as u16;
+        self.cursor_x = (target % self.row_bytes) as u16;
+
+        if !self.update_needed {
+            self.update_cursor();
+        }
     }
 
     fn key_home(&mut self) {
@@ -827,15 +6552,10 @@ impl HexView {
     }
 
     fn key_end(&mut self) {
-        let one_page = self.view_height as u64 * 16;
-        let end_offset = if self.filesize <= one_page {
-            0
-        } else {
-            ((self.filesize + 15) / 16 * 16) - one_page
-        };
+        let end_offset = self.end_offset();
 
-        let cx = (self.filesize - 1 - end_offset) % 16;
-        let cy = (self.filesize - 1 - end_offset) / 16;
+        let cx = (self.filesize - 1 - end_offset) % self.row_bytes;
+        let cy = (self.filesize - 1 - end_offset) / self.row_bytes;
         assert!(cy < self.view_height as u64);
 
         if self.offset == end_offset && self.cursor_x as u64 == cx && self.cursor_y as u64 == cy {
@@ -857,31 +6577,658 @@ impl HexView {
         }
     }
 
+    fn key_row_start(&mut self) {
+        let pos = self.cursor_pos();
+        self.move_cursor(pos / self.row_bytes * self.row_bytes);
+    }
+
+    fn key_row_end(&mut self) {
+        let pos = self.cursor_pos();
+        self.move_cursor(
+            (pos / self.row_bytes * self.row_bytes + self.row_bytes - 1).min(self.filesize - 1),
+        );
+    }
+
+    // absolute position of the cursor in the file
+    fn cursor_pos(&self) -> u64 {
+        if self.array_view {
+            return self.array_cursor * self.array_type.size() as u64;
+        }
+        self.offset + self.cursor_y as u64 * self.row_bytes + self.cursor_x as u64
+    }
+
+    // offset of the last page, where the last row is at the bottom
+    fn end_offset(&self) -> u64 {
+        let one_page = self.view_height as u64 * self.row_bytes;
+        if self.filesize <= one_page {
+            0
+        } else {
+            (self.filesize.div_ceil(self.row_bytes) * self.row_bytes) - one_page
+        }
+    }
+
+    // move the cursor to absolute position
+    // the row lands at the top of the view, unless it is already visible
+    fn jump_to(&mut self, pos: u64) {
+        let pos = pos.min(self.filesize - 1);
+        if self.array_view {
+            self.array_set_cursor(pos / self.array_type.size() as u64);
+            return;
+        }
+        let row = pos / self.row_bytes * self.row_bytes;
+        let one_page = self.view_height as u64 * self.row_bytes;
+
+        if row < self.offset || row >= self.offset + one_page {
+            self.offset = row.min(self.end_offset());
+            self.update_needed = true;
+        } else if !self.update_needed {
+            self.erase_cursor();
+        }
+        self.cursor_y = ((row - self.offset) / self.row_bytes) as u16;
+        self.cursor_x = (pos % self.row_bytes) as u16;
+
+        if !self.update_needed {
+            self.update_cursor();
+        }
+    }
+
     fn update_cursor(&mut self) {
+        if self.squeeze && self.squeeze_stale() || self.hscroll_to_cursor() {
+            self.update_needed = true;
+            return;
+        }
         self.draw_cursor();
         self.draw_bottom_pane();
+        if self.stride.is_some() {
+            self.draw_statusline();
+        }
         self.stdout.flush().unwrap();
     }
 }
 
+// command-line options
+#[derive(Debug)]
+struct Options {
+    filename: String,
+    strings: bool,
+    min_len: usize,
+    encoding: Encoding,
+    offset: u64,
+    length: Option<u64>,
+    endian: Option<Endiannes>,
+    read_only: bool,
+    patch: Option<String>,
+    cols: Option<u64>,
+    no_color: bool,
+    addr_base: Option<AddrBase>,
+    theme: Option<String>,
+}
+
+fn usage() -> ! {
+    let args: Vec<_> = env::args().collect();
+    let path = Path::new(&args[0]);
+    let basename = path.file_name().unwrap_or(OsStr::new("rhex"));
+    println!("usage: {} [options] FILENAME", basename.to_str().unwrap());
+    println!("options:");
+    println!("  --strings              print printable strings with their offset and exit");
+    println!("  --min-len N            minimum string length (default: 6)");
+    println!("  --encoding ENC         string encoding: ascii or utf16le (default: ascii)");
+    println!("  --offset N             start scanning at offset N");
+    println!("  --length N             scan at most N bytes");
+    println!("  --endian little|big    byte order for the info pane");
+    println!("  -R, --readonly         do not allow changes");
+    println!("  --patch DUMP           apply a hexdump in xxd format to the file and exit");
+    println!("  --cols N               show 8, 16, 24 or 32 bytes per row (default: 16)");
+    println!("  --no-color             do not color bytes by class");
+    println!("  --addr hex|dec|oct     number base of the addresses (default: hex)");
+    println!("  --theme NAME           color theme: default, light, or one from the config");
+    process::exit(1);
+}
+
+// Esc pressed during a long operation
+// other keys are discarded
+fn user_cancelled() -> bool {
+    while crossterm::event::poll(Duration::ZERO).unwrap_or(false) {
+        if let Ok(Event::Key(key_event)) = crossterm::event::read() {
+            if key_event.code == KeyCode::Esc {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn range_error(err: &std::io::Error) -> String {
+    if err.kind() == std::io::ErrorKind::Interrupted {
+        "cancelled".to_owned()
+    } else {
+        format!("error: {}", err)
+    }
+}
+
+// vim-style movement: hjkl are arrow keys, G goes to the end
+fn vim_key(key_event: &KeyEvent) -> KeyEvent {
+    if !key_event
+        .modifiers
+        .difference(KeyModifiers::SHIFT)
+        .is_empty()
+    {
+        return *key_event;
+    }
+    let code = match key_event.code {
+        KeyCode::Char('h') => KeyCode::Left,
+        KeyCode::Char('j') => KeyCode::Down,
+        KeyCode::Char('k') => KeyCode::Up,
+        KeyCode::Char('l') => KeyCode::Right,
+        KeyCode::Char('G') => KeyCode::End,
+        _ => return *key_event,
+    };
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+fn is_movement_key(key_event: &KeyEvent) -> bool {
+    matches!(
+        key_event.code,
+        KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::Home
+            | KeyCode::End
+    )
+}
+
+// expand leading "~/" to the home directory
+fn expand_path(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Ok(home) = env::var("HOME") {
+            return format!("{}/{}", home, rest);
+        }
+    }
+    path.to_owned()
+}
+
+// length of the UTF-8 sequence that starts with lead, or 0 if it
+// is not a lead byte
+fn utf8_len(lead: u8) -> usize {
+    match lead {
+        0x00..=0x7f => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => 0,
+    }
+}
+
+// rough kind of character; the standard library has no tables of the
+// Unicode general categories
+fn char_class(c: char) -> &'static str {
+    if c.is_control() {
+        "control"
+    } else if c.is_whitespace() {
+        "space"
+    } else if c.is_uppercase() {
+        "uppercase letter"
+    } else if c.is_lowercase() {
+        "lowercase letter"
+    } else if c.is_alphabetic() {
+        "letter"
+    } else if c.is_numeric() {
+        "number"
+    } else if c.is_ascii_punctuation() {
+        "punctuation"
+    } else {
+        "symbol or mark"
+    }
+}
+
+// IEEE 754 half precision, which an f32 holds exactly
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (bits >> 10 & 0x1f) as i32;
+    let fraction = (bits & 0x03ff) as f32;
+    match exponent {
+        0 => sign * fraction * 2f32.powi(-24),
+        0x1f if fraction == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + fraction / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+// a value of one of the 16-bit float formats, with special values
+// named
+fn format_small_float(value: f32, subnormal: bool) -> String {
+    if value.is_nan() {
+        "NaN".to_owned()
+    } else if value.is_infinite() {
+        if value < 0.0 { "-inf" } else { "inf" }.to_owned()
+    } else {
+        let text = format!("{:20.20}", PrettyPrintFloat(value as f64));
+        let text = text.trim();
+        if subnormal {
+            format!("{} subnormal", text)
+        } else {
+            text.to_owned()
+        }
+    }
+}
+
+// 16 bytes as "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx"
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let mut text = String::new();
+    for (i, byte) in bytes.iter().enumerate() {
+        if [4, 6, 8, 10].contains(&i) {
+            text.push('-');
+        }
+        write!(text, "{:02x}", byte).unwrap();
+    }
+    text
+}
+
+// LEB128 varint as value and length in bytes
+// Err when it does not end within 10 bytes, or does not fit in 64 bits;
+// None when the bytes run out before it ends
+fn decode_leb128(bytes: &[u8]) -> Option<Result<(u64, usize), ()>> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate() {
+        if i == 9 && byte > 1 {
+            // the tenth byte holds only the top bit
+            return Some(Err(()));
+        }
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some(Ok((value, i + 1)));
+        }
+    }
+    if bytes.len() < 10 {
+        None
+    } else {
+        Some(Err(()))
+    }
+}
+
+// the value of a decoded LEB128 of len bytes, as signed
+fn sign_extend_leb128(value: u64, len: usize) -> i64 {
+    let bits = 7 * len as u32;
+    if bits < 64 && value & (1 << (bits - 1)) != 0 {
+        (value | (!0 << bits)) as i64
+    } else {
+        value as i64
+    }
+}
+
+// first row and number of rows of the scrollbar thumb, for a view of
+// height rows at row top of total rows
+fn scrollbar_thumb(top: u64, total: u64, height: u64) -> (u64, u64) {
+    let total = total.max(1) as u128;
+    let height = height as u128;
+    let len = (height * height / total).clamp(1, height);
+    let start = if top as u128 + height >= total {
+        // at the end, the thumb is at the bottom
+        height - len
+    } else {
+        (top as u128 * height / total).min(height - len)
+    };
+    (start as u64, len as u64)
+}
+
+// size in human-readable units
+fn human_size(size: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    if size < 1024 {
+        return format!("{} bytes", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+// mode bits as in "ls -l"
+#[cfg(unix)]
+fn mode_string(mode: u32) -> String {
+    let file_type = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '-',
+    };
+    let mut text = String::from(file_type);
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 7;
+        text.push(if bits & 4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 2 != 0 { 'w' } else { '-' });
+        text.push(if bits & 1 != 0 { 'x' } else { '-' });
+    }
+    text
+}
+
+// look up user or group name by id in /etc/passwd or /etc/group
+#[cfg(unix)]
+fn unix_name(database: &str, id: u32) -> String {
+    let contents = std::fs::read_to_string(database).unwrap_or_default();
+    for line in contents.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() > 2 && fields[2].parse::<u32>() == Ok(id) {
+            return fields[0].to_owned();
+        }
+    }
+    "?".to_owned()
+}
+
+// the current user may write to the file
+#[cfg(unix)]
+fn is_writable(filename: &str) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    match std::ffi::CString::new(Path::new(filename).as_os_str().as_bytes()) {
+        Ok(path) => unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 },
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_writable(filename: &str) -> bool {
+    std::fs::metadata(filename).is_ok_and(|metadata| !metadata.permissions().readonly())
+}
+
+// parse decimal or 0x-prefixed hexadecimal number
+fn parse_number(s: &str) -> Option<u64> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        s.parse::<u64>().ok()
+    }
+}
+
+fn parse_args() -> Options {
+    let mut options = Options {
+        filename: String::new(),
+        strings: false,
+        min_len: 6,
+        encoding: Encoding::Ascii,
+        offset: 0,
+        length: None,
+        endian: None,
+        read_only: false,
+        patch: None,
+        cols: None,
+        no_color: false,
+        addr_base: None,
+        theme: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strings" => options.strings = true,
+            "--no-color" => options.no_color = true,
+            "-R" | "--readonly" => options.read_only = true,
+            "--min-len" | "--encoding" | "--offset" | "--length" | "--endian" | "--patch"
+            | "--cols" | "--addr" | "--theme" => {
+                let value = args.next().unwrap_or_else(|| {
+                    eprintln!("error: option {} requires an argument", arg);
+                    process::exit(1);
+                });
+                let bad_value = || -> ! {
+                    eprintln!("error: invalid argument for {}: '{}'", arg, value);
+                    process::exit(1);
+                };
+                match arg.as_str() {
+                    "--min-len" => {
+                        options.min_len = match parse_number(&value) {
+                            Some(n) if n > 0 => n as usize,
+                            _ => bad_value(),
+                        }
+                    }
+                    "--encoding" => {
+                        options.encoding =
+                            Encoding::from_name(&value).unwrap_or_else(|| bad_value())
+                    }
+                    "--offset" => {
+                        options.offset = parse_number(&value).unwrap_or_else(|| bad_value())
+                    }
+                    "--length" => {
+                        options.length = Some(parse_number(&value).unwrap_or_else(|| bad_value()))
+                    }
+                    "--patch" => options.patch = Some(value),
+                    "--theme" => options.theme = Some(value),
+                    "--addr" => {
+                        options.addr_base =
+                            Some(AddrBase::from_name(&value).unwrap_or_else(|| bad_value()))
+                    }
+                    "--cols" => {
+                        options.cols = match parse_number(&value) {
+                            Some(n) if [8, 16, 24, 32].contains(&n) => Some(n),
+                            _ => bad_value(),
+                        }
+                    }
+                    "--endian" => {
+                        options.endian = match value.as_str() {
+                            "little" | "le" => Some(LittleEndian),
+                            "big" | "be" => Some(BigEndian),
+                            _ => bad_value(),
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            "-h" | "--help" => usage(),
+            _ => {
+                if arg.starts_with('-') && arg.len() > 1 {
+                    eprintln!("error: unknown option '{}'", arg);
+                    process::exit(1);
+                }
+                if !options.filename.is_empty() {
+                    usage();
+                }
+                options.filename = arg;
+            }
+        }
+    }
+
+    if options.filename.is_empty() {
+        usage();
+    }
+    options
+}
+
+// write the data to a new file next to path, that then takes its
+// place; a failed write leaves what was at path as it was
+fn write_replace(edits: &Edits, fd: &File, path: &Path) -> std::io::Result<()> {
+    let mut tmpname = path.as_os_str().to_owned();
+    tmpname.push(".rhex~");
+    let result =
+        write_copy(edits, fd, Path::new(&tmpname)).and_then(|_| std::fs::rename(&tmpname, path));
+    if result.is_err() {
+        _ = std::fs::remove_file(&tmpname);
+    }
+    result
+}
+
+fn write_copy(edits: &Edits, fd: &File, path: &Path) -> std::io::Result<()> {
+    let mut out = File::create(path)?;
+    out.set_permissions(fd.metadata()?.permissions())?;
+    edits.copy_to(fd, &mut out)?;
+    out.sync_all()
+}
+
+// overwrite the data with the runs of a hexdump; a run may go on past
+// the end, but not start after it
+// Returns the changes, and the number of bytes
+fn patch_edits(edits: &mut Edits, runs: &[xxd::Run]) -> Result<(Vec<Change>, u64), Vec<String>> {
+    let mut len = edits.data_len();
+    let mut errors = Vec::new();
+    for run in runs {
+        if run.addr > len {
+            errors.push(format!(
+                "line {}: offset {:08x} is past the end of the data",
+                run.line, run.addr
+            ));
+        }
+        len = len.max(run.addr + run.bytes.len() as u64);
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut changes = Vec::new();
+    let mut count = 0;
+    for run in runs {
+        let overwrite = (run.bytes.len() as u64).min(edits.data_len() - run.addr);
+        changes.push(edits.splice(run.addr, overwrite, &run.bytes));
+        count += run.bytes.len() as u64;
+    }
+    Ok((changes, count))
+}
+
+// rhex --patch DUMP FILE applies the hexdump to the file, and exits
+fn patch_file(options: &Options, dump: &str) {
+    let filename = &options.filename;
+    let exit_with = |errors: Vec<String>| -> ! {
+        for err in errors {
+            eprintln!("error: {}: {}", dump, err);
+        }
+        process::exit(1);
+    };
+    let text = std::fs::read_to_string(dump).unwrap_or_else(|err| exit_with(vec![err.to_string()]));
+    let runs = xxd::parse(&text).unwrap_or_else(|errors| exit_with(errors));
+
+    let fd = File::open(filename).unwrap_or_else(|err| {
+        eprintln!("error: failed to open '{}': {}", filename, err);
+        process::exit(1);
+    });
+    let len = fd.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    let mut edits = Edits::new(len);
+    let (_, count) = patch_edits(&mut edits, &runs).unwrap_or_else(|errors| exit_with(errors));
+
+    let result = if edits.is_in_place() {
+        OpenOptions::new()
+            .write(true)
+            .open(filename)
+            .and_then(|mut out| edits.write(&mut out))
+    } else {
+        std::fs::canonicalize(filename).and_then(|path| write_replace(&edits, &fd, &path))
+    };
+    if let Err(err) = result {
+        eprintln!("error: failed to write '{}': {}", filename, err);
+        process::exit(1);
+    }
+    println!("patched {} bytes in {}", count, filename);
+}
+
+// non-interactive mode: print each string found, prefixed by its offset
+fn print_strings(options: &Options) {
+    let filename = &options.filename;
+    let mut file = File::open(filename).unwrap_or_else(|err| {
+        eprintln!("error: failed to open '{}': {}", filename, err);
+        process::exit(1);
+    });
+    if let Err(err) = file.seek(std::io::SeekFrom::Start(options.offset)) {
+        eprintln!("error: failed to seek '{}': {}", filename, err);
+        process::exit(1);
+    }
+
+    let mut out = BufWriter::new(stdout().lock());
+    let mut write_ok = true;
+    let mut found = |offset: u64, text: &str| {
+        if write_ok && writeln!(out, "{:08X}  {}", offset, text).is_err() {
+            // most likely a broken pipe; no use printing any more
+            write_ok = false;
+        }
+    };
+
+    let mut scanner = Scanner::new(options.encoding, options.min_len, options.offset);
+    let mut remaining = options.length.unwrap_or(u64::MAX);
+    let mut buf = vec![0u8; READ_CHUNKSIZE];
+    while remaining > 0 {
+        let want = remaining.min(READ_CHUNKSIZE as u64) as usize;
+        let n = match file.read(&mut buf[..want]) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                eprintln!("error: failed to read '{}': {}", filename, err);
+                process::exit(1);
+            }
+        };
+        scanner.feed(&buf[..n], &mut found);
+        remaining -= n as u64;
+    }
+    scanner.finish(&mut found);
+    _ = out.flush();
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_args();
+
+    if options.strings {
+        print_strings(&options);
+        return Ok(());
+    }
+    if let Some(dump) = &options.patch {
+        patch_file(&options, dump);
+        return Ok(());
+    }
+
     if !stdout().is_tty() {
         eprintln!("stdout: not a tty");
         process::exit(1);
     }
 
     let mut hexview = HexView::new();
-
-    let args: Vec<_> = env::args().collect();
-    if args.len() <= 1 {
-        let path = Path::new(&args[0]);
-        let basename = path.file_name().unwrap_or(OsStr::new("rhex"));
-        println!("usage: {} FILENAME", basename.to_str().unwrap());
-        process::exit(1);
+    let config_problems = hexview.load_config();
+    if let Some(endian) = options.endian {
+        hexview.endian = endian;
+        hexview.suggest_endian = false;
+    }
+    // readonly in the config file works like -R
+    hexview.open_read_only = options.read_only || hexview.read_only;
+    hexview.read_only = hexview.open_read_only;
+    if options.no_color {
+        hexview.byte_colors = false;
+    }
+    if Path::new(&options.filename).is_dir() {
+        hexview.open_browser(Path::new(&options.filename));
+    } else {
+        hexview.load(&options.filename);
+    }
+    // opening the file sets its own notes; errors in the config go first
+    match config_problems.len() {
+        0 => {}
+        1 => hexview.message = config_problems.into_iter().next(),
+        n => {
+            hexview.message = Some(format!("{} problems in the config file", n));
+            // the file browser goes first
+            if hexview.overlay.is_none() {
+                hexview.open_info_overlay("config", config_problems);
+            }
+        }
+    }
+    if let Some(cols) = options.cols {
+        if let Err(msg) = hexview.set_row_bytes(cols) {
+            eprintln!("error: {}", msg);
+            process::exit(1);
+        }
+    }
+    if let Some(addr_base) = options.addr_base {
+        hexview.set_addr_base(addr_base);
+    }
+    if let Some(theme) = &options.theme {
+        if let Err(msg) = hexview.set_theme(theme) {
+            eprintln!("error: {}", msg);
+            process::exit(1);
+        }
     }
-
-    let filename = &args[1];
-    hexview.load(filename);
 
     terminal::enable_raw_mode().expect("unable to put terminal in raw mode");
 
@@ -891,22 +7238,36 @@ fn main() -> Result<(), Box<dyn Error>> {
         .queue(Clear(ClearType::All))?
         .queue(cursor::MoveTo(0, 0))?
         .queue(cursor::Hide)?
-        .queue(style::PrintStyledContent("Title".reverse()))?
-        .queue(cursor::MoveTo(0, 1))?
         .flush()?;
 
     loop {
+        hexview.poll_search();
         hexview.draw_screen();
 
+        // keep checking on a search in the background
+        if hexview.search_job.is_some()
+            && !crossterm::event::poll(PROGRESS_INTERVAL).expect("unable to poll terminal")
+        {
+            continue;
+        }
+
+        // in autoreload mode, poll the file every second
+        if hexview.autoreload
+            && !crossterm::event::poll(Duration::from_secs(1)).expect("unable to poll terminal")
+        {
+            hexview.check_reload();
+            continue;
+        }
+
         let event = crossterm::event::read().expect("unable to get terminal event");
         match event {
             Event::Key(key_event) => {
-                if key_event.code == KeyCode::Esc || key_event.code == KeyCode::Char('q') {
+                hexview.key_event(&key_event);
+                if hexview.quit {
                     break;
-                } else {
-                    hexview.key_event(&key_event);
                 }
             }
+            Event::Resize(width, height) => hexview.resize(width, height),
             _ => {}
         }
     }
@@ -0,0 +1,182 @@
+/*
+    overlay.rs  WJ126
+
+    * selectable list drawn on top of the hexdump
+*/
+
+use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::{cursor, style, QueueableCommand};
+use std::io::Stdout;
+
+use crate::theme::Theme;
+
+#[derive(Debug)]
+pub struct OverlayItem {
+    pub text: String,
+    // filtering matches against key
+    pub key: String,
+    // disabled items are shown, but can not be selected
+    pub enabled: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OverlayResult {
+    Continue,
+    Close,
+    // index into items
+    Select(usize),
+}
+
+#[derive(Debug)]
+pub struct Overlay {
+    pub title: String,
+    pub items: Vec<OverlayItem>,
+    pub filterable: bool,
+    pub filter: String,
+    // indices of items that pass the filter
+    visible: Vec<usize>,
+    selected: usize,
+    top: usize,
+}
+
+impl Overlay {
+    pub fn new(title: &str, items: Vec<OverlayItem>, filterable: bool) -> Self {
+        let mut overlay = Overlay {
+            title: title.to_owned(),
+            items,
+            filterable,
+            filter: String::new(),
+            visible: Vec::new(),
+            selected: 0,
+            top: 0,
+        };
+        overlay.apply_filter();
+        overlay
+    }
+
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_owned();
+        self.apply_filter();
+    }
+
+    fn apply_filter(&mut self) {
+        self.visible = (0..self.items.len())
+            .filter(|&i| self.items[i].key.contains(&self.filter))
+            .collect();
+        self.selected = 0;
+        self.top = 0;
+    }
+
+    // height is the number of list lines that fit on screen
+    pub fn key_event(&mut self, key_event: &KeyEvent, height: usize) -> OverlayResult {
+        let height = height.max(1);
+        let last = self.visible.len().saturating_sub(1);
+
+        match key_event.code {
+            KeyCode::Esc => return OverlayResult::Close,
+            KeyCode::Enter => {
+                if let Some(&idx) = self.visible.get(self.selected) {
+                    if self.items[idx].enabled {
+                        return OverlayResult::Select(idx);
+                    }
+                }
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(height),
+            KeyCode::PageDown => self.selected = (self.selected + height).min(last),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Backspace if self.filterable => {
+                self.filter.pop();
+                self.apply_filter();
+            }
+            KeyCode::Char(c) if self.filterable => {
+                self.filter.push(c);
+                self.apply_filter();
+            }
+            KeyCode::Char('q') => return OverlayResult::Close,
+            _ => {}
+        }
+
+        // keep selection in view
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + height {
+            self.top = self.selected + 1 - height;
+        }
+        OverlayResult::Continue
+    }
+
+    // draw in a box of width x height, at screen position (x, y)
+    pub fn draw(
+        &self,
+        stdout: &mut Stdout,
+        theme: &Theme,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+    ) {
+        let width = width as usize;
+        let list_height = (height as usize).saturating_sub(1);
+
+        let title = if self.filterable {
+            format!(
+                " {}  filter: {} ({}/{})",
+                self.title,
+                self.filter,
+                self.visible.len(),
+                self.items.len()
+            )
+        } else {
+            format!(" {}", self.title)
+        };
+        stdout
+            .queue(cursor::MoveTo(x, y))
+            .unwrap()
+            .queue(style::PrintStyledContent(
+                theme.overlay_title.paint(fit(&title, width)),
+            ))
+            .unwrap();
+
+        for line in 0..list_height {
+            stdout
+                .queue(cursor::MoveTo(x, y + 1 + line as u16))
+                .unwrap();
+            let pos = self.top + line;
+            let idx = match self.visible.get(pos) {
+                Some(&idx) => idx,
+                None => {
+                    stdout.queue(style::Print(fit("", width))).unwrap();
+                    continue;
+                }
+            };
+            let item = &self.items[idx];
+            let text = fit(&format!(" {}", item.text), width);
+            if pos == self.selected {
+                stdout
+                    .queue(style::PrintStyledContent(
+                        theme.overlay_selected.paint(text),
+                    ))
+                    .unwrap();
+            } else if !item.enabled {
+                stdout
+                    .queue(style::PrintStyledContent(
+                        theme.overlay_disabled.paint(text),
+                    ))
+                    .unwrap();
+            } else {
+                stdout.queue(style::Print(text)).unwrap();
+            }
+        }
+    }
+}
+
+// pad or cut text to exactly width chars
+fn fit(text: &str, width: usize) -> String {
+    let text: String = text.chars().take(width).collect();
+    format!("{:<width$}", text, width = width)
+}
+
+// EOB
@@ -0,0 +1,110 @@
+/*
+    prompt.rs  WJ126
+
+    * single line input prompt
+*/
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PromptResult {
+    Continue,
+    // tab pressed; caller may complete the input
+    Complete,
+    Cancel,
+    Done(String),
+}
+
+#[derive(Debug)]
+pub struct Prompt {
+    pub label: String,
+    pub input: String,
+    // cursor position in input, in chars
+    pub cursor: usize,
+    // view to go back to, as (offset, cursor position)
+    pub saved: Option<(u64, u64)>,
+    // earlier answers, oldest first; Up and Down recall them
+    history: Vec<String>,
+    history_pos: usize,
+    // what was typed before going through the history
+    draft: String,
+}
+
+impl Prompt {
+    pub fn new(label: &str) -> Self {
+        Prompt {
+            label: label.to_owned(),
+            input: String::new(),
+            cursor: 0,
+            saved: None,
+            history: Vec::new(),
+            history_pos: 0,
+            draft: String::new(),
+        }
+    }
+
+    pub fn key_event(&mut self, key_event: &KeyEvent) -> PromptResult {
+        match key_event.code {
+            KeyCode::Esc => return PromptResult::Cancel,
+            KeyCode::Enter => return PromptResult::Done(self.input.clone()),
+            KeyCode::Tab => return PromptResult::Complete,
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Up => self.recall(-1),
+            KeyCode::Down => self.recall(1),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let idx = self.byte_index(self.cursor);
+                self.input.remove(idx);
+            }
+            KeyCode::Delete if self.cursor < self.len() => {
+                let idx = self.byte_index(self.cursor);
+                self.input.remove(idx);
+            }
+            KeyCode::Char(c) => {
+                let idx = self.byte_index(self.cursor);
+                self.input.insert(idx, c);
+                self.cursor += 1;
+            }
+            _ => {}
+        }
+        PromptResult::Continue
+    }
+
+    // move through the history; past the newest entry is the draft
+    pub fn set_history(&mut self, history: &[String]) {
+        self.history = history.to_vec();
+        self.history_pos = history.len();
+    }
+
+    fn recall(&mut self, step: isize) {
+        let pos = match self.history_pos.checked_add_signed(step) {
+            Some(pos) if pos <= self.history.len() => pos,
+            _ => return,
+        };
+        if self.history_pos == self.history.len() {
+            self.draft = self.input.clone();
+        }
+        self.history_pos = pos;
+        self.input = match self.history.get(pos) {
+            Some(entry) => entry.clone(),
+            None => self.draft.clone(),
+        };
+        self.cursor = self.len();
+    }
+
+    fn len(&self) -> usize {
+        self.input.chars().count()
+    }
+
+    fn byte_index(&self, char_pos: usize) -> usize {
+        self.input
+            .char_indices()
+            .nth(char_pos)
+            .map_or(self.input.len(), |(idx, _)| idx)
+    }
+}
+
+// EOB
@@ -0,0 +1,698 @@
+/*
+    regex.rs  WJ126
+
+    * small regular expressions over bytes, for searching
+*/
+
+// longest match that search finds whole when it crosses a chunk
+// boundary; this bounds the window carried over between chunks
+pub const MAX_MATCH: usize = 1024;
+
+// larger counts in {m,n} are refused, as they blow up the program
+// The program size bounds the work per byte searched
+const MAX_REPEAT: u32 = 1000;
+const MAX_PROGRAM: usize = 10000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ByteSet([u64; 4]);
+
+impl ByteSet {
+    fn new() -> ByteSet {
+        ByteSet([0; 4])
+    }
+
+    fn all() -> ByteSet {
+        ByteSet([u64::MAX; 4])
+    }
+
+    fn byte(b: u8) -> ByteSet {
+        let mut set = ByteSet::new();
+        set.add(b);
+        set
+    }
+
+    fn range(lo: u8, hi: u8) -> ByteSet {
+        let mut set = ByteSet::new();
+        for b in lo..=hi {
+            set.add(b);
+        }
+        set
+    }
+
+    fn add(&mut self, b: u8) {
+        self.0[b as usize / 64] |= 1 << (b % 64);
+    }
+
+    fn contains(&self, b: u8) -> bool {
+        self.0[b as usize / 64] & (1 << (b % 64)) != 0
+    }
+
+    fn union(&mut self, other: &ByteSet) {
+        for (word, other) in self.0.iter_mut().zip(other.0) {
+            *word |= other;
+        }
+    }
+
+    fn negate(&mut self) {
+        for word in self.0.iter_mut() {
+            *word = !*word;
+        }
+    }
+
+    // add the other case of ASCII letters
+    fn fold_case(&mut self) {
+        for b in b'A'..=b'Z' {
+            if self.contains(b) || self.contains(b.to_ascii_lowercase()) {
+                self.add(b);
+                self.add(b.to_ascii_lowercase());
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Set(ByteSet),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    },
+}
+
+// recursive descent:
+//   alt    = concat { "|" concat }
+//   concat = { repeat }
+//   repeat = atom { ("*" | "+" | "?" | "{m,n}") ["?"] }
+//   atom   = "(" alt ")" | "[" class "]" | "." | "\" escape | char
+struct Parser {
+    chars: Vec<char>,
+    idx: usize,
+    nocase: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.idx += 1;
+        }
+        c
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.idx += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alt(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.concat()?];
+        while self.eat('|') {
+            branches.push(self.concat()?);
+        }
+        if branches.len() == 1 {
+            Ok(branches.pop().unwrap())
+        } else {
+            Ok(Node::Alt(branches))
+        }
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            nodes.push(self.repeat()?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn repeat(&mut self) -> Result<Node, String> {
+        let mut node = self.atom()?;
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => {
+                    self.next();
+                    let counts = self.counts()?;
+                    self.idx -= 1;
+                    counts
+                }
+                _ => break,
+            };
+            self.next();
+            let greedy = !self.eat('?');
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+                greedy,
+            };
+        }
+        Ok(node)
+    }
+
+    // {m}, {m,} or {m,n}, after the opening brace
+    fn counts(&mut self) -> Result<(u32, Option<u32>), String> {
+        let min = self.number()?.ok_or("expected a number after '{'")?;
+        let max = if self.eat(',') {
+            self.number()?
+        } else {
+            Some(min)
+        };
+        if !self.eat('}') {
+            return Err("missing '}'".to_owned());
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err(format!("invalid repeat {{{},{}}}", min, max.unwrap()));
+        }
+        Ok((min, max))
+    }
+
+    fn number(&mut self) -> Result<Option<u32>, String> {
+        let start = self.idx;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.idx += 1;
+        }
+        if start == self.idx {
+            return Ok(None);
+        }
+        let digits: String = self.chars[start..self.idx].iter().collect();
+        match digits.parse::<u32>() {
+            Ok(value) if value <= MAX_REPEAT => Ok(Some(value)),
+            _ => Err(format!("repeat count over {}", MAX_REPEAT)),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = match self.next() {
+            Some(c) => c,
+            None => return Err("unexpected end of regex".to_owned()),
+        };
+        let node = match c {
+            '(' => {
+                let node = self.alt()?;
+                if !self.eat(')') {
+                    return Err("missing ')'".to_owned());
+                }
+                return Ok(node);
+            }
+            '[' => Node::Set(self.class()?),
+            '.' => Node::Set(ByteSet::all()),
+            '\\' => Node::Set(self.escape()?),
+            '*' | '+' | '?' | '{' => return Err(format!("nothing to repeat before '{}'", c)),
+            '^' | '$' => return Err(format!("anchor '{}' is not supported", c)),
+            _ => return Ok(self.literal(c)),
+        };
+        Ok(self.fold(node))
+    }
+
+    // a character may be several bytes in UTF-8
+    fn literal(&self, c: char) -> Node {
+        let mut buf = [0u8; 4];
+        let bytes = c.encode_utf8(&mut buf).as_bytes();
+        if bytes.len() == 1 {
+            return self.fold(Node::Set(ByteSet::byte(bytes[0])));
+        }
+        Node::Concat(bytes.iter().map(|&b| Node::Set(ByteSet::byte(b))).collect())
+    }
+
+    fn fold(&self, node: Node) -> Node {
+        match node {
+            Node::Set(mut set) if self.nocase => {
+                set.fold_case();
+                Node::Set(set)
+            }
+            _ => node,
+        }
+    }
+
+    // after the backslash
+    fn escape(&mut self) -> Result<ByteSet, String> {
+        let c = match self.next() {
+            Some(c) => c,
+            None => return Err("trailing backslash".to_owned()),
+        };
+        let mut set = match c {
+            'x' => {
+                let hex: String = (0..2).filter_map(|_| self.next()).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) if hex.len() == 2 => ByteSet::byte(byte),
+                    _ => return Err(format!("invalid escape \\x{}", hex)),
+                }
+            }
+            'n' => ByteSet::byte(b'\n'),
+            'r' => ByteSet::byte(b'\r'),
+            't' => ByteSet::byte(b'\t'),
+            '0' => ByteSet::byte(0),
+            'd' | 'D' => ByteSet::range(b'0', b'9'),
+            'w' | 'W' => {
+                let mut set = ByteSet::range(b'0', b'9');
+                set.union(&ByteSet::range(b'A', b'Z'));
+                set.union(&ByteSet::range(b'a', b'z'));
+                set.add(b'_');
+                set
+            }
+            's' | 'S' => {
+                let mut set = ByteSet::range(b'\t', b'\r');
+                set.add(b' ');
+                set
+            }
+            _ if c.is_ascii_punctuation() => ByteSet::byte(c as u8),
+            _ => return Err(format!("invalid escape \\{}", c)),
+        };
+        if matches!(c, 'D' | 'W' | 'S') {
+            set.negate();
+        }
+        Ok(set)
+    }
+
+    // after the opening bracket
+    fn class(&mut self) -> Result<ByteSet, String> {
+        let negated = self.eat('^');
+        let mut set = ByteSet::new();
+        let mut first = true;
+        loop {
+            let c = match self.next() {
+                Some(c) => c,
+                None => return Err("missing ']'".to_owned()),
+            };
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = match c {
+                '\\' => {
+                    let escaped = self.escape()?;
+                    match single(&escaped) {
+                        Some(b) => b,
+                        None => {
+                            set.union(&escaped);
+                            continue;
+                        }
+                    }
+                }
+                _ => class_byte(c)?,
+            };
+            if self.peek() == Some('-') && self.chars.get(self.idx + 1) != Some(&']') {
+                self.next();
+                let hi = match self.next() {
+                    Some('\\') => single(&self.escape()?).ok_or("invalid range in class")?,
+                    Some(c) => class_byte(c)?,
+                    None => return Err("missing ']'".to_owned()),
+                };
+                if hi < lo {
+                    return Err("invalid range in class".to_owned());
+                }
+                set.union(&ByteSet::range(lo, hi));
+            } else {
+                set.add(lo);
+            }
+        }
+        if self.nocase {
+            set.fold_case();
+        }
+        if negated {
+            set.negate();
+        }
+        Ok(set)
+    }
+}
+
+fn class_byte(c: char) -> Result<u8, String> {
+    if c.is_ascii() {
+        Ok(c as u8)
+    } else {
+        Err(format!("non-ASCII '{}' in class, use \\xHH", c))
+    }
+}
+
+// the byte, if the set holds only one
+fn single(set: &ByteSet) -> Option<u8> {
+    let mut bytes = (0..=255).filter(|&b| set.contains(b));
+    match (bytes.next(), bytes.next()) {
+        (Some(b), None) => Some(b),
+        _ => None,
+    }
+}
+
+// instructions for a Pike VM
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+    Byte(ByteSet),
+    Split(usize, usize), // the first one has priority
+    Jump(usize),
+    Match,
+}
+
+fn compile(node: &Node, prog: &mut Vec<Inst>) -> Result<(), String> {
+    if prog.len() > MAX_PROGRAM {
+        return Err("regex is too large".to_owned());
+    }
+    match node {
+        Node::Set(set) => prog.push(Inst::Byte(*set)),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, prog)?;
+            }
+        }
+        Node::Alt(branches) => {
+            let mut jumps = Vec::new();
+            for (i, branch) in branches.iter().enumerate() {
+                if i + 1 < branches.len() {
+                    let split = prog.len();
+                    prog.push(Inst::Split(split + 1, 0));
+                    compile(branch, prog)?;
+                    jumps.push(prog.len());
+                    prog.push(Inst::Jump(0));
+                    prog[split] = Inst::Split(split + 1, prog.len());
+                } else {
+                    compile(branch, prog)?;
+                }
+            }
+            let end = prog.len();
+            for jump in jumps {
+                prog[jump] = Inst::Jump(end);
+            }
+        }
+        Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } => {
+            for _ in 0..*min {
+                compile(node, prog)?;
+            }
+            let split = |body: usize, out: usize| {
+                if *greedy {
+                    Inst::Split(body, out)
+                } else {
+                    Inst::Split(out, body)
+                }
+            };
+            match max {
+                None => {
+                    let start = prog.len();
+                    prog.push(Inst::Jump(0));
+                    compile(node, prog)?;
+                    prog.push(Inst::Jump(start));
+                    prog[start] = split(start + 1, prog.len());
+                }
+                Some(max) => {
+                    // optional copies all skip to the end
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(prog.len());
+                        prog.push(Inst::Jump(0));
+                        compile(node, prog)?;
+                    }
+                    let end = prog.len();
+                    for at in splits {
+                        prog[at] = split(at + 1, end);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// the set of threads, in priority order, with the offset at which
+// each one started
+struct Threads {
+    dense: Vec<usize>,
+    sparse: Vec<usize>,
+    starts: Vec<usize>,
+}
+
+impl Threads {
+    fn new(size: usize) -> Threads {
+        Threads {
+            dense: Vec::with_capacity(size),
+            sparse: vec![0; size],
+            starts: vec![0; size],
+        }
+    }
+
+    fn contains(&self, pc: usize) -> bool {
+        let i = self.sparse[pc];
+        i < self.dense.len() && self.dense[i] == pc
+    }
+
+    fn insert(&mut self, pc: usize, start: usize) {
+        self.sparse[pc] = self.dense.len();
+        self.dense.push(pc);
+        self.starts[pc] = start;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regex {
+    prog: Vec<Inst>,
+}
+
+impl Regex {
+    // ASCII letters match regardless of case when nocase is set
+    pub fn new(pattern: &str, nocase: bool) -> Result<Regex, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            idx: 0,
+            nocase,
+        };
+        let node = parser.alt()?;
+        if parser.peek().is_some() {
+            return Err("unmatched ')'".to_owned());
+        }
+        let mut prog = Vec::new();
+        compile(&node, &mut prog)?;
+        prog.push(Inst::Match);
+        Ok(Regex { prog })
+    }
+
+    // follow jumps and splits from pc, in priority order
+    fn add(&self, threads: &mut Threads, pc: usize, start: usize, stack: &mut Vec<usize>) {
+        stack.push(pc);
+        while let Some(pc) = stack.pop() {
+            if threads.contains(pc) {
+                continue;
+            }
+            threads.insert(pc, start);
+            match self.prog[pc] {
+                Inst::Jump(to) => stack.push(to),
+                Inst::Split(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // length of the match at the start of data, if any
+    pub fn match_len(&self, data: &[u8]) -> Option<usize> {
+        self.find(data, 0, 1).map(|(_, len)| len)
+    }
+
+    // the leftmost match that starts in data[from..last], as
+    // (index, length); a single pass, that starts a thread at each
+    // position until something matches
+    // Like Perl, of the matches at that index the first alternative wins
+    // A match may run on to the end of data
+    pub fn find(&self, data: &[u8], from: usize, last: usize) -> Option<(usize, usize)> {
+        let size = self.prog.len();
+        let mut current = Threads::new(size);
+        let mut next = Threads::new(size);
+        let mut stack = Vec::new();
+        let mut matched = None;
+
+        for pos in from..=data.len() {
+            // threads started earlier have priority
+            if matched.is_none() && pos < last {
+                self.add(&mut current, 0, pos, &mut stack);
+            }
+            if current.dense.is_empty() {
+                break;
+            }
+            let byte = data.get(pos).copied();
+            for &pc in current.dense.iter() {
+                let start = current.starts[pc];
+                match &self.prog[pc] {
+                    Inst::Byte(set) if byte.is_some_and(|b| set.contains(b)) => {
+                        self.add(&mut next, pc + 1, start, &mut stack);
+                    }
+                    Inst::Match => {
+                        // threads after this one have lower priority
+                        matched = Some((start, pos - start));
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.dense.clear();
+        }
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(pattern: &str, data: &[u8]) -> Option<usize> {
+        Regex::new(pattern, false).unwrap().match_len(data)
+    }
+
+    fn error(pattern: &str) -> String {
+        Regex::new(pattern, false).unwrap_err()
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(find("abc", b"abcd"), Some(3));
+        assert_eq!(find("abc", b"xabc"), None);
+        assert_eq!(find("abc", b"ab"), None);
+        assert_eq!(find("a.c", b"a\x00c"), Some(3));
+        // a non-ASCII character is its UTF-8 bytes
+        assert_eq!(find("é", "é".as_bytes()), Some(2));
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(find("[a-c]+", b"abcd"), Some(3));
+        assert_eq!(find("[^a-c]+", b"xyza"), Some(3));
+        assert_eq!(find("[]a]+", b"]a]b"), Some(3));
+        assert_eq!(find("[a-]+", b"-a-b"), Some(3));
+        assert_eq!(find(r"[\x00-\x1f]+", b"\x01\x1f "), Some(2));
+        assert_eq!(find(r"[\d_]+", b"12_3x"), Some(4));
+        assert_eq!(find(r"\d+", b"2024-"), Some(4));
+        assert_eq!(find(r"\D", b"7"), None);
+        assert_eq!(find(r"\w+", b"foo_1 bar"), Some(5));
+        assert_eq!(find(r"\W", b"\xff"), Some(1));
+        assert_eq!(find(r"\s+", b" \t\r\nx"), Some(4));
+        assert_eq!(find(r"\S", b" "), None);
+        assert_eq!(error("[abc"), "missing ']'");
+        assert_eq!(error("[z-a]"), "invalid range in class");
+        assert_eq!(error("[é]"), "non-ASCII 'é' in class, use \\xHH");
+    }
+
+    #[test]
+    fn byte_escapes() {
+        assert_eq!(find(r"\x7fELF", b"\x7fELF\x02"), Some(4));
+        assert_eq!(find(r"\xff\xFE", b"\xff\xfe"), Some(2));
+        assert_eq!(find(r"\0\n\r\t", b"\x00\n\r\t"), Some(4));
+        assert_eq!(find(r"\.\*\\", b".*\\"), Some(3));
+        assert_eq!(error(r"\x4"), "invalid escape \\x4");
+        assert_eq!(error(r"\xzz"), "invalid escape \\xzz");
+        assert_eq!(error(r"\q"), "invalid escape \\q");
+        assert_eq!(error("ab\\"), "trailing backslash");
+    }
+
+    #[test]
+    fn anchors() {
+        // a match is tried at every offset, so anchors mean nothing
+        assert_eq!(error("^abc"), "anchor '^' is not supported");
+        assert_eq!(error("abc$"), "anchor '$' is not supported");
+        assert_eq!(find(r"\^\$", b"^$"), Some(2));
+        assert_eq!(find("[$^]+", b"$^"), Some(2));
+    }
+
+    #[test]
+    fn alternation() {
+        assert_eq!(find("cat|dog", b"dog"), Some(3));
+        assert_eq!(find("cat|dog", b"cow"), None);
+        // the first alternative that matches wins, not the longest
+        assert_eq!(find("ab|abcd", b"abcd"), Some(2));
+        assert_eq!(find("abcd|ab", b"abcd"), Some(4));
+        assert_eq!(find("x(ab|cd)+y", b"xabcdaby"), Some(8));
+        assert_eq!(find("a(|b)", b"ab"), Some(1));
+        assert_eq!(error("(ab"), "missing ')'");
+        assert_eq!(error("ab)"), "unmatched ')'");
+    }
+
+    #[test]
+    fn quantifiers() {
+        assert_eq!(find("ab*", b"abbbc"), Some(4));
+        assert_eq!(find("ab*", b"ac"), Some(1));
+        assert_eq!(find("ab+", b"ac"), None);
+        assert_eq!(find("ab?c", b"abc"), Some(3));
+        assert_eq!(find("ab?c", b"ac"), Some(2));
+        assert_eq!(find("a{3}", b"aaaa"), Some(3));
+        assert_eq!(find("a{2,}", b"aaaa"), Some(4));
+        assert_eq!(find("a{2,3}", b"aaaa"), Some(3));
+        assert_eq!(find("a{2,3}", b"a"), None);
+        // non-greedy
+        assert_eq!(find("a+?", b"aaa"), Some(1));
+        assert_eq!(find("<.*?>", b"<a><b>"), Some(3));
+        assert_eq!(find("<.*>", b"<a><b>"), Some(6));
+        assert_eq!(find("a{2,}?", b"aaaa"), Some(2));
+        assert_eq!(error("*a"), "nothing to repeat before '*'");
+        assert_eq!(error("a{3,1}"), "invalid repeat {3,1}");
+        assert_eq!(error("a{1001}"), "repeat count over 1000");
+        assert_eq!(error("a{2"), "missing '}'");
+        assert_eq!(error("a{,2}"), "expected a number after '{'");
+    }
+
+    #[test]
+    fn nocase() {
+        let regex = Regex::new("riff[a-c]", true).unwrap();
+        assert_eq!(regex.match_len(b"RiFfB"), Some(5));
+        assert_eq!(find("riff", b"RIFF"), None);
+    }
+
+    #[test]
+    fn unanchored() {
+        let regex = Regex::new("b+|ab", false).unwrap();
+        // leftmost first, then the first alternative
+        assert_eq!(regex.find(b"xxabbb", 0, 6), Some((2, 2)));
+        assert_eq!(regex.find(b"xxabbb", 3, 6), Some((3, 3)));
+        assert_eq!(regex.find(b"xxabbb", 0, 2), None);
+        // a match may run on past last
+        assert_eq!(regex.find(b"xxabbb", 0, 3), Some((2, 2)));
+        assert_eq!(regex.find(b"xxabbb", 6, 6), None);
+
+        let regex = Regex::new(".*X", false).unwrap();
+        let mut data = vec![b'.'; 3000];
+        data[2500] = b'X';
+        assert_eq!(regex.find(&data, 0, data.len()), Some((0, 2501)));
+        assert_eq!(regex.find(&data, 2000, data.len()), Some((2000, 501)));
+        data[2500] = b'.';
+        assert_eq!(regex.find(&data, 0, data.len()), None);
+    }
+
+    #[test]
+    fn catastrophic_patterns() {
+        // these take exponential time in a backtracking matcher
+        let data = vec![b'a'; MAX_MATCH];
+        assert_eq!(find("(a*)*b", &data), None);
+        assert_eq!(find("(a|aa)+b", &data), None);
+        assert_eq!(find("(a|a)*", &data), Some(MAX_MATCH));
+
+        let regex = Regex::new("(a?){1000}a{1000}", false).unwrap();
+        assert_eq!(regex.match_len(&data), Some(MAX_MATCH));
+
+        assert_eq!(error("((a{1000}){1000}){1000}"), "regex is too large");
+
+        // one pass, not one per offset
+        let data = vec![b'.'; 1 << 20];
+        let regex = Regex::new(".*X", false).unwrap();
+        assert_eq!(regex.find(&data, 0, data.len()), None);
+    }
+}
+
+// EOB
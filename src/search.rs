@@ -0,0 +1,847 @@
+/*
+    search.rs  WJ126
+
+    * streaming search for byte patterns
+*/
+
+use crate::edits::Edits;
+use crate::regex::{self, Regex};
+use crate::typed::ElemType;
+use crate::Endiannes::{self, *};
+use crate::{PROGRESS_INTERVAL, READ_CHUNKSIZE};
+use std::fs::File;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+// searching back, a regex looks at this many starting offsets at once
+// Each match in the block costs a pass over it, so keep it small
+const REGEX_BLOCK: usize = 1024;
+
+// part of the file to search, as (start, end, limit), where
+// matches start before limit
+pub type Span = (u64, u64, u64);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pattern {
+    bytes: Vec<u8>,
+    // ASCII letters match regardless of case
+    pub nocase: bool,
+    // text was expanded to UTF-16 in this byte order
+    pub utf16: Option<Endiannes>,
+    // if set, bytes is unused
+    regex: Option<Regex>,
+    // bytes is this number, serialized
+    value: Option<Value>,
+    // if set, bytes is empty
+    float: Option<Float>,
+    // matches start at a multiple of their length
+    aligned: bool,
+    // bits that must match, per byte; empty is all of them
+    mask: Vec<u8>,
+}
+
+// a number typed at the search prompt, like "=8192:u16"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Value {
+    number: i128,
+    elem: ElemType,
+    endian: Endiannes,
+}
+
+impl Value {
+    fn encode(&self) -> Vec<u8> {
+        // two's complement for negative numbers
+        let raw = self.number as u64;
+        let size = self.elem.size();
+        match self.endian {
+            LittleEndian => raw.to_le_bytes()[..size].to_vec(),
+            BigEndian => raw.to_be_bytes()[8 - size..].to_vec(),
+        }
+    }
+}
+
+// a float to look for, like "=3.14159~0.0001"
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Float {
+    target: f64,
+    eps: f64,
+    elem: Option<ElemType>, // None is both f32 and f64
+    endian: Endiannes,
+}
+
+impl Float {
+    // bytes are 4 or 8 long; NaN and infinity never match
+    fn matches(&self, bytes: &[u8]) -> bool {
+        let value = if bytes.len() == 4 {
+            let arr = bytes.try_into().unwrap();
+            (match self.endian {
+                LittleEndian => f32::from_le_bytes(arr),
+                BigEndian => f32::from_be_bytes(arr),
+            }) as f64
+        } else {
+            let arr = bytes.try_into().unwrap();
+            match self.endian {
+                LittleEndian => f64::from_le_bytes(arr),
+                BigEndian => f64::from_be_bytes(arr),
+            }
+        };
+        value.is_finite() && (value - self.target).abs() <= self.eps
+    }
+}
+
+// range of values of an integer type
+fn int_range(elem: ElemType) -> Option<(i128, i128)> {
+    let range = match elem {
+        ElemType::U8 => (0, u8::MAX as i128),
+        ElemType::I8 => (i8::MIN as i128, i8::MAX as i128),
+        ElemType::U16 => (0, u16::MAX as i128),
+        ElemType::I16 => (i16::MIN as i128, i16::MAX as i128),
+        ElemType::U32 => (0, u32::MAX as i128),
+        ElemType::I32 => (i32::MIN as i128, i32::MAX as i128),
+        ElemType::U64 => (0, u64::MAX as i128),
+        ElemType::I64 => (i64::MIN as i128, i64::MAX as i128),
+        ElemType::F32 | ElemType::F64 | ElemType::Bits => return None,
+    };
+    Some(range)
+}
+
+impl Pattern {
+    pub fn new(bytes: &[u8]) -> Pattern {
+        assert!(!bytes.is_empty());
+        Pattern {
+            bytes: bytes.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    // hex digits like "DE AD BE EF" or "deadbeef"; whitespace is ignored
+    // A '?' is a wildcard nibble, so "E8 ?? ?? 4?" matches any byte at
+    // the second and third position
+    pub fn parse_hex(text: &str) -> Result<Pattern, String> {
+        let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if digits.is_empty() {
+            return Err("empty pattern".to_owned());
+        }
+        if let Some(c) = digits.iter().find(|&&c| !c.is_ascii_hexdigit() && c != '?') {
+            return Err(format!("invalid hex digit '{}'", c));
+        }
+        if !digits.len().is_multiple_of(2) {
+            return Err("odd number of hex digits".to_owned());
+        }
+        // a nibble as (value, mask)
+        let nibble = |c: char| match c.to_digit(16) {
+            Some(value) => (value as u8, 0xf),
+            None => (0, 0),
+        };
+        let (bytes, mask): (Vec<u8>, Vec<u8>) = digits
+            .chunks(2)
+            .map(|pair| {
+                let (hi, hi_mask) = nibble(pair[0]);
+                let (lo, lo_mask) = nibble(pair[1]);
+                (hi << 4 | lo, hi_mask << 4 | lo_mask)
+            })
+            .unzip();
+        if mask.iter().all(|&m| m == 0) {
+            return Err("pattern is all wildcards".to_owned());
+        }
+        let mut pattern = Pattern::new(&bytes);
+        if mask.iter().any(|&m| m != 0xff) {
+            pattern.mask = mask;
+        }
+        Ok(pattern)
+    }
+
+    // text with escapes: \xHH, \n, \r, \t, \0, \\ and \"
+    pub fn parse_text(text: &str) -> Result<Pattern, String> {
+        let mut bytes = Vec::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                let mut buf = [0u8; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                continue;
+            }
+            let byte = match chars.next() {
+                Some('x') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    match u8::from_str_radix(&hex, 16) {
+                        Ok(byte) if hex.len() == 2 => byte,
+                        _ => return Err(format!("invalid escape \\x{}", hex)),
+                    }
+                }
+                Some('n') => b'\n',
+                Some('r') => b'\r',
+                Some('t') => b'\t',
+                Some('0') => 0,
+                Some(c @ ('\\' | '"')) => c as u8,
+                Some(c) => return Err(format!("invalid escape \\{}", c)),
+                None => return Err("trailing backslash".to_owned()),
+            };
+            bytes.push(byte);
+        }
+        if bytes.is_empty() {
+            return Err("empty pattern".to_owned());
+        }
+        Ok(Pattern::new(&bytes))
+    }
+
+    // search prompt input: "text" in double quotes, or else hex,
+    // optionally followed by flags: /i for ignoring case, /u16 for
+    // text encoded as UTF-16 in the given byte order, and /r for
+    // text that is a regular expression
+    // "=VALUE" or "=VALUE:TYPE" searches for a number
+    pub fn parse(input: &str, endian: Endiannes) -> Result<Pattern, String> {
+        let input = input.trim();
+        if let Some(value) = input.strip_prefix('=') {
+            return Pattern::parse_value(value, endian);
+        }
+        let (text, flags) = match input.strip_prefix('"') {
+            Some(text) => {
+                let (text, flags) = split_quoted(text);
+                (Some(text), flags)
+            }
+            None => match input.split_once('/') {
+                Some((_, flags)) => (None, flags),
+                None => (None, ""),
+            },
+        };
+        let (mut nocase, mut utf16, mut is_regex) = (false, false, false);
+        for flag in flags.split('/').map(str::trim) {
+            match flag {
+                "" => {}
+                "i" => nocase = true,
+                "u16" => utf16 = true,
+                "r" => is_regex = true,
+                _ => return Err(format!("unknown search flag /{}", flag)),
+            }
+        }
+
+        let text = match text {
+            Some(text) => text,
+            None if utf16 || is_regex => {
+                let flag = if is_regex { "r" } else { "u16" };
+                return Err(format!("/{} only applies to text", flag));
+            }
+            None => {
+                let hex = input.split_once('/').map_or(input, |(hex, _)| hex);
+                let mut pattern = Pattern::parse_hex(hex)?;
+                pattern.nocase = nocase;
+                return Ok(pattern);
+            }
+        };
+        if is_regex {
+            if utf16 {
+                return Err("/r and /u16 do not go together".to_owned());
+            }
+            let regex = Regex::new(text, nocase).map_err(|err| format!("regex: {}", err))?;
+            if regex.match_len(&[]).is_some() {
+                return Err("regex: matches the empty string".to_owned());
+            }
+            let mut pattern = Pattern::new(text.as_bytes());
+            pattern.nocase = nocase;
+            pattern.regex = Some(regex);
+            return Ok(pattern);
+        }
+        let mut pattern = Pattern::parse_text(text)?;
+        pattern.nocase = nocase;
+        if utf16 {
+            pattern.encode_utf16(endian)?;
+        }
+        Ok(pattern)
+    }
+
+    // decimal or 0x hex, optionally negative, and an integer type like
+    // "u32"; without a type, the smallest one that holds the number
+    // Floats look like "3.14159", "1e-3:f32" or "2.5~0.01", where
+    // ~ gives the tolerance
+    // The /aligned flag only finds values at a multiple of their size
+    pub fn parse_value(text: &str, endian: Endiannes) -> Result<Pattern, String> {
+        let (text, flags) = text.split_once('/').unwrap_or((text, ""));
+        let mut aligned = false;
+        for flag in flags.split('/').map(str::trim) {
+            match flag {
+                "" => {}
+                "aligned" => aligned = true,
+                _ => return Err(format!("unknown search flag /{}", flag)),
+            }
+        }
+        let (text, eps) = match text.split_once('~') {
+            Some((text, eps)) => (text, Some(eps.trim())),
+            None => (text, None),
+        };
+        let (number, elem) = match text.split_once(':') {
+            Some((number, elem)) => (number.trim(), Some(elem.trim())),
+            None => (text.trim(), None),
+        };
+        let unsigned = number.trim_start_matches('-');
+        let is_hex = unsigned.starts_with("0x") || unsigned.starts_with("0X");
+        if eps.is_some()
+            || matches!(elem, Some("f32" | "f64"))
+            || (!is_hex && number.contains(['.', 'e', 'E']))
+        {
+            let mut pattern = Pattern::parse_float(number, elem, eps, endian)?;
+            pattern.aligned = aligned;
+            return Ok(pattern);
+        }
+        let (negative, digits) = match number.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, number),
+        };
+        let magnitude = match digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => digits.parse::<u64>(),
+        }
+        .map_err(|_| format!("invalid number '{}'", number))?;
+        let number = if negative {
+            -(magnitude as i128)
+        } else {
+            magnitude as i128
+        };
+
+        let fits = |elem: ElemType| {
+            int_range(elem).is_some_and(|(min, max)| (min..=max).contains(&number))
+        };
+        let elem = match elem {
+            Some(name) => {
+                let elem = ElemType::from_name(name)
+                    .filter(|&elem| int_range(elem).is_some())
+                    .ok_or_else(|| format!("invalid integer type '{}'", name))?;
+                if !fits(elem) {
+                    return Err(format!("{} does not fit in {}", number, name));
+                }
+                elem
+            }
+            None => {
+                let candidates = if negative {
+                    [ElemType::I8, ElemType::I16, ElemType::I32, ElemType::I64]
+                } else {
+                    [ElemType::U8, ElemType::U16, ElemType::U32, ElemType::U64]
+                };
+                match candidates.into_iter().find(|&elem| fits(elem)) {
+                    Some(elem) => elem,
+                    None => return Err(format!("{} does not fit in 64 bits", number)),
+                }
+            }
+        };
+        let value = Value {
+            number,
+            elem,
+            endian,
+        };
+        let mut pattern = Pattern::new(&value.encode());
+        pattern.value = Some(value);
+        pattern.aligned = aligned;
+        Ok(pattern)
+    }
+
+    // the default tolerance is relative, 1e-6
+    fn parse_float(
+        number: &str,
+        elem: Option<&str>,
+        eps: Option<&str>,
+        endian: Endiannes,
+    ) -> Result<Pattern, String> {
+        let target = match number.parse::<f64>() {
+            Ok(target) if target.is_finite() => target,
+            _ => return Err(format!("invalid number '{}'", number)),
+        };
+        let eps = match eps {
+            Some(eps) => match eps.parse::<f64>() {
+                Ok(eps) if eps.is_finite() && eps >= 0.0 => eps,
+                _ => return Err(format!("invalid tolerance '{}'", eps)),
+            },
+            None => 1e-6 * target.abs().max(1.0),
+        };
+        let elem = match elem {
+            None => None,
+            Some("f32") => Some(ElemType::F32),
+            Some("f64") => Some(ElemType::F64),
+            Some(name) => return Err(format!("invalid float type '{}'", name)),
+        };
+        Ok(Pattern {
+            float: Some(Float {
+                target,
+                eps,
+                elem,
+                endian,
+            }),
+            ..Default::default()
+        })
+    }
+
+    // numbers are serialized again in the new byte order
+    pub fn set_endian(&mut self, endian: Endiannes) {
+        if let Some(value) = self.value.as_mut() {
+            value.endian = endian;
+            self.bytes = value.encode();
+        }
+        if let Some(float) = self.float.as_mut() {
+            float.endian = endian;
+        }
+    }
+
+    fn encode_utf16(&mut self, endian: Endiannes) -> Result<(), String> {
+        let text = std::str::from_utf8(&self.bytes)
+            .map_err(|_| "/u16 needs text that is valid UTF-8".to_owned())?;
+        self.bytes = text
+            .encode_utf16()
+            .flat_map(|unit| match endian {
+                LittleEndian => unit.to_le_bytes(),
+                BigEndian => unit.to_be_bytes(),
+            })
+            .collect();
+        self.utf16 = Some(endian);
+        Ok(())
+    }
+
+    // name of the encoding, if not plain bytes
+    pub fn encoding(&self) -> Option<String> {
+        if let Some(value) = &self.value {
+            return Some(format!(
+                "{} {}",
+                value.elem.name(),
+                order_name(value.endian)
+            ));
+        }
+        if let Some(float) = &self.float {
+            let name = float.elem.map_or("float", |elem| elem.name());
+            return Some(format!(
+                "{} {} ~{}",
+                name,
+                order_name(float.endian),
+                float.eps
+            ));
+        }
+        match self.utf16 {
+            Some(LittleEndian) => Some("UTF-16LE".to_owned()),
+            Some(BigEndian) => Some("UTF-16BE".to_owned()),
+            None => None,
+        }
+    }
+
+    // like encoding(), but floats tell which type matched
+    pub fn match_encoding(&self, len: usize) -> Option<String> {
+        match &self.float {
+            Some(float) => {
+                let name = if len == 4 { "f32" } else { "f64" };
+                Some(format!("{} {}", name, order_name(float.endian)))
+            }
+            None => self.encoding(),
+        }
+    }
+
+    pub fn is_regex(&self) -> bool {
+        self.regex.is_some()
+    }
+
+    // the longest a match can be
+    pub fn max_len(&self) -> usize {
+        if let Some(float) = &self.float {
+            return float.elem.map_or(8, |elem| elem.size());
+        }
+        match self.regex {
+            Some(_) => regex::MAX_MATCH,
+            None => self.bytes.len(),
+        }
+    }
+
+    // matches in data as (offset, length), overlapping ones too
+    // data is at offset pos in the file
+    pub fn find_all<'a>(
+        &'a self,
+        pos: u64,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let mut from = 0;
+        std::iter::from_fn(move || {
+            let found = self.find(pos, data, from, data.len())?;
+            from = found.0 + 1;
+            Some(found)
+        })
+    }
+
+    // the first match that starts in data[from..last], as (index, length)
+    // data is at offset pos in the file
+    fn find(&self, pos: u64, data: &[u8], from: usize, last: usize) -> Option<(usize, usize)> {
+        if let Some(regex) = &self.regex {
+            // a single pass, rather than a match at every offset
+            return regex.find(data, from, last);
+        }
+        (from..last).find_map(|i| {
+            self.match_at(pos + i as u64, &data[i..])
+                .map(|len| (i, len))
+        })
+    }
+
+    // the last match that starts in data[..last], as (index, length)
+    fn find_last(&self, pos: u64, data: &[u8], last: usize) -> Option<(usize, usize)> {
+        if self.regex.is_none() {
+            return (0..last).rev().find_map(|i| {
+                self.match_at(pos + i as u64, &data[i..])
+                    .map(|len| (i, len))
+            });
+        }
+        // go back a block at a time, and take the last match in the
+        // first block that has one
+        let mut end = last;
+        while end > 0 {
+            let start = end.saturating_sub(REGEX_BLOCK);
+            let block = &data[..data.len().min(end + regex::MAX_MATCH)];
+            let mut found = None;
+            let mut from = start;
+            while let Some((i, len)) = self.find(pos, block, from, end) {
+                found = Some((i, len));
+                from = i + 1;
+            }
+            if found.is_some() {
+                return found;
+            }
+            end = start;
+        }
+        None
+    }
+
+    // length of the match at file offset pos, where data starts
+    fn match_at(&self, pos: u64, data: &[u8]) -> Option<usize> {
+        let aligned = |len: usize| !self.aligned || pos.is_multiple_of(len as u64);
+        if let Some(float) = &self.float {
+            return [ElemType::F32, ElemType::F64]
+                .into_iter()
+                .filter(|&elem| float.elem.is_none_or(|only| only == elem))
+                .map(|elem| elem.size())
+                .find(|&len| aligned(len) && data.len() >= len && float.matches(&data[..len]));
+        }
+        let len = self.match_len(data)?;
+        aligned(len).then_some(len)
+    }
+
+    // length of the match at the start of data, if any
+    fn match_len(&self, data: &[u8]) -> Option<usize> {
+        let n = self.bytes.len();
+        let found = match &self.regex {
+            Some(regex) => return regex.match_len(data),
+            None if !self.mask.is_empty() => {
+                data.len() >= n
+                    && (0..n).all(|i| {
+                        let (b, m) = (data[i], self.mask[i]);
+                        b & m == self.bytes[i]
+                            || (self.nocase
+                                && b.to_ascii_lowercase() & m
+                                    == self.bytes[i].to_ascii_lowercase() & m)
+                    })
+            }
+            None if self.nocase => data.len() >= n && data[..n].eq_ignore_ascii_case(&self.bytes),
+            None => data.starts_with(&self.bytes),
+        };
+        found.then_some(n)
+    }
+}
+
+fn order_name(endian: Endiannes) -> &'static str {
+    match endian {
+        LittleEndian => "LE",
+        BigEndian => "BE",
+    }
+}
+
+// split text"/flags at the closing quote, skipping escaped quotes
+// Without a closing quote, it is all text
+fn split_quoted(text: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return (&text[..i], &text[i + 1..]),
+            _ => {}
+        }
+    }
+    (text, "")
+}
+
+// Finder is fed consecutive chunks of data, in either direction, and
+// finds matches that straddle chunk boundaries by keeping max_len-1
+// bytes of the previous chunk
+// Matches are (offset, length), and must start before limit
+pub struct Finder<'a> {
+    pattern: &'a Pattern,
+    limit: u64,
+    carry: Vec<u8>,
+    carry_pos: u64,
+}
+
+impl<'a> Finder<'a> {
+    pub fn new(pattern: &'a Pattern, limit: u64) -> Finder<'a> {
+        Finder {
+            pattern,
+            limit,
+            carry: Vec::new(),
+            carry_pos: 0,
+        }
+    }
+
+    // chunks in increasing order; returns the first match
+    // Matches near the end of the chunk are only looked for once the
+    // next chunk is in, or in finish()
+    pub fn feed_forward(&mut self, pos: u64, data: &[u8]) -> Option<(u64, usize)> {
+        let base = pos - self.carry.len() as u64;
+        let mut window = std::mem::take(&mut self.carry);
+        window.extend_from_slice(data);
+
+        let keep = window.len().min(self.pattern.max_len() - 1);
+        let complete = window.len() - keep;
+        if let Some(found) = self.first_match(base, &window, complete) {
+            return Some(found);
+        }
+        self.carry = window[complete..].to_vec();
+        self.carry_pos = base + complete as u64;
+        None
+    }
+
+    // matches in what is left over at the end of a forward search
+    pub fn finish(&mut self) -> Option<(u64, usize)> {
+        let carry = std::mem::take(&mut self.carry);
+        self.first_match(self.carry_pos, &carry, carry.len())
+    }
+
+    fn first_match(&self, base: u64, window: &[u8], starts: usize) -> Option<(u64, usize)> {
+        let before_limit = self.limit.saturating_sub(base);
+        let starts = starts.min(before_limit.try_into().unwrap_or(usize::MAX));
+        self.pattern
+            .find(base, window, 0, starts)
+            .map(|(i, len)| (base + i as u64, len))
+    }
+
+    // chunks in decreasing order; returns the last match
+    pub fn feed_backward(&mut self, pos: u64, data: &[u8]) -> Option<(u64, usize)> {
+        let mut window = data.to_vec();
+        window.extend_from_slice(&self.carry);
+
+        let starts = data.len().min(
+            self.limit
+                .saturating_sub(pos)
+                .try_into()
+                .unwrap_or(usize::MAX),
+        );
+        if let Some((i, len)) = self.pattern.find_last(pos, &window, starts) {
+            return Some((pos + i as u64, len));
+        }
+        window.truncate(self.pattern.max_len() - 1);
+        self.carry = window;
+        None
+    }
+}
+
+// search the span in chunks, that read(pos, buf) fills in; it
+// returns the number of bytes read, or an error to stop the search
+// Returns the match as (position, length)
+pub fn scan(
+    pattern: &Pattern,
+    (start, end, limit): Span,
+    forward: bool,
+    read: &mut dyn FnMut(u64, &mut [u8]) -> io::Result<usize>,
+) -> io::Result<Option<(u64, usize)>> {
+    let mut finder = Finder::new(pattern, limit);
+    let mut buf = vec![0u8; READ_CHUNKSIZE];
+    let mut done = 0;
+    while start + done < end {
+        let want = (end - start - done).min(READ_CHUNKSIZE as u64);
+        let pos = if forward {
+            start + done
+        } else {
+            end - done - want
+        };
+        let n = read(pos, &mut buf[..want as usize])?;
+        if n == 0 {
+            break;
+        }
+        let found = if forward {
+            finder.feed_forward(pos, &buf[..n])
+        } else {
+            finder.feed_backward(pos, &buf[..n])
+        };
+        if found.is_some() {
+            return Ok(found);
+        }
+        done += n as u64;
+    }
+    Ok(if forward { finder.finish() } else { None })
+}
+
+#[derive(Debug)]
+pub enum JobEvent {
+    // bytes searched, out of total
+    Progress(u64, u64),
+    // the match as (position, length, wrapped)
+    Done(io::Result<Option<(u64, u64, bool)>>),
+}
+
+// a search running on a worker thread, with a file handle of its own
+// Dropping the job cancels it
+#[derive(Debug)]
+pub struct Job {
+    pub pattern: Pattern,
+    events: Receiver<JobEvent>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Job {
+    // the spans are searched in order; any but the first one means
+    // the search wrapped around
+    pub fn spawn(
+        file: File,
+        edits: Edits,
+        pattern: Pattern,
+        spans: Vec<Span>,
+        forward: bool,
+    ) -> Job {
+        let (tx, events) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let job = Job {
+            pattern: pattern.clone(),
+            events,
+            cancel: cancel.clone(),
+        };
+        thread::spawn(move || {
+            let total = spans
+                .iter()
+                .map(|&(start, end, _)| end.saturating_sub(start))
+                .sum();
+            let mut done = 0;
+            let mut last_update = Instant::now();
+            let mut read = |pos: u64, buf: &mut [u8]| {
+                if cancel.load(Ordering::Relaxed) {
+                    return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+                }
+                let n = edits.read(&file, pos, buf)?;
+                done += n as u64;
+                if last_update.elapsed() >= PROGRESS_INTERVAL {
+                    last_update = Instant::now();
+                    let _ = tx.send(JobEvent::Progress(done, total));
+                }
+                Ok(n)
+            };
+            let mut result = Ok(None);
+            for (i, &span) in spans.iter().enumerate() {
+                if span.0 >= span.1 {
+                    continue;
+                }
+                match scan(&pattern, span, forward, &mut read) {
+                    Ok(Some((found, len))) => {
+                        result = Ok(Some((found, len as u64, i > 0)));
+                        break;
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        result = Err(err);
+                        break;
+                    }
+                }
+            }
+            let _ = tx.send(JobEvent::Done(result));
+        });
+        job
+    }
+
+    pub fn poll(&self) -> Option<JobEvent> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(JobEvent::Done(Err(io::Error::other(
+                "search thread stopped",
+            )))),
+        }
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // search all of data, in chunks
+    fn search(input: &str, data: &[u8], forward: bool) -> Option<(u64, usize)> {
+        let pattern = Pattern::parse(input, LittleEndian).unwrap();
+        let len = data.len() as u64;
+        let mut read = |pos: u64, buf: &mut [u8]| {
+            let pos = pos as usize;
+            buf.copy_from_slice(&data[pos..pos + buf.len()]);
+            Ok(buf.len())
+        };
+        scan(&pattern, (0, len, len), forward, &mut read).unwrap()
+    }
+
+    #[test]
+    fn across_chunks() {
+        let mut data = vec![b'.'; 3 * READ_CHUNKSIZE];
+        let at = READ_CHUNKSIZE - 3;
+        data[at..at + 6].copy_from_slice(b"needle");
+        data[2 * READ_CHUNKSIZE..2 * READ_CHUNKSIZE + 6].copy_from_slice(b"needle");
+        for input in ["\"needle\"", "\"ne+dle\"/r", "\"n[a-z]{4}e\"/r"] {
+            assert_eq!(
+                search(input, &data, true),
+                Some((at as u64, 6)),
+                "{}",
+                input
+            );
+            assert_eq!(
+                search(input, &data, false),
+                Some((2 * READ_CHUNKSIZE as u64, 6)),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn regex_leftmost() {
+        let mut data = vec![b'.'; 2 * READ_CHUNKSIZE];
+        data[5000] = b'X';
+        data[5010] = b'X';
+        // greedy runs on to the last X; searching back finds the last start
+        assert_eq!(search("\".*X\"/r", &data, true), Some((0, 5011)));
+        assert_eq!(search("\".*X\"/r", &data, false), Some((5010, 1)));
+        assert_eq!(search("\"\\.+X\"/r", &data, true), Some((0, 5001)));
+        assert_eq!(search("\"\\.+X\"/r", &data, false), Some((5009, 2)));
+        assert_eq!(search("\".*Y\"/r", &data, true), None);
+        assert_eq!(search("\".*Y\"/r", &data, false), None);
+    }
+
+    #[test]
+    fn find_all() {
+        let pattern = Pattern::parse("\"a+\"/r", LittleEndian).unwrap();
+        let found: Vec<_> = pattern.find_all(0, b"xaax a").collect();
+        assert_eq!(found, vec![(1, 2), (2, 1), (5, 1)]);
+        let pattern = Pattern::parse("\"aa\"", LittleEndian).unwrap();
+        let found: Vec<_> = pattern.find_all(0, b"aaa").collect();
+        assert_eq!(found, vec![(0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn hex_prefix() {
+        // 1E is not an exponent
+        for input in ["0x1e", "0X1E", "0X1E:u16", "-0X1E", "0XFF/aligned"] {
+            let lower = Pattern::parse_value(&input.to_lowercase(), LittleEndian).unwrap();
+            assert_eq!(
+                Pattern::parse_value(input, LittleEndian),
+                Ok(lower),
+                "{}",
+                input
+            );
+        }
+    }
+}
+
+// EOB
@@ -0,0 +1,237 @@
+/*
+    stats.rs  WJ126
+
+    * statistics over a range of bytes
+*/
+
+// the stride guess looks at the start of the data only
+pub const STRIDE_SAMPLE: usize = 16 * 1024;
+const MAX_STRIDE: usize = 512;
+
+pub struct Stats {
+    pub len: u64,
+    pub set_bits: u64,
+    pub counts: [u64; 256],
+    // longest run: value, length, offset
+    pub longest_run: (u8, u64, u64),
+    run: (u8, u64, u64),
+    sample: Vec<u8>,
+}
+
+impl Stats {
+    pub fn new(start: u64) -> Stats {
+        Stats {
+            len: 0,
+            set_bits: 0,
+            counts: [0; 256],
+            longest_run: (0, 0, start),
+            run: (0, 0, start),
+            sample: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.set_bits += byte.count_ones() as u64;
+            self.counts[byte as usize] += 1;
+
+            let (value, len, offset) = self.run;
+            if len > 0 && byte == value {
+                self.run.1 += 1;
+            } else {
+                self.run = (byte, 1, offset + len);
+            }
+            if self.run.1 > self.longest_run.1 {
+                self.longest_run = self.run;
+            }
+        }
+        self.len += data.len() as u64;
+
+        if self.sample.len() < STRIDE_SAMPLE {
+            let n = (STRIDE_SAMPLE - self.sample.len()).min(data.len());
+            self.sample.extend_from_slice(&data[..n]);
+        }
+    }
+
+    // autocorrelation: for each candidate stride, the fraction of bytes
+    // that equal the byte one stride further
+    // Also returns the minimum score for a stride to be meaningful
+    fn stride_scores(&self) -> Option<(Vec<f64>, f64)> {
+        let data = &self.sample;
+        let max_stride = MAX_STRIDE.min(data.len() / 2);
+        if max_stride < 2 {
+            return None;
+        }
+
+        let score = |stride: usize| {
+            let matches = data
+                .iter()
+                .zip(&data[stride..])
+                .filter(|(a, b)| a == b)
+                .count();
+            matches as f64 / (data.len() - stride) as f64
+        };
+        let scores: Vec<f64> = (0..=max_stride)
+            .map(|stride| if stride == 0 { 0.0 } else { score(stride) })
+            .collect();
+
+        // chance that two random bytes from this data are equal
+        let n = data.len() as f64;
+        let mut counts = [0u64; 256];
+        for &byte in data {
+            counts[byte as usize] += 1;
+        }
+        let baseline: f64 = counts.iter().map(|&c| (c as f64 / n).powi(2)).sum();
+
+        // must stand out from chance and from plain runs of equal bytes
+        let min_score = (2.0 * baseline).max(baseline + 0.2).max(scores[1] + 0.05);
+        Some((scores, min_score))
+    }
+
+    // sum of the byte values
+    pub fn sum(&self) -> u64 {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(value, &count)| value as u64 * count)
+            .sum()
+    }
+
+    // Shannon entropy in bits per byte, 0 to 8
+    // Compressed or encrypted data comes close to 8
+    pub fn entropy(&self) -> f64 {
+        if self.len == 0 {
+            return 0.0;
+        }
+        let n = self.len as f64;
+        self.counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / n;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    // guess the record size by autocorrelation
+    // Multiples of the period score just as well, so take the smallest
+    // stride that comes close to the best score
+    // Returns the stride and the fraction of matching bytes
+    pub fn guess_stride(&self) -> Option<(usize, f64)> {
+        let (scores, min_score) = self.stride_scores()?;
+        let best = scores[2..].iter().copied().fold(0.0, f64::max);
+        let stride = (2..scores.len()).find(|&stride| scores[stride] >= 0.9 * best)?;
+        let found = scores[stride];
+        if found < min_score {
+            return None;
+        }
+        Some((stride, found))
+    }
+
+    // up to count likely record sizes, best first
+    // A stride is left out when a divisor of it scores about as well
+    pub fn stride_candidates(&self, count: usize) -> Vec<(usize, f64)> {
+        let (scores, min_score) = match self.stride_scores() {
+            Some(result) => result,
+            None => return Vec::new(),
+        };
+        let mut candidates: Vec<(usize, f64)> = Vec::new();
+        for (stride, &score) in scores.iter().enumerate().skip(2) {
+            if score < min_score {
+                continue;
+            }
+            let is_multiple = candidates
+                .iter()
+                .any(|&(other, other_score)| stride % other == 0 && other_score >= 0.9 * score);
+            if !is_multiple {
+                candidates.push((stride, score));
+            }
+        }
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates.truncate(count);
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // xorshift, for data without any period
+    fn noise(len: usize, mut state: u64) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    // fixed-size records of mostly constant fields, with a counter
+    // and a few bytes that differ from record to record
+    fn records(size: usize, count: usize) -> Vec<u8> {
+        let template = noise(size, 1);
+        let varying = noise(4 * count, 2);
+        let mut data = Vec::new();
+        for i in 0..count {
+            let mut record = template.clone();
+            record[0] = i as u8;
+            record[size - 4..].copy_from_slice(&varying[i * 4..i * 4 + 4]);
+            data.extend_from_slice(&record);
+        }
+        data
+    }
+
+    fn stats_of(data: &[u8]) -> Stats {
+        let mut stats = Stats::new(0);
+        stats.update(data);
+        stats
+    }
+
+    #[test]
+    fn known_period() {
+        for size in [12, 24, 100] {
+            let stats = stats_of(&records(size, 200));
+            let (stride, _) = stats.guess_stride().unwrap();
+            assert_eq!(stride, size);
+            assert_eq!(stats.stride_candidates(8)[0].0, size);
+        }
+    }
+
+    #[test]
+    fn multiples_left_out() {
+        let stats = stats_of(&records(16, 400));
+        let candidates = stats.stride_candidates(8);
+        assert!(candidates
+            .iter()
+            .all(|&(stride, _)| stride % 16 != 0 || stride == 16));
+    }
+
+    #[test]
+    fn no_period() {
+        let stats = stats_of(&noise(STRIDE_SAMPLE, 0x2545F4914F6CDD1D));
+        assert_eq!(stats.guess_stride(), None);
+        assert!(stats.stride_candidates(8).is_empty());
+
+        // a run of equal bytes matches at every stride
+        let stats = stats_of(&[0u8; 4096]);
+        assert_eq!(stats.guess_stride(), None);
+
+        // too short to say anything
+        assert_eq!(stats_of(b"abc").guess_stride(), None);
+    }
+
+    #[test]
+    fn sample_is_limited() {
+        let mut stats = Stats::new(0);
+        stats.update(&records(24, 1000));
+        stats.update(&noise(STRIDE_SAMPLE, 7));
+        assert_eq!(stats.len, 24 * 1000 + STRIDE_SAMPLE as u64);
+        assert_eq!(stats.guess_stride().unwrap().0, 24);
+    }
+}
+
+// EOB
@@ -0,0 +1,123 @@
+/*
+    strings.rs  WJ126
+
+    * printable string scanner
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Ascii,
+    Utf16le,
+}
+
+impl Encoding {
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name {
+            "ascii" => Some(Encoding::Ascii),
+            "utf16le" | "utf-16le" => Some(Encoding::Utf16le),
+            _ => None,
+        }
+    }
+}
+
+pub fn is_printable(byte: u8) -> bool {
+    (b' '..=b'~').contains(&byte)
+}
+
+// a run of printable characters that is being collected
+#[derive(Debug, Default)]
+struct Run {
+    start: u64,
+    text: String,
+}
+
+// Scanner is fed consecutive chunks of data and reports every run of
+// at least min_len printable characters, with its absolute offset
+// Runs that span chunk boundaries are carried over to the next feed()
+#[derive(Debug)]
+pub struct Scanner {
+    encoding: Encoding,
+    min_len: usize,
+    pos: u64,
+    // utf16le keeps a run for both even and odd alignment
+    runs: [Run; 2],
+    prev_byte: Option<u8>,
+}
+
+impl Scanner {
+    pub fn new(encoding: Encoding, min_len: usize, start: u64) -> Self {
+        Scanner {
+            encoding,
+            min_len: min_len.max(1),
+            pos: start,
+            runs: [Run::default(), Run::default()],
+            prev_byte: None,
+        }
+    }
+
+    pub fn feed<F: FnMut(u64, &str)>(&mut self, data: &[u8], found: &mut F) {
+        for &byte in data {
+            match self.encoding {
+                Encoding::Ascii => self.feed_ascii(byte, found),
+                Encoding::Utf16le => self.feed_utf16le(byte, found),
+            }
+            self.pos += 1;
+        }
+    }
+
+    // flush any pending runs; call this at the end of the data
+    pub fn finish<F: FnMut(u64, &str)>(&mut self, found: &mut F) {
+        // report runs in order of offset
+        let (first, second) = if self.runs[0].start <= self.runs[1].start {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+        self.end_run(first, found);
+        self.end_run(second, found);
+        self.prev_byte = None;
+    }
+
+    fn feed_ascii<F: FnMut(u64, &str)>(&mut self, byte: u8, found: &mut F) {
+        if is_printable(byte) {
+            let run = &mut self.runs[0];
+            if run.text.is_empty() {
+                run.start = self.pos;
+            }
+            run.text.push(byte as char);
+        } else {
+            self.end_run(0, found);
+        }
+    }
+
+    fn feed_utf16le<F: FnMut(u64, &str)>(&mut self, byte: u8, found: &mut F) {
+        let prev = self.prev_byte.replace(byte);
+        let lo = match prev {
+            Some(lo) => lo,
+            None => return,
+        };
+
+        // the code unit (lo, byte) starts at pos - 1
+        let unit_start = self.pos - 1;
+        let idx = (unit_start % 2) as usize;
+        if byte == 0 && is_printable(lo) {
+            let run = &mut self.runs[idx];
+            if run.text.is_empty() {
+                run.start = unit_start;
+            }
+            run.text.push(lo as char);
+        } else {
+            self.end_run(idx, found);
+        }
+    }
+
+    fn end_run<F: FnMut(u64, &str)>(&mut self, idx: usize, found: &mut F) {
+        let run = &mut self.runs[idx];
+        if run.text.len() >= self.min_len {
+            found(run.start, &run.text);
+        }
+        run.text.clear();
+    }
+}
+
+// EOB
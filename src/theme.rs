@@ -0,0 +1,355 @@
+/*
+    theme.rs  WJ126
+
+    * the colors and attributes of the things on screen, by name
+    * a built-in default and a light theme; more can be made in
+      the config file as [theme.NAME] tables
+*/
+
+use crate::config::{Table, Value};
+use crossterm::style::{Color, ContentStyle, StyledContent, Stylize};
+use std::fmt::Display;
+
+// colors and attributes, laid over what is already there
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub reverse: bool,
+    pub underlined: bool,
+    pub dim: bool,
+    pub bold: bool,
+}
+
+impl Style {
+    // a style like "black on yellow" or "bold reverse"
+    // Words may also be separated by commas, as in "black,on,yellow"
+    pub fn parse(spec: &str) -> Result<Style, String> {
+        let mut style = Style::default();
+        let mut words = spec
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty());
+        while let Some(word) = words.next() {
+            match word.to_lowercase().as_str() {
+                "none" => {}
+                "reverse" => style.reverse = true,
+                "underline" | "underlined" => style.underlined = true,
+                "dim" => style.dim = true,
+                "bold" => style.bold = true,
+                "on" => {
+                    let color = words.next().ok_or("missing color after 'on'")?;
+                    style.bg = Some(parse_color(color)?);
+                }
+                _ => style.fg = Some(parse_color(word)?),
+            }
+        }
+        Ok(style)
+    }
+
+    fn fg(color: Color) -> Style {
+        Style {
+            fg: Some(color),
+            ..Default::default()
+        }
+    }
+
+    fn bg(color: Color) -> Style {
+        Style {
+            bg: Some(color),
+            ..Default::default()
+        }
+    }
+
+    fn reverse() -> Style {
+        Style {
+            reverse: true,
+            ..Default::default()
+        }
+    }
+
+    // only the background color of the style
+    pub fn background(&self) -> Style {
+        Style {
+            bg: self.bg,
+            ..Default::default()
+        }
+    }
+
+    pub fn apply<D: Display>(&self, mut content: StyledContent<D>) -> StyledContent<D> {
+        if let Some(fg) = self.fg {
+            content = content.with(fg);
+        }
+        if let Some(bg) = self.bg {
+            content = content.on(bg);
+        }
+        if self.reverse {
+            content = content.reverse();
+        }
+        if self.underlined {
+            content = content.underlined();
+        }
+        if self.dim {
+            content = content.dim();
+        }
+        if self.bold {
+            content = content.bold();
+        }
+        content
+    }
+
+    pub fn paint<D: Display>(&self, content: D) -> StyledContent<D> {
+        self.apply(StyledContent::new(ContentStyle::new(), content))
+    }
+}
+
+fn parse_color(name: &str) -> Result<Color, String> {
+    let color = match name.to_lowercase().replace(['_', '-'], "").as_str() {
+        "default" | "reset" => Color::Reset,
+        "black" => Color::Black,
+        "darkgrey" | "darkgray" => Color::DarkGrey,
+        "red" => Color::Red,
+        "darkred" => Color::DarkRed,
+        "green" => Color::Green,
+        "darkgreen" => Color::DarkGreen,
+        "yellow" => Color::Yellow,
+        "darkyellow" => Color::DarkYellow,
+        "blue" => Color::Blue,
+        "darkblue" => Color::DarkBlue,
+        "magenta" => Color::Magenta,
+        "darkmagenta" => Color::DarkMagenta,
+        "cyan" => Color::Cyan,
+        "darkcyan" => Color::DarkCyan,
+        "white" => Color::White,
+        "grey" | "gray" => Color::Grey,
+        s => {
+            if let Some(hex) = s.strip_prefix('#').filter(|hex| hex.len() == 6) {
+                // #rrggbb
+                let rgb =
+                    u32::from_str_radix(hex, 16).map_err(|_| format!("unknown color: {}", name))?;
+                Color::Rgb {
+                    r: (rgb >> 16) as u8,
+                    g: (rgb >> 8) as u8,
+                    b: rgb as u8,
+                }
+            } else {
+                // one of the 256 terminal colors
+                let value = s
+                    .parse::<u8>()
+                    .map_err(|_| format!("unknown color: {}", name))?;
+                Color::AnsiValue(value)
+            }
+        }
+    };
+    Ok(color)
+}
+
+// cursor styles to cycle through, to find one that shows up well
+pub const CURSOR_STYLES: [&str; 5] = [
+    "reverse",
+    "underline",
+    "bold",
+    "black on yellow",
+    "white on blue",
+];
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub cursor: Style,
+    pub cursor_other: Style, // the cursor in the other pane
+    pub selection: Style,
+    pub search_match: Style,
+    pub visible_match: Style, // other matches on screen
+    pub same_byte: Style,     // bytes equal to the one at the cursor
+    pub modified: Style,
+    pub changed: Style, // since the last reload
+    pub preview: Style,
+    pub crosshair: Style,
+    pub shade: Style, // tinted rows
+    pub dim_char: Style,
+    // byte classes, when colors are on
+    pub zero: Style,
+    pub printable: Style,
+    pub ff: Style,
+    pub other: Style,
+    pub title: Style,
+    pub status_bar: Style,
+    pub ruler: Style,
+    pub scrollbar: Style,
+    pub scrollbar_thumb: Style,
+    pub overlay_title: Style,
+    pub overlay_selected: Style,
+    pub overlay_disabled: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            name: "default".to_owned(),
+            cursor: Style::reverse(),
+            cursor_other: Style {
+                underlined: true,
+                ..Default::default()
+            },
+            selection: Style::bg(Color::DarkGrey),
+            search_match: Style {
+                fg: Some(Color::Black),
+                bg: Some(Color::Yellow),
+                ..Default::default()
+            },
+            visible_match: Style::bg(Color::DarkYellow),
+            same_byte: Style {
+                fg: Some(Color::Black),
+                bg: Some(Color::DarkCyan),
+                ..Default::default()
+            },
+            modified: Style::fg(Color::Red),
+            changed: Style::fg(Color::Yellow),
+            preview: Style::fg(Color::Cyan),
+            crosshair: Style::bg(Color::AnsiValue(236)),
+            shade: Style::bg(Color::AnsiValue(235)),
+            dim_char: Style {
+                dim: true,
+                ..Default::default()
+            },
+            zero: Style::fg(Color::DarkGrey),
+            printable: Style::fg(Color::Green),
+            ff: Style::fg(Color::Blue),
+            other: Style::fg(Color::Magenta),
+            title: Style::reverse(),
+            status_bar: Style::reverse(),
+            ruler: Style {
+                dim: true,
+                ..Default::default()
+            },
+            scrollbar: Style {
+                dim: true,
+                ..Default::default()
+            },
+            scrollbar_thumb: Style::default(),
+            overlay_title: Style::reverse(),
+            overlay_selected: Style::reverse(),
+            overlay_disabled: Style {
+                dim: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Theme {
+    // for terminals with a light background
+    fn light() -> Theme {
+        Theme {
+            name: "light".to_owned(),
+            selection: Style::bg(Color::Grey),
+            visible_match: Style::bg(Color::AnsiValue(229)),
+            same_byte: Style {
+                fg: Some(Color::Black),
+                bg: Some(Color::AnsiValue(152)),
+                ..Default::default()
+            },
+            modified: Style::fg(Color::DarkRed),
+            changed: Style::fg(Color::DarkYellow),
+            preview: Style::fg(Color::DarkCyan),
+            crosshair: Style::bg(Color::AnsiValue(254)),
+            shade: Style::bg(Color::AnsiValue(255)),
+            zero: Style::fg(Color::Grey),
+            printable: Style::fg(Color::DarkGreen),
+            ff: Style::fg(Color::DarkBlue),
+            other: Style::fg(Color::DarkMagenta),
+            ..Default::default()
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Theme::default()),
+            "light" => Some(Theme::light()),
+            _ => None,
+        }
+    }
+
+    // color of a byte by its class
+    pub fn byte_class(&self, byte: u8, printable: bool) -> Style {
+        match byte {
+            0x00 => self.zero,
+            0xff => self.ff,
+            _ if printable => self.printable,
+            _ => self.other,
+        }
+    }
+
+    fn slot_mut(&mut self, name: &str) -> Option<&mut Style> {
+        let slot = match name {
+            "cursor" => &mut self.cursor,
+            "cursor_other" => &mut self.cursor_other,
+            "selection" => &mut self.selection,
+            "match" => &mut self.search_match,
+            "visible_match" => &mut self.visible_match,
+            "same_byte" => &mut self.same_byte,
+            "modified" => &mut self.modified,
+            "changed" => &mut self.changed,
+            "preview" => &mut self.preview,
+            "crosshair" => &mut self.crosshair,
+            "shade" => &mut self.shade,
+            "dim_char" => &mut self.dim_char,
+            "zero" => &mut self.zero,
+            "printable" => &mut self.printable,
+            "ff" => &mut self.ff,
+            "other" => &mut self.other,
+            "title" => &mut self.title,
+            "status_bar" => &mut self.status_bar,
+            "ruler" => &mut self.ruler,
+            "scrollbar" => &mut self.scrollbar,
+            "scrollbar_thumb" => &mut self.scrollbar_thumb,
+            "overlay_title" => &mut self.overlay_title,
+            "overlay_selected" => &mut self.overlay_selected,
+            "overlay_disabled" => &mut self.overlay_disabled,
+            _ => return None,
+        };
+        Some(slot)
+    }
+
+    // a [theme.NAME] table, on top of a built-in theme given as base
+    // Slots that do not parse keep the base style; they come back as
+    // warnings
+    pub fn from_table(name: &str, table: &Table) -> (Theme, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut theme = match table.get("base") {
+            Some(Value::Str(base)) => Theme::builtin(base).unwrap_or_else(|| {
+                warnings.push(format!("unknown base theme: {}", base));
+                Theme::default()
+            }),
+            _ => Theme::default(),
+        };
+        theme.name = name.to_owned();
+
+        for (key, value) in &table.entries {
+            if key == "base" {
+                continue;
+            }
+            let spec = match value {
+                Value::Str(spec) => spec,
+                _ => {
+                    warnings.push(format!("{} must be a string", key));
+                    continue;
+                }
+            };
+            let slot = match theme.slot_mut(key) {
+                Some(slot) => slot,
+                None => {
+                    warnings.push(format!("unknown slot: {}", key));
+                    continue;
+                }
+            };
+            match Style::parse(spec) {
+                Ok(style) => *slot = style,
+                Err(err) => warnings.push(format!("{}: {}", key, err)),
+            }
+        }
+        (theme, warnings)
+    }
+}
+
+// EOB
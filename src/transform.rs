@@ -0,0 +1,107 @@
+/*
+    transform.rs  WJ126
+
+    * simple byte transforms for previewing obfuscated data
+    * XOR takes a repeating key of one or more bytes
+*/
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transform {
+    Xor(Vec<u8>),
+    Rot13,
+    Add(u8),
+    Sub(u8),
+}
+
+impl Transform {
+    // parse "xor 0x5a", "xor deadbeef", "rot13", "add 3", "sub 3"
+    pub fn parse(text: &str) -> Result<Transform, String> {
+        let mut words = text.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name.to_lowercase(),
+            None => return Err("no transform given".to_owned()),
+        };
+
+        if name == "rot13" {
+            if words.next().is_some() {
+                return Err("rot13 takes no parameter".to_owned());
+            }
+            return Ok(Transform::Rot13);
+        }
+
+        if name == "xor" {
+            let key = match words.next() {
+                Some(key) => key,
+                None => return Err("xor needs a key".to_owned()),
+            };
+            if words.next().is_some() {
+                return Err("xor takes a single key".to_owned());
+            }
+            return Ok(Transform::Xor(parse_key(key)?));
+        }
+
+        let make = match name.as_str() {
+            "add" => Transform::Add,
+            "sub" => Transform::Sub,
+            _ => return Err(format!("unknown transform '{}'", name)),
+        };
+        let param = match words.next() {
+            Some(param) => param,
+            None => return Err(format!("{} needs a byte value", name)),
+        };
+        if words.next().is_some() {
+            return Err(format!("{} takes a single byte value", name));
+        }
+        match crate::parse_number(param) {
+            Some(value) if value <= 0xff => Ok(make(value as u8)),
+            _ => Err(format!("invalid byte value '{}'", param)),
+        }
+    }
+
+    // index is the position of the byte from where the key starts
+    pub fn apply(&self, byte: u8, index: u64) -> u8 {
+        match *self {
+            Transform::Xor(ref key) => byte ^ key[(index % key.len() as u64) as usize],
+            Transform::Rot13 => match byte {
+                b'a'..=b'z' => (byte - b'a' + 13) % 26 + b'a',
+                b'A'..=b'Z' => (byte - b'A' + 13) % 26 + b'A',
+                _ => byte,
+            },
+            Transform::Add(value) => byte.wrapping_add(value),
+            Transform::Sub(value) => byte.wrapping_sub(value),
+        }
+    }
+}
+
+// a byte value like "0x5a" or "90", or a longer key in hex like "deadbeef"
+pub fn parse_key(text: &str) -> Result<Vec<u8>, String> {
+    match crate::parse_number(text) {
+        Some(value) if value <= 0xff => Ok(vec![value as u8]),
+        _ => match crate::clipboard::parse_hex(text) {
+            Ok(key) if !key.is_empty() => Ok(key),
+            _ => Err(format!("invalid key '{}'", text)),
+        },
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Transform::Xor(ref key) if key.len() == 1 => write!(f, "XOR 0x{:02X}", key[0]),
+            Transform::Xor(ref key) => {
+                write!(f, "XOR ")?;
+                for byte in key {
+                    write!(f, "{:02X}", byte)?;
+                }
+                Ok(())
+            }
+            Transform::Rot13 => write!(f, "ROT13"),
+            Transform::Add(value) => write!(f, "ADD 0x{:02X}", value),
+            Transform::Sub(value) => write!(f, "SUB 0x{:02X}", value),
+        }
+    }
+}
+
+// EOB
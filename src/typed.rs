@@ -0,0 +1,113 @@
+/*
+    typed.rs  WJ126
+
+    * element types for the typed array view
+    * bits shows each byte as 8 binary digits, for the bit view
+*/
+
+use float_pretty_print::PrettyPrintFloat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElemType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    Bits,
+}
+
+impl ElemType {
+    pub fn from_name(name: &str) -> Option<ElemType> {
+        match name {
+            "u8" => Some(ElemType::U8),
+            "i8" => Some(ElemType::I8),
+            "u16" => Some(ElemType::U16),
+            "i16" => Some(ElemType::I16),
+            "u32" => Some(ElemType::U32),
+            "i32" => Some(ElemType::I32),
+            "u64" => Some(ElemType::U64),
+            "i64" => Some(ElemType::I64),
+            "f32" => Some(ElemType::F32),
+            "f64" => Some(ElemType::F64),
+            "bits" => Some(ElemType::Bits),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ElemType::U8 => "u8",
+            ElemType::I8 => "i8",
+            ElemType::U16 => "u16",
+            ElemType::I16 => "i16",
+            ElemType::U32 => "u32",
+            ElemType::I32 => "i32",
+            ElemType::U64 => "u64",
+            ElemType::I64 => "i64",
+            ElemType::F32 => "f32",
+            ElemType::F64 => "f64",
+            ElemType::Bits => "bits",
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            ElemType::U8 | ElemType::I8 | ElemType::Bits => 1,
+            ElemType::U16 | ElemType::I16 => 2,
+            ElemType::U32 | ElemType::I32 | ElemType::F32 => 4,
+            ElemType::U64 | ElemType::I64 | ElemType::F64 => 8,
+        }
+    }
+
+    // column width needed for the widest value
+    pub fn width(&self) -> usize {
+        match self {
+            ElemType::U8 => 3,
+            ElemType::I8 => 4,
+            ElemType::U16 => 5,
+            ElemType::I16 => 6,
+            ElemType::U32 => 10,
+            ElemType::I32 => 11,
+            ElemType::U64 | ElemType::I64 => 20,
+            ElemType::F32 | ElemType::F64 => 14,
+            ElemType::Bits => 8,
+        }
+    }
+
+    // bytes must be exactly size() long
+    pub fn format(&self, bytes: &[u8], big_endian: bool) -> String {
+        macro_rules! decode {
+            ($t:ty) => {{
+                let arr = bytes.try_into().unwrap();
+                if big_endian {
+                    <$t>::from_be_bytes(arr)
+                } else {
+                    <$t>::from_le_bytes(arr)
+                }
+            }};
+        }
+
+        let width = self.width();
+        match self {
+            ElemType::U8 => format!("{:>width$}", bytes[0]),
+            ElemType::I8 => format!("{:>width$}", bytes[0] as i8),
+            ElemType::U16 => format!("{:>width$}", decode!(u16)),
+            ElemType::I16 => format!("{:>width$}", decode!(i16)),
+            ElemType::U32 => format!("{:>width$}", decode!(u32)),
+            ElemType::I32 => format!("{:>width$}", decode!(i32)),
+            ElemType::U64 => format!("{:>width$}", decode!(u64)),
+            ElemType::I64 => format!("{:>width$}", decode!(i64)),
+            ElemType::F32 => format!("{:>width$.width$}", PrettyPrintFloat(decode!(f32) as f64)),
+            ElemType::F64 => format!("{:>width$.width$}", PrettyPrintFloat(decode!(f64))),
+            ElemType::Bits => format!("{:08b}", bytes[0]),
+        }
+    }
+}
+
+// EOB
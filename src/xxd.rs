@@ -0,0 +1,210 @@
+/*
+    xxd.rs  WJ126
+
+    * hexdump text in the format of "xxd": offset, 16 bytes in groups
+      of two, and the printable characters
+    * and reading it back, like "xxd -r"
+*/
+
+use std::io::{self, Write};
+
+// write data as lines of 16 bytes; addr is the offset of data[0]
+// Data may be given in pieces, as long as only the last one has a
+// length that is not a multiple of 16
+pub fn write_lines<W: Write>(out: &mut W, addr: u64, data: &[u8]) -> io::Result<()> {
+    let mut line = String::with_capacity(80);
+    for (n, chunk) in data.chunks(16).enumerate() {
+        line.clear();
+        line.push_str(&format!("{:08x}: ", addr + n as u64 * 16));
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(byte) => line.push_str(&format!("{:02x}", byte)),
+                None => line.push_str("  "),
+            }
+            if i % 2 == 1 {
+                line.push(' ');
+            }
+        }
+        line.push(' ');
+        for &byte in chunk {
+            line.push(if (0x20..0x7f).contains(&byte) {
+                byte as char
+            } else {
+                '.'
+            });
+        }
+        line.push('\n');
+        out.write_all(line.as_bytes())?;
+    }
+    Ok(())
+}
+
+// bytes at addr, from consecutive lines of a hexdump
+#[derive(Debug)]
+pub struct Run {
+    pub line: usize,
+    pub addr: u64,
+    pub bytes: Vec<u8>,
+}
+
+// parse hexdump text; lines that follow each other make a single run
+// The text after the hex bytes, from two spaces on, is ignored
+// Returns the errors with their line numbers
+pub fn parse(text: &str) -> Result<Vec<Run>, Vec<String>> {
+    let mut runs: Vec<Run> = Vec::new();
+    let mut errors = Vec::new();
+    let mut end = 0;
+    for (n, line) in text.lines().enumerate() {
+        let lineno = n + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (offset, rest) = match line.split_once(':') {
+            Some(parts) => parts,
+            None => {
+                errors.push(format!("line {}: no offset", lineno));
+                continue;
+            }
+        };
+        let addr = match u64::from_str_radix(offset.trim(), 16) {
+            Ok(addr) => addr,
+            Err(_) => {
+                errors.push(format!(
+                    "line {}: malformed offset '{}'",
+                    lineno,
+                    offset.trim()
+                ));
+                continue;
+            }
+        };
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+        let hex: String = match rest.split_once("  ") {
+            Some((hex, _)) => hex,
+            None => rest,
+        }
+        .split(' ')
+        .collect();
+        if hex.is_empty()
+            || !hex.len().is_multiple_of(2)
+            || !hex.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            errors.push(format!("line {}: malformed hex bytes", lineno));
+            continue;
+        }
+        let bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+
+        if !runs.is_empty() && addr < end {
+            errors.push(format!(
+                "line {}: offset {:08x} overlaps the line before",
+                lineno, addr
+            ));
+            continue;
+        }
+        end = addr + bytes.len() as u64;
+        match runs.last_mut() {
+            Some(run) if run.addr + run.bytes.len() as u64 == addr => {
+                run.bytes.extend_from_slice(&bytes)
+            }
+            _ => runs.push(Run {
+                line: lineno,
+                addr,
+                bytes,
+            }),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    if runs.is_empty() {
+        return Err(vec!["no hexdump lines found".to_owned()]);
+    }
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = b"Hello, world!\n\x00\x01\x02\x7f\x80\xff rhex dumps like xxd";
+
+    // output of "xxd FILE", with DATA in FILE
+    const XXD: &str = "\
+00000000: 4865 6c6c 6f2c 2077 6f72 6c64 210a 0001  Hello, world!...
+00000010: 027f 80ff 2072 6865 7820 6475 6d70 7320  .... rhex dumps 
+00000020: 6c69 6b65 2078 7864                      like xxd
+";
+
+    // output of "xxd -o 0x1000 -s 3 FILE"
+    const XXD_OFFSET: &str = "\
+00001003: 6c6f 2c20 776f 726c 6421 0a00 0102 7f80  lo, world!......
+00001013: ff20 7268 6578 2064 756d 7073 206c 696b  . rhex dumps lik
+00001023: 6520 7878 64                             e xxd
+";
+
+    fn dump(addr: u64, data: &[u8]) -> String {
+        let mut out = Vec::new();
+        write_lines(&mut out, addr, data).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn same_as_xxd() {
+        assert_eq!(dump(0, DATA), XXD);
+        assert_eq!(dump(0x1003, &DATA[3..]), XXD_OFFSET);
+
+        // in pieces
+        let mut out = Vec::new();
+        write_lines(&mut out, 0, &DATA[..16]).unwrap();
+        write_lines(&mut out, 16, &DATA[16..]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), XXD);
+    }
+
+    #[test]
+    fn round_trip() {
+        let runs = parse(XXD).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!((runs[0].line, runs[0].addr), (1, 0));
+        assert_eq!(runs[0].bytes, DATA);
+
+        let runs = parse(XXD_OFFSET).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].addr, 0x1003);
+        assert_eq!(runs[0].bytes, &DATA[3..]);
+
+        let runs = parse(&dump(0x40, DATA)).unwrap();
+        assert_eq!(runs[0].bytes, DATA);
+    }
+
+    #[test]
+    fn separate_runs() {
+        let text = "00000000: 4142  AB\n\n00000100: 4344 45  CDE\n";
+        let runs = parse(text).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!((runs[1].line, runs[1].addr), (3, 0x100));
+        assert_eq!(runs[1].bytes, b"CDE");
+    }
+
+    #[test]
+    fn errors() {
+        let text = "zzz: 4142\n00000010: 414\n00000008: 4142\njunk\n";
+        assert_eq!(
+            parse(text).unwrap_err(),
+            vec![
+                "line 1: malformed offset 'zzz'",
+                "line 2: malformed hex bytes",
+                "line 4: no offset",
+            ]
+        );
+        let text = "00000010: 4142\n00000000: 4142\n";
+        assert_eq!(
+            parse(text).unwrap_err(),
+            vec!["line 2: offset 00000000 overlaps the line before"]
+        );
+        assert_eq!(parse("\n").unwrap_err(), vec!["no hexdump lines found"]);
+    }
+}
+
+// EOB